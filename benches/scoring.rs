@@ -0,0 +1,114 @@
+//! Times the scoring hot path over a synthetic tree of file names.
+//!
+//! Run with `cargo bench --bench scoring`. Each line shows how long ranking every candidate took
+//! per candidate, so runs before and after a change can be compared directly.
+
+use fuzzy_ls::arena::PathArena;
+use fuzzy_ls::search::{self, FuzzySearchAlgorithm};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// The number of synthetic candidates ranked per measurement.
+const CANDIDATES: usize = 50_000;
+
+/// The number of times each measurement is repeated, keeping the fastest.
+const ROUNDS: usize = 5;
+
+const WORDS: [&str; 16] = [
+    "main",
+    "user",
+    "service",
+    "test",
+    "controller",
+    "window",
+    "config",
+    "search",
+    "index",
+    "parser",
+    "util",
+    "helper",
+    "model",
+    "view",
+    "router",
+    "handler",
+];
+
+const EXTENSIONS: [&str; 6] = ["rs", "py", "ts", "md", "json", "tar.gz"];
+
+/// Builds deterministic paths like `./src/user/ServiceHandler_3.rs` from a small linear
+/// congruential generator, so every run ranks the same candidates.
+fn synthetic_paths() -> Vec<String> {
+    let mut state: u64 = 0x5eed;
+    let mut next = |bound: usize| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) as usize % bound
+    };
+    (0..CANDIDATES)
+        .map(|index| {
+            let words: Vec<&str> = (0..1 + next(3)).map(|_| WORDS[next(WORDS.len())]).collect();
+            let separator = ["_", "-", ""][next(3)];
+            let name = words.join(separator);
+            format!(
+                "./src/{}/{}_{}.{}",
+                WORDS[next(WORDS.len())],
+                name,
+                index % 10,
+                EXTENSIONS[next(EXTENSIONS.len())]
+            )
+        })
+        .collect()
+}
+
+/// Runs `measure` [`ROUNDS`] times and returns the fastest run.
+fn fastest(mut measure: impl FnMut() -> Duration) -> Duration {
+    (0..ROUNDS).map(|_| measure()).min().unwrap_or_default()
+}
+
+fn report(label: &str, duration: Duration) {
+    println!(
+        "{:<40}{:>10.1} ns/candidate",
+        label,
+        duration.as_nanos() as f64 / CANDIDATES as f64
+    );
+}
+
+fn main() {
+    let paths = synthetic_paths();
+    let names = fastest(|| {
+        let start = Instant::now();
+        for path in &paths {
+            black_box(search::candidate_name_of_path(path));
+        }
+        start.elapsed()
+    });
+    report("candidate names", names);
+    let candidates: Vec<(String, String)> = paths
+        .iter()
+        .map(|path| (search::candidate_name_of_path(path), path.clone()))
+        .collect();
+    for algorithm in FuzzySearchAlgorithm::ALL {
+        for query in ["main", "usr srvce tst", "windowcontroller"] {
+            let duration = fastest(|| {
+                let candidates = candidates.clone();
+                let start = Instant::now();
+                black_box(search::rank_candidates(query, candidates, &algorithm).ok());
+                start.elapsed()
+            });
+            report(&format!("{} \"{}\"", algorithm.name(), query), duration);
+        }
+    }
+    let mut arena = PathArena::new();
+    for (name, path) in &candidates {
+        arena.push(name, path);
+    }
+    let duration = fastest(|| {
+        let start = Instant::now();
+        let ids = arena.ids().collect();
+        let algorithm = FuzzySearchAlgorithm::DamerauLevenshtein;
+        black_box(search::rank_files("main", &arena, ids, &algorithm).ok());
+        start.elapsed()
+    });
+    report("rank_files damerau-levenshtein \"main\"", duration);
+}
@@ -0,0 +1,38 @@
+//! An example scorer plugin for fuzzy-ls.
+//!
+//! Build it with `cargo build --example prefix_scorer` and copy the resulting dynamic library
+//! (e.g. `target/debug/examples/libprefix_scorer.so`) into the plugins directory, then select it
+//! with `--algorithm plugin:prefix_scorer`.
+//!
+//! File names starting with the query score 0; otherwise the score is one plus the number of query
+//! characters that cannot be found in order in the file name.
+
+use std::slice;
+
+#[no_mangle]
+pub extern "C" fn ffs_plugin_abi_version() -> u32 {
+    1
+}
+
+/// # Safety
+///
+/// Both pointers must reference valid UTF-8 buffers of the given lengths.
+#[no_mangle]
+pub unsafe extern "C" fn ffs_score(
+    query: *const u8,
+    query_len: usize,
+    file_name: *const u8,
+    file_name_len: usize,
+) -> u32 {
+    let query = String::from_utf8_lossy(slice::from_raw_parts(query, query_len));
+    let file_name = String::from_utf8_lossy(slice::from_raw_parts(file_name, file_name_len));
+    if file_name.starts_with(query.as_ref()) {
+        return 0;
+    }
+    let mut remaining = file_name.chars();
+    let missing = query
+        .chars()
+        .filter(|c| !remaining.any(|candidate| candidate == *c))
+        .count();
+    1 + missing as u32
+}
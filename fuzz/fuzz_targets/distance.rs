@@ -0,0 +1,16 @@
+#![no_main]
+
+use fuzzy_ls::search::{self, FuzzySearchAlgorithm};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (String, String)| {
+    let (query, file_name) = input;
+    for algorithm in FuzzySearchAlgorithm::ALL {
+        // Queries an algorithm can't score report an error; others must never panic and must
+        // score identical strings as an exact match.
+        if search::score_fuzzy_search(&query, &file_name, algorithm).is_ok() {
+            let identical = search::score_fuzzy_search(&query, &query, algorithm);
+            assert_eq!(identical, Ok(0), "{:?}", algorithm);
+        }
+    }
+});
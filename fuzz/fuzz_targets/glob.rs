@@ -0,0 +1,13 @@
+#![no_main]
+
+use fuzzy_ls::glob::{glob_match, PathPattern};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (String, String)| {
+    let (pattern, path) = input;
+    glob_match(&pattern, &path);
+    if let Some(path_pattern) = PathPattern::parse(&pattern) {
+        path_pattern.matches(&path, false);
+        path_pattern.matches(&path, true);
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use fuzzy_ls::search;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|path: String| {
+    let name = search::candidate_name_of_path(&path);
+    assert!(name.len() <= path.len());
+    search::candidate_name(&path);
+});
@@ -0,0 +1,52 @@
+/* C interface to the fuzzy-ls ranking engine, exported by the fuzzy_ls dynamic library. */
+#ifndef FUZZY_LS_H
+#define FUZZY_LS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define FFS_OK 0
+#define FFS_INVALID_ARGUMENT -1
+#define FFS_SCORER_ERROR -2
+
+#define FFS_ALGORITHM_DAMERAU_LEVENSHTEIN 0
+#define FFS_ALGORITHM_LEVENSHTEIN 1
+#define FFS_ALGORITHM_BITAP 2
+#define FFS_ALGORITHM_JARO_WINKLER 3
+#define FFS_ALGORITHM_SUBSEQUENCE 4
+#define FFS_ALGORITHM_TOKENS 5
+#define FFS_ALGORITHM_SMITH_WATERMAN 6
+#define FFS_ALGORITHM_TRIGRAM 7
+
+typedef struct {
+    uint32_t algorithm;  /* One of FFS_ALGORITHM_*. */
+    size_t max_results;  /* 0 for no limit. */
+} FfsRankOptions;
+
+typedef struct {
+    size_t index;        /* Index of the candidate in the input paths. */
+    uint32_t score;      /* Lower is better, 0 is an exact match. */
+} FfsRankResult;
+
+typedef struct {
+    FfsRankResult *results;
+    size_t len;
+    int32_t status;      /* FFS_OK, or an error and no results. */
+} FfsRankResults;
+
+/* Ranks paths against query, best first. options may be NULL for the defaults. */
+FfsRankResults ffs_rank(const char *query, const char *const *paths, size_t n,
+                        const FfsRankOptions *options);
+
+/* Releases results returned by ffs_rank. */
+void ffs_rank_results_free(FfsRankResults results);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* FUZZY_LS_H */
@@ -0,0 +1,155 @@
+use crate::storage;
+use fuzzy_ls::glob::PathPattern;
+use std::fs;
+
+/// Score adjustments by extension or path, from the `adjustments` file in the config directory,
+/// e.g. to rank minified files below their sources.
+///
+/// Each line of the file holds a pattern and the number added to the score of the results
+/// matching it, e.g. `*.min.js 5` to penalize minified files or `.rs -1` to boost Rust files, as
+/// lower scores rank first. Patterns are extensions like `.rs` or gitignore-style patterns like
+/// `dist/`, and every matching line applies. Lines starting with `#` are comments.
+#[derive(Default)]
+pub struct ScoreAdjustments {
+    rules: Vec<(String, PathPattern, i64)>,
+}
+
+impl ScoreAdjustments {
+    /// Reads the adjustments file of the config directory. Without one, or without a config
+    /// directory, nothing is adjusted.
+    pub fn load() -> Result<ScoreAdjustments, String> {
+        let Ok(dir) = storage::config_dir() else {
+            return Ok(ScoreAdjustments::default());
+        };
+        let path = dir.join("adjustments");
+        match fs::read_to_string(&path) {
+            Ok(contents) => ScoreAdjustments::parse(&contents)
+                .map_err(|error| format!("{}: {}", path.display(), error)),
+            Err(_) => Ok(ScoreAdjustments::default()),
+        }
+    }
+
+    fn parse(contents: &str) -> Result<ScoreAdjustments, String> {
+        let mut rules = Vec::new();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (pattern, adjustment) = line
+                .rsplit_once(char::is_whitespace)
+                .ok_or(format!("The adjustment {} has no number.", line))?;
+            let adjustment: i64 = adjustment
+                .trim_start_matches('+')
+                .parse()
+                .map_err(|_| format!("The adjustment of {} is not a number.", line))?;
+            let pattern = pattern.trim();
+            let glob = match pattern.strip_prefix('.') {
+                Some(extension) if !extension.contains(['/', '*']) => format!("*.{}", extension),
+                _ => pattern.to_string(),
+            };
+            let path_pattern = PathPattern::parse(&glob)
+                .ok_or(format!("The adjustment {} has no pattern.", line))?;
+            rules.push((pattern.to_string(), path_pattern, adjustment));
+        }
+        Ok(ScoreAdjustments { rules })
+    }
+
+    /// Returns the patterns matching a result with their adjustments, in the order of the file.
+    pub fn matching(&self, full_path: &str) -> Vec<(&str, i64)> {
+        let path = full_path.replace('\\', "/");
+        let path = path.trim_start_matches("./");
+        let is_dir = path.ends_with('/');
+        let path = path.trim_end_matches('/');
+        self.rules
+            .iter()
+            .filter(|(_, pattern, _)| pattern.matches(path, is_dir))
+            .map(|(pattern, _, adjustment)| (pattern.as_str(), *adjustment))
+            .collect()
+    }
+
+    /// Adds the adjustments of each result to its score and reorders the results by their new
+    /// scores. Results with equal scores keep their order. Only exact matches score 0, so fuzzy
+    /// matches never go below 1.
+    pub fn apply(&self, potential_hits: &mut [(u32, String, String)]) {
+        if self.rules.is_empty() {
+            return;
+        }
+        for hit in potential_hits.iter_mut() {
+            let adjustment: i64 = self.matching(&hit.2).iter().map(|rule| rule.1).sum();
+            let floor = hit.0.min(1) as i64;
+            hit.0 = (hit.0 as i64 + adjustment).clamp(floor, u32::MAX as i64) as u32;
+        }
+        potential_hits.sort_by_key(|hit| hit.0);
+    }
+
+    /// Describes how the score of a result came about, for `--explain`, e.g.
+    /// `./dist/app.min.js: score 8 (match 1, *.min.js +5, dist/ +2)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `full_path` - The full path of the result.
+    /// * `score` - The score of the result after the adjustments.
+    /// * `match_score` - The score the result was matched with.
+    /// * `learned_weight` - The weight learned from picks, which reorders results on top of scores.
+    pub fn explain(
+        &self,
+        full_path: &str,
+        score: u32,
+        match_score: u32,
+        learned_weight: f64,
+    ) -> String {
+        let mut parts = vec![format!("match {}", match_score)];
+        for (pattern, adjustment) in self.matching(full_path) {
+            parts.push(format!("{} {:+}", pattern, adjustment));
+        }
+        if learned_weight != 0.0 {
+            parts.push(format!("learned weight {:+.2}", learned_weight));
+        }
+        format!("{}: score {} ({})", full_path, score, parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_adjustments() {
+        let adjustments =
+            ScoreAdjustments::parse("# minified\n*.min.js 5\n.rs -1\ndist/ +2\n").unwrap();
+        let mut potential_hits = vec![
+            (1, "app.min".to_string(), "./dist/app.min.js".to_string()),
+            (2, "app".to_string(), "./src/app.js".to_string()),
+            (2, "app".to_string(), "./src/app.rs".to_string()),
+            (0, "main".to_string(), "./src/main.rs".to_string()),
+            (1, "lib".to_string(), "./src/lib.rs".to_string()),
+        ];
+        assert_eq!(
+            adjustments.matching("./dist/app.min.js"),
+            [("*.min.js", 5), ("dist/", 2)]
+        );
+        adjustments.apply(&mut potential_hits);
+        let ranked: Vec<(u32, &str)> = potential_hits
+            .iter()
+            .map(|(score, _, full_path)| (*score, full_path.as_str()))
+            .collect();
+        assert_eq!(
+            ranked,
+            [
+                (0, "./src/main.rs"),
+                (1, "./src/app.rs"),
+                (1, "./src/lib.rs"),
+                (2, "./src/app.js"),
+                (8, "./dist/app.min.js")
+            ]
+        );
+        assert_eq!(
+            adjustments.explain("./dist/app.min.js", 8, 1, -0.5),
+            "./dist/app.min.js: score 8 (match 1, *.min.js +5, dist/ +2, learned weight -0.50)"
+        );
+        assert_eq!(
+            ScoreAdjustments::parse(".rs boost").err(),
+            Some("The adjustment of .rs boost is not a number.".to_string())
+        );
+    }
+}
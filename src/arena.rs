@@ -0,0 +1,251 @@
+use std::collections::hash_map::{Entry, RandomState};
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::path::Path;
+
+/// Refers to a path stored in a [`PathArena`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PathId(u32);
+
+impl PathId {
+    /// The position of the path in its arena, for storing data about paths alongside it.
+    pub(crate) fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Where a path and the name it is matched by are stored in the text of a [`PathArena`].
+#[derive(Debug, Clone, Copy)]
+struct PathSpan {
+    start: usize,
+    end: usize,
+    name_start: usize,
+    name_end: usize,
+}
+
+/// Stores the paths of candidates back to back in one buffer, so a candidate takes a [`PathId`]
+/// instead of two strings of its own. Names that are part of their path, like the name of
+/// `./src/main.rs`, aren't stored again.
+#[derive(Debug, Default)]
+pub struct PathArena {
+    text: String,
+    spans: Vec<PathSpan>,
+}
+
+impl PathArena {
+    /// Creates an empty arena.
+    pub fn new() -> PathArena {
+        PathArena::default()
+    }
+
+    /// Stores a candidate.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name matched against the query.
+    /// * `full_path` - The full path of the candidate.
+    ///
+    /// # Returns
+    ///
+    /// The id the candidate is referred to by.
+    pub fn push(&mut self, name: &str, full_path: &str) -> PathId {
+        let id = PathId(self.spans.len() as u32);
+        let start = self.text.len();
+        self.text.push_str(full_path);
+        let end = self.text.len();
+        let file_name = Path::new(full_path)
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .filter(|file_name| full_path.ends_with(file_name) && file_name.starts_with(name));
+        let name_start = match file_name {
+            Some(file_name) => end - file_name.len(),
+            None => {
+                self.text.push_str(name);
+                end
+            }
+        };
+        self.spans.push(PathSpan {
+            start,
+            end,
+            name_start,
+            name_end: name_start + name.len(),
+        });
+        id
+    }
+
+    /// Returns the full path of a candidate.
+    pub fn path(&self, id: PathId) -> &str {
+        let span = self.spans[id.0 as usize];
+        &self.text[span.start..span.end]
+    }
+
+    /// Returns the name a candidate is matched by.
+    pub fn name(&self, id: PathId) -> &str {
+        let span = self.spans[id.0 as usize];
+        &self.text[span.name_start..span.name_end]
+    }
+
+    /// Returns the number of stored candidates.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Returns whether no candidates are stored.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Returns the ids of the stored candidates, in the order they were pushed.
+    pub fn ids(&self) -> impl Iterator<Item = PathId> {
+        (0..self.spans.len() as u32).map(PathId)
+    }
+
+    /// Converts a candidate into the owned `(name, full path)` tuple the rest of the pipeline uses.
+    pub fn to_owned_candidate(&self, id: PathId) -> (String, String) {
+        (self.name(id).to_string(), self.path(id).to_string())
+    }
+
+    /// Converts scored candidates into owned `(score, name, full path)` hits.
+    pub fn to_hits(&self, hits: &[(u32, PathId)]) -> Vec<(u32, String, String)> {
+        hits.iter()
+            .map(|&(score, id)| (score, self.name(id).to_string(), self.path(id).to_string()))
+            .collect()
+    }
+}
+
+/// Adds paths to a [`PathArena`] once, treating paths that only differ by a leading `./` or `.\`
+/// or by their separators, like `./src/main.rs` and `src\main.rs`, as the same.
+///
+/// Paths are indexed by a hash of that form instead of a copy of it, so deduplicating doesn't
+/// allocate per path.
+#[derive(Debug, Default)]
+pub struct PathInterner {
+    pub arena: PathArena,
+    hasher: RandomState,
+    first: HashMap<u64, PathId>,
+    /// Paths whose hash was already taken by a different path.
+    collisions: Vec<PathId>,
+}
+
+impl PathInterner {
+    /// Creates an empty interner.
+    pub fn new() -> PathInterner {
+        PathInterner::default()
+    }
+
+    /// Adds a candidate unless an equivalent path was already added.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name matched against the query.
+    /// * `full_path` - The full path of the candidate.
+    ///
+    /// # Returns
+    ///
+    /// The id of the candidate, and whether it was added rather than found.
+    pub fn intern(&mut self, name: &str, full_path: &str) -> (PathId, bool) {
+        let hash = self.hash(full_path);
+        let arena = &mut self.arena;
+        match self.first.entry(hash) {
+            Entry::Vacant(entry) => (*entry.insert(arena.push(name, full_path)), true),
+            Entry::Occupied(entry) => {
+                let found = std::iter::once(*entry.get())
+                    .chain(self.collisions.iter().copied())
+                    .find(|id| same_path(arena.path(*id), full_path));
+                match found {
+                    Some(id) => (id, false),
+                    None => {
+                        let id = arena.push(name, full_path);
+                        self.collisions.push(id);
+                        (id, true)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Removes every path, handing back the arena holding them.
+    pub fn take(&mut self) -> PathArena {
+        self.first.clear();
+        self.collisions.clear();
+        std::mem::take(&mut self.arena)
+    }
+
+    fn hash(&self, path: &str) -> u64 {
+        // The normalized bytes are written in fixed chunks, so equal paths hash equally however
+        // their separators are spelled.
+        let mut hasher = self.hasher.build_hasher();
+        let mut chunk = [0u8; 64];
+        let mut length = 0;
+        for byte in normalized_bytes(path) {
+            chunk[length] = byte;
+            length += 1;
+            if length == chunk.len() {
+                hasher.write(&chunk);
+                length = 0;
+            }
+        }
+        hasher.write(&chunk[..length]);
+        hasher.write_usize(length);
+        hasher.finish()
+    }
+}
+
+/// The bytes of a path without a leading `./` or `.\` and with `/` for every separator.
+fn normalized_bytes(path: &str) -> impl Iterator<Item = u8> + '_ {
+    let path = path.trim_start_matches("./").trim_start_matches(".\\");
+    path.bytes()
+        .map(|byte| if byte == b'\\' { b'/' } else { byte })
+}
+
+/// Returns whether two paths are the same for a [`PathInterner`].
+fn same_path(left: &str, right: &str) -> bool {
+    normalized_bytes(left).eq(normalized_bytes(right))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_arena() {
+        let mut arena = PathArena::new();
+        let main = arena.push("main", "./src/main.rs");
+        let dir = arena.push("projects", "/home/user/projects");
+        let renamed = arena.push("lib", "./src/other.rs");
+        assert_eq!(arena.path(main), "./src/main.rs");
+        assert_eq!(arena.name(main), "main");
+        assert_eq!(arena.name(dir), "projects");
+        assert_eq!(arena.path(renamed), "./src/other.rs");
+        assert_eq!(arena.name(renamed), "lib");
+        // Names found in their path take no space of their own.
+        assert_eq!(
+            arena.text.len(),
+            "./src/main.rs/home/user/projects./src/other.rslib".len()
+        );
+        assert_eq!(arena.ids().collect::<Vec<_>>(), vec![main, dir, renamed]);
+        assert_eq!(
+            arena.to_hits(&[(1, renamed)]),
+            vec![(1, "lib".to_string(), "./src/other.rs".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_path_interner() {
+        let mut interner = PathInterner::new();
+        let (main, added) = interner.intern("main", "./src/main.rs");
+        assert!(added);
+        assert_eq!(interner.intern("main", "src/main.rs"), (main, false));
+        assert_eq!(interner.intern("main", ".\\src\\main.rs"), (main, false));
+        let (lib, added) = interner.intern("lib", "src/lib.rs");
+        assert!(added && lib != main);
+        let long = "a/".repeat(100);
+        let (long_id, _) = interner.intern("a", &long);
+        assert_eq!(
+            interner.intern("a", &long.replace('/', "\\")),
+            (long_id, false)
+        );
+        assert_eq!(interner.take().len(), 3);
+        assert!(interner.intern("main", "src/main.rs").1);
+    }
+}
@@ -0,0 +1,294 @@
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Runs a build tool in the current directory and returns its standard output.
+///
+/// # Arguments
+///
+/// * `program` - The build tool, `cargo` or `bazel`.
+/// * `args` - The arguments passed to it.
+///
+/// # Returns
+///
+/// The output, or an error message if the tool could not be run or failed.
+fn run_tool(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|error| format!("Failed to run {}: {}", program, error))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Collects the source files of a build target.
+///
+/// Bazel labels (`//pkg:target`, `@repo//pkg:target`) are resolved with `bazel query` to the
+/// files in the `srcs` and `hdrs` of the target. Anything else names a Cargo package of the
+/// workspace, optionally followed by `:<target>` to pick one of its targets, e.g. `fuzzy-ls:scoring`.
+/// Cargo only knows the root file of each target, so the files its `mod` declarations pull in are
+/// followed from there.
+///
+/// # Arguments
+///
+/// * `target` - The Bazel label or Cargo package and target.
+///
+/// # Returns
+///
+/// The source paths relative to the current directory, in the form of [`crate::git::normalize_path`].
+/// Files outside the current directory are left out, since they can't be candidates.
+pub fn target_files(target: &str) -> Result<BTreeSet<String>, String> {
+    let files = if target.starts_with("//") || target.starts_with('@') {
+        bazel_files(target)?
+    } else {
+        cargo_files(target)?
+    };
+    let current_dir = current_dir()?;
+    let files: BTreeSet<String> = files
+        .iter()
+        .filter_map(|file| relative_to(file, &current_dir))
+        .collect();
+    if files.is_empty() {
+        return Err(format!(
+            "No source files of {} were found below the current directory.",
+            target
+        ));
+    }
+    Ok(files)
+}
+
+/// Returns the canonical path of the current directory.
+fn current_dir() -> Result<PathBuf, String> {
+    std::env::current_dir()
+        .and_then(|dir| dir.canonicalize())
+        .map_err(|error| format!("Failed to read the current directory: {}", error))
+}
+
+/// Returns the path of a file relative to the current directory, in the form of
+/// [`crate::git::normalize_path`], or `None` if it's outside of it.
+fn relative_to(file: &Path, current_dir: &Path) -> Option<String> {
+    let file = file.canonicalize().ok()?;
+    let relative = file.strip_prefix(current_dir).ok()?;
+    Some(crate::git::normalize_path(&relative.to_string_lossy()))
+}
+
+/// Returns the source directory of a member of the Cargo workspace, e.g. `crates/core/src`: the
+/// directory of its library, or of its first target if it has none.
+///
+/// # Arguments
+///
+/// * `name` - The name of the package.
+///
+/// # Returns
+///
+/// The directory relative to the current directory, in the form of [`crate::git::normalize_path`],
+/// or an error message if the package isn't in the workspace or is outside the current directory.
+pub fn crate_directory(name: &str) -> Result<PathBuf, String> {
+    let package = cargo_package(name)?;
+    let targets = package["targets"].as_array().cloned().unwrap_or_default();
+    let src_path = targets
+        .iter()
+        .find(|target| {
+            target["kind"]
+                .as_array()
+                .is_some_and(|kinds| kinds.contains(&"lib".into()))
+        })
+        .or(targets.first())
+        .and_then(|target| target["src_path"].as_str())
+        .ok_or(format!("Package {} has no targets.", name))?;
+    let directory = Path::new(src_path).parent().unwrap_or(Path::new("."));
+    relative_to(directory, &current_dir()?)
+        .map(PathBuf::from)
+        .ok_or(format!(
+            "The sources of {} are outside the current directory.",
+            name
+        ))
+}
+
+/// Finds a member of the Cargo workspace in `cargo metadata`.
+fn cargo_package(name: &str) -> Result<Value, String> {
+    let metadata = run_tool("cargo", &["metadata", "--format-version", "1", "--no-deps"])?;
+    let mut metadata: Value = serde_json::from_str(&metadata)
+        .map_err(|error| format!("Invalid cargo metadata: {}", error))?;
+    metadata["packages"]
+        .as_array_mut()
+        .and_then(|packages| {
+            let position = packages
+                .iter()
+                .position(|package| package["name"] == name)?;
+            Some(packages.swap_remove(position))
+        })
+        .ok_or(format!("No package {} in the Cargo workspace.", name))
+}
+
+/// Resolves the `srcs` and `hdrs` of a Bazel target to files in the workspace.
+fn bazel_files(target: &str) -> Result<Vec<PathBuf>, String> {
+    let workspace = PathBuf::from(run_tool("bazel", &["info", "workspace"])?.trim());
+    let query = format!("labels(srcs, {0}) + labels(hdrs, {0})", target);
+    Ok(run_tool("bazel", &["query", &query, "--output=label"])?
+        .lines()
+        .filter_map(label_path)
+        .map(|path| workspace.join(path))
+        .collect())
+}
+
+/// Converts the label of a source file in the main repository, e.g. `//src/app:main.cc`, into its
+/// path relative to the workspace root.
+///
+/// # Returns
+///
+/// `None` for labels of other repositories, which aren't in the workspace.
+fn label_path(label: &str) -> Option<PathBuf> {
+    let label = label.trim().strip_prefix("@@").unwrap_or(label.trim());
+    let label = label.strip_prefix("//")?;
+    let (package, name) = label.split_once(':').unwrap_or((label, ""));
+    let mut path = PathBuf::from(package);
+    if !name.is_empty() {
+        path.push(name);
+    }
+    Some(path)
+}
+
+/// Resolves a Cargo package, or one of its targets, to its Rust source files.
+fn cargo_files(target: &str) -> Result<Vec<PathBuf>, String> {
+    let (package_name, target_name) = match target.split_once(':') {
+        Some((package, target)) => (package, Some(target)),
+        None => (target, None),
+    };
+    let package = cargo_package(package_name)?;
+    let roots: Vec<PathBuf> = package["targets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|target| target_name.is_none_or(|name| target["name"] == name))
+        .filter_map(|target| target["src_path"].as_str().map(PathBuf::from))
+        .collect();
+    if roots.is_empty() {
+        return Err(format!(
+            "No target {} in package {}.",
+            target_name.unwrap_or_default(),
+            package_name
+        ));
+    }
+    let mut files: Vec<PathBuf> = Vec::new();
+    for root in roots {
+        module_files(&root, true, &mut files);
+    }
+    Ok(files)
+}
+
+/// Adds a Rust source file and the files of the modules it declares, recursively.
+///
+/// # Arguments
+///
+/// * `file` - The source file.
+/// * `is_root` - Whether the file is the root of a target, whose modules are next to it.
+/// * `files` - The files found so far.
+fn module_files(file: &Path, is_root: bool, files: &mut Vec<PathBuf>) {
+    if files.iter().any(|found| found == file) {
+        return;
+    }
+    let Ok(source) = std::fs::read_to_string(file) else {
+        return;
+    };
+    files.push(file.to_path_buf());
+    let parent = file.parent().unwrap_or(Path::new("."));
+    // Modules of `lib.rs`, `main.rs` and `mod.rs` are next to them, other modules are in the
+    // directory named after their file.
+    let directory = if is_root || file.file_name().is_some_and(|name| name == "mod.rs") {
+        parent.to_path_buf()
+    } else {
+        parent.join(file.file_stem().unwrap_or_default())
+    };
+    for (name, path) in declared_modules(&source) {
+        match path {
+            Some(path) => module_files(&parent.join(path), false, files),
+            None => {
+                let file = directory.join(format!("{}.rs", name));
+                if file.is_file() {
+                    module_files(&file, false, files);
+                } else {
+                    module_files(&directory.join(name).join("mod.rs"), true, files);
+                }
+            }
+        }
+    }
+}
+
+/// Finds the out-of-line modules a Rust file declares, e.g. `pub mod search;`, with the path of
+/// their `#[path = "..."]` attribute, if any.
+fn declared_modules(source: &str) -> Vec<(String, Option<String>)> {
+    let mut modules = Vec::new();
+    let mut path_attribute: Option<String> = None;
+    for line in source.lines().map(str::trim) {
+        if let Some(path) = line
+            .strip_prefix("#[path")
+            .and_then(|rest| rest.split('"').nth(1))
+        {
+            path_attribute = Some(path.to_string());
+            continue;
+        }
+        if line.starts_with("#[") {
+            continue;
+        }
+        let declaration = match line.strip_prefix("pub") {
+            Some(rest) if rest.starts_with('(') => rest.split_once(')').map(|(_, rest)| rest),
+            Some(rest) => Some(rest),
+            None => Some(line),
+        };
+        let name = declaration
+            .and_then(|declaration| declaration.trim_start().strip_prefix("mod "))
+            .and_then(|rest| rest.trim().strip_suffix(';'))
+            .map(str::trim)
+            .filter(|name| name.chars().all(|c| c.is_alphanumeric() || c == '_'));
+        if let Some(name) = name {
+            modules.push((name.to_string(), path_attribute.take()));
+        } else {
+            path_attribute = None;
+        }
+    }
+    modules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_path() {
+        assert_eq!(
+            label_path("//src/app:main.cc"),
+            Some(PathBuf::from("src/app/main.cc"))
+        );
+        assert_eq!(
+            label_path("@@//lib:util/strings.h"),
+            Some(PathBuf::from("lib/util/strings.h"))
+        );
+        assert_eq!(label_path("@abseil//absl:base.h"), None);
+    }
+
+    #[test]
+    fn test_declared_modules() {
+        let source = "mod codeowners;\npub mod search;\npub(crate) mod arena;\n\
+                      #[cfg(feature = \"tui\")]\nmod gui;\n#[path = \"other/impl.rs\"]\nmod imp;\n\
+                      mod inline {\n}\n// mod commented;\n";
+        assert_eq!(
+            declared_modules(source),
+            vec![
+                ("codeowners".to_string(), None),
+                ("search".to_string(), None),
+                ("arena".to_string(), None),
+                ("gui".to_string(), None),
+                ("imp".to_string(), Some("other/impl.rs".to_string())),
+            ]
+        );
+    }
+}
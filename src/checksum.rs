@@ -0,0 +1,284 @@
+use clap::ValueEnum;
+use fuzzy_ls::arena::PathArena;
+use fuzzy_ls::filter::{FilterChain, HiddenFilter};
+use fuzzy_ls::search::{self, FuzzySearchAlgorithm};
+use sha2::{Digest, Sha256, Sha512};
+use std::cell::OnceCell;
+use std::fs::File;
+use std::path::Path;
+
+/// How many of the files with the closest names are checked for a file listed in a manifest that
+/// isn't found by its name.
+const LOCATE_CANDIDATES: usize = 5;
+
+/// The hash functions checksums are computed with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// The name of the algorithm, e.g. `sha256`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+        }
+    }
+
+    /// Returns the algorithm of a checksum in hex by its length.
+    fn of_checksum(checksum: &str) -> Option<HashAlgorithm> {
+        match checksum.len() {
+            64 => Some(HashAlgorithm::Sha256),
+            128 => Some(HashAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    /// Computes the checksum of a file in lower case hex, reading it in chunks.
+    pub fn file_checksum(&self, full_path: &str) -> Result<String, String> {
+        let mut file =
+            File::open(full_path).map_err(|error| format!("{}: {}", full_path, error))?;
+        let digest = match self {
+            HashAlgorithm::Sha256 => hash_file::<Sha256>(&mut file),
+            HashAlgorithm::Sha512 => hash_file::<Sha512>(&mut file),
+        }
+        .map_err(|error| format!("{}: {}", full_path, error))?;
+        Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+}
+
+fn hash_file<D: Digest + std::io::Write>(file: &mut File) -> std::io::Result<Vec<u8>> {
+    let mut hasher = D::new();
+    std::io::copy(file, &mut hasher)?;
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Lists the checksums of the results in the format of `sha256sum`, one `<checksum>  <path>` line
+/// per result, which `verify` reads back.
+///
+/// # Arguments
+///
+/// * `algorithm` - The hash function.
+/// * `potential_hits` - The results, as tuples of score, file name and full path.
+///
+/// # Returns
+///
+/// The manifest, or the error of the first result that couldn't be read.
+pub fn manifest(
+    algorithm: HashAlgorithm,
+    potential_hits: &[(u32, String, String)],
+) -> Result<String, String> {
+    let mut manifest = String::new();
+    for (_, _, full_path) in potential_hits {
+        let checksum = algorithm.file_checksum(full_path)?;
+        manifest.push_str(&format!("{}  {}\n", checksum, full_path));
+    }
+    Ok(manifest)
+}
+
+/// Collects the file names and full paths of the files to look for moved files in.
+pub type Walk<'a> = dyn Fn() -> Vec<(String, String)> + 'a;
+
+/// A file listed in a checksum manifest.
+#[derive(Debug, PartialEq, Eq)]
+struct ManifestEntry {
+    checksum: String,
+    path: String,
+}
+
+/// Parses a manifest in the format of `sha256sum` and `sha512sum`, in text or binary mode. Blank
+/// lines and comments are skipped.
+fn parse_manifest(contents: &str) -> Result<Vec<ManifestEntry>, String> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            let (checksum, path) = line
+                .split_once(' ')
+                .filter(|(checksum, _)| HashAlgorithm::of_checksum(checksum).is_some())
+                .ok_or(format!("Line {} is not a checksum and a path.", index + 1))?;
+            let path = path.strip_prefix([' ', '*']).unwrap_or(path);
+            Ok(ManifestEntry {
+                checksum: checksum.to_lowercase(),
+                path: path.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The outcome of verifying a file listed in a manifest.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// The checksum matches the file at this path.
+    Ok(String),
+    /// Files were found for the listed one, but none has its checksum.
+    Failed,
+    /// No file was found for the listed one.
+    Missing,
+}
+
+/// Finds the files that may be a file listed in a manifest: the listed path when it exists,
+/// otherwise the candidates with its file name, otherwise the candidates whose names are closest
+/// to its name.
+fn locate(
+    listed: &str,
+    candidates: &OnceCell<Vec<(String, String)>>,
+    walk: &Walk,
+) -> Result<Vec<String>, String> {
+    if Path::new(listed).is_file() {
+        return Ok(vec![listed.to_string()]);
+    }
+    let candidates = candidates.get_or_init(walk);
+    let Some(file_name) = Path::new(listed).file_name() else {
+        return Ok(Vec::new());
+    };
+    let same_name: Vec<String> = candidates
+        .iter()
+        .filter(|(_, full_path)| Path::new(full_path).file_name() == Some(file_name))
+        .map(|(_, full_path)| full_path.clone())
+        .collect();
+    if !same_name.is_empty() {
+        return Ok(same_name);
+    }
+    let mut paths = PathArena::new();
+    for (name, full_path) in candidates {
+        paths.push(name, full_path);
+    }
+    let query = Path::new(file_name)
+        .file_stem()
+        .unwrap_or(file_name)
+        .to_string_lossy();
+    let ranked = search::rank_files(
+        &query,
+        &paths,
+        paths.ids().collect(),
+        &FuzzySearchAlgorithm::DamerauLevenshtein,
+    )?;
+    Ok(ranked
+        .into_iter()
+        .take(LOCATE_CANDIDATES)
+        .map(|(_, id)| paths.path(id).to_string())
+        .collect())
+}
+
+/// Verifies the files listed in a manifest, locating the ones that moved among the candidates.
+///
+/// # Arguments
+///
+/// * `contents` - The manifest, in the format of `sha256sum` or `sha512sum`.
+/// * `walk` - Collects the files the listed ones may have moved to, once one isn't at its path.
+///
+/// # Returns
+///
+/// The listed paths with their verdicts, in the order of the manifest.
+pub fn verify(contents: &str, walk: &Walk) -> Result<Vec<(String, Verdict)>, String> {
+    let candidates = OnceCell::new();
+    let mut verdicts = Vec::new();
+    for entry in parse_manifest(contents)? {
+        let algorithm =
+            HashAlgorithm::of_checksum(&entry.checksum).unwrap_or(HashAlgorithm::Sha256);
+        let located = locate(&entry.path, &candidates, walk)?;
+        let verdict = located
+            .iter()
+            .find(|full_path| {
+                algorithm
+                    .file_checksum(full_path)
+                    .is_ok_and(|checksum| checksum == entry.checksum)
+            })
+            .map_or(
+                if located.is_empty() {
+                    Verdict::Missing
+                } else {
+                    Verdict::Failed
+                },
+                |full_path| Verdict::Ok(full_path.clone()),
+            );
+        verdicts.push((entry.path, verdict));
+    }
+    Ok(verdicts)
+}
+
+/// Verifies the files listed in a manifest and prints the verdict of each, like `sha256sum -c`.
+/// Files that aren't at their listed path are looked for among the files under the current
+/// directory, by name and then by the closest names.
+///
+/// # Arguments
+///
+/// * `manifest_path` - The path of the manifest.
+///
+/// # Returns
+///
+/// An error unless every listed file was verified.
+pub fn run_verify(manifest_path: &str) -> Result<(), String> {
+    let contents = std::fs::read_to_string(manifest_path)
+        .map_err(|error| format!("{}: {}", manifest_path, error))?;
+    let walk = || {
+        let filters = FilterChain::new().with(HiddenFilter { hidden: false });
+        search::walk_directory(Path::new("."), &filters)
+    };
+    let verdicts = verify(&contents, &walk)?;
+    let mut failed = 0;
+    for (path, verdict) in &verdicts {
+        match verdict {
+            Verdict::Ok(full_path) if full_path == path => println!("{}: OK", path),
+            Verdict::Ok(full_path) => println!("{}: OK (found at {})", path, full_path),
+            Verdict::Failed => println!("{}: FAILED", path),
+            Verdict::Missing => println!("{}: MISSING", path),
+        }
+        if !matches!(verdict, Verdict::Ok(_)) {
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(format!(
+            "{} of {} listed files could not be verified.",
+            failed,
+            verdicts.len()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let root = std::env::temp_dir().join(format!("ffs-verify-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("moved")).unwrap();
+        let path = |name: &str| root.join(name).to_string_lossy().into_owned();
+        std::fs::write(path("kept.txt"), "kept").unwrap();
+        std::fs::write(path("moved/renamed.txt"), "renamed").unwrap();
+        std::fs::write(path("changed.txt"), "changed").unwrap();
+        let checksum = |contents: &str| format!("{:x}", Sha256::digest(contents));
+        let manifest = format!(
+            "{}  {}\n{} *{}\n{}  {}\n{}  {}\n",
+            checksum("kept"),
+            path("kept.txt"),
+            checksum("renamed"),
+            path("renamed.txt"),
+            checksum("original"),
+            path("changed.txt"),
+            checksum("gone"),
+            path("gone/zzzz"),
+        );
+        let walk = || search::walk_directory(&root, &FilterChain::new());
+        let verdicts = verify(&manifest, &walk).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            verdicts,
+            [
+                (path("kept.txt"), Verdict::Ok(path("kept.txt"))),
+                (path("renamed.txt"), Verdict::Ok(path("moved/renamed.txt"))),
+                (path("changed.txt"), Verdict::Failed),
+                (path("gone/zzzz"), Verdict::Missing),
+            ]
+        );
+        assert!(verify("not a checksum\n", &walk).is_err());
+        assert!(HashAlgorithm::Sha512.file_checksum("/nonexistent").is_err());
+    }
+}
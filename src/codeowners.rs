@@ -0,0 +1,100 @@
+use crate::git;
+use fuzzy_ls::glob::PathPattern;
+use std::fs;
+use std::path::Path;
+
+/// Locations searched for a `CODEOWNERS` file, relative to the repository root.
+const CODEOWNERS_LOCATIONS: [&str; 3] = ["CODEOWNERS", ".github/CODEOWNERS", "docs/CODEOWNERS"];
+
+/// Ownership rules parsed from a `CODEOWNERS` file.
+pub struct CodeOwners {
+    rules: Vec<(PathPattern, Vec<String>)>,
+    prefix: String,
+}
+
+impl CodeOwners {
+    /// Parses the contents of a `CODEOWNERS` file.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - The file contents.
+    /// * `prefix` - The current directory relative to the repository root, prepended to walked paths.
+    pub fn parse(contents: &str, prefix: &str) -> CodeOwners {
+        let rules = contents
+            .lines()
+            .filter_map(|line| {
+                let mut tokens = line.split_whitespace();
+                let pattern = PathPattern::parse(tokens.next()?)?;
+                let owners = tokens
+                    .take_while(|token| !token.starts_with('#'))
+                    .map(String::from)
+                    .collect();
+                Some((pattern, owners))
+            })
+            .collect();
+        CodeOwners {
+            rules,
+            prefix: prefix.to_string(),
+        }
+    }
+
+    /// Loads the `CODEOWNERS` file of the repository containing the current directory.
+    ///
+    /// # Returns
+    ///
+    /// The parsed rules, or an error message if no `CODEOWNERS` file could be found.
+    pub fn load() -> Result<CodeOwners, String> {
+        let (root, prefix) = match git::repository_root() {
+            Ok(root) => (root, git::current_prefix()?),
+            Err(_) => (".".to_string(), String::new()),
+        };
+        CODEOWNERS_LOCATIONS
+            .iter()
+            .find_map(|location| fs::read_to_string(Path::new(&root).join(location)).ok())
+            .map(|contents| CodeOwners::parse(&contents, &prefix))
+            .ok_or(format!("No CODEOWNERS file found in {}", root))
+    }
+
+    /// Returns the owners of a walked file. As in GitHub, the last matching rule wins.
+    ///
+    /// # Arguments
+    ///
+    /// * `full_path` - The path of the file as produced by the directory walk.
+    pub fn owners_of(&self, full_path: &str) -> &[String] {
+        let relative_path = format!("{}{}", self.prefix, git::normalize_path(full_path));
+        self.rules
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.matches(&relative_path, false))
+            .map_or(&[], |(_, owners)| owners.as_slice())
+    }
+
+    /// Whether any of the given owners owns the walked file. Owners are compared case-insensitively.
+    pub fn is_owned_by(&self, full_path: &str, owners: &[String]) -> bool {
+        self.owners_of(full_path).iter().any(|owner| {
+            owners
+                .iter()
+                .any(|wanted| owner.eq_ignore_ascii_case(wanted))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_matching_rule_wins() {
+        let code_owners = CodeOwners::parse(
+            "# Default owners\n* @org/core\n/docs/ @org/docs # docs team\n*.rs @rustacean @org/core\n",
+            "",
+        );
+        assert_eq!(code_owners.owners_of("./README.md"), ["@org/core"]);
+        assert_eq!(code_owners.owners_of("./docs/guide.md"), ["@org/docs"]);
+        assert_eq!(
+            code_owners.owners_of("./src/main.rs"),
+            ["@rustacean", "@org/core"]
+        );
+        assert!(code_owners.is_owned_by("./docs/guide.md", &["@ORG/docs".to_string()]));
+    }
+}
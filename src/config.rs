@@ -0,0 +1,324 @@
+use crate::storage;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+/// Defaults for the options of a search, from the `config` file in the config directory, as
+/// written by `fuzzy-ls setup`. Options given on the command line take precedence.
+///
+/// Each line of the file sets an option, e.g. `editor = code`, `terminal = alacritty -e`,
+/// `theme = dark`, `exclude = lock log` or `learn = false`. Lines starting with `#` are comments.
+///
+/// The `on_open`, `on_results` and `on_no_results` options are shell commands run on those events,
+/// e.g. `on_open = echo "$FUZZY_LS_FILES" >> ~/opened.log`; see [`crate::hook`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// The editor results are opened in, as passed to -d.
+    pub editor: Option<String>,
+    /// The terminal the editor is opened in, with the arguments preceding the editor command.
+    pub terminal: Option<String>,
+    /// The color theme of the UI: auto, dark or light.
+    pub theme: Option<String>,
+    /// The extensions left out of every search, as passed to -e.
+    pub exclude: Vec<String>,
+    /// Whether results are ranked by the picks learned in each project.
+    pub learn: Option<bool>,
+    /// The command run when results are opened.
+    pub on_open: Option<String>,
+    /// The command run when a search finds results.
+    pub on_results: Option<String>,
+    /// The command run when a search finds nothing.
+    pub on_no_results: Option<String>,
+}
+
+impl Config {
+    fn path() -> Result<PathBuf, String> {
+        Ok(storage::config_dir()?.join("config"))
+    }
+
+    /// Reads the config file of the config directory. Without one, or without a config directory,
+    /// nothing is configured.
+    pub fn load() -> Result<Config, String> {
+        let Ok(path) = Config::path() else {
+            return Ok(Config::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                Config::parse(&contents).map_err(|error| format!("{}: {}", path.display(), error))
+            }
+            Err(_) => Ok(Config::default()),
+        }
+    }
+
+    fn parse(contents: &str) -> Result<Config, String> {
+        let mut config = Config::default();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(format!("The option {} has no value.", line))?;
+            let value = value.trim().to_string();
+            match key.trim() {
+                "editor" => config.editor = Some(value),
+                "terminal" => config.terminal = Some(value),
+                "theme" => config.theme = Some(parse_theme(&value)?),
+                "exclude" => {
+                    config.exclude = value.split_whitespace().map(str::to_string).collect()
+                }
+                "learn" => config.learn = Some(parse_bool(&value)?),
+                "on_open" => config.on_open = Some(value),
+                "on_results" => config.on_results = Some(value),
+                "on_no_results" => config.on_no_results = Some(value),
+                key => return Err(format!("There is no option named {}.", key)),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Renders the config in the format of the config file.
+    fn render(&self) -> String {
+        let mut contents = String::from("# Written by fuzzy-ls setup.\n");
+        if let Some(editor) = &self.editor {
+            contents.push_str(&format!("editor = {}\n", editor));
+        }
+        if let Some(terminal) = &self.terminal {
+            contents.push_str(&format!("terminal = {}\n", terminal));
+        }
+        if let Some(theme) = &self.theme {
+            contents.push_str(&format!("theme = {}\n", theme));
+        }
+        if !self.exclude.is_empty() {
+            contents.push_str(&format!("exclude = {}\n", self.exclude.join(" ")));
+        }
+        if let Some(learn) = self.learn {
+            contents.push_str(&format!("learn = {}\n", learn));
+        }
+        for (key, hook) in [
+            ("on_open", &self.on_open),
+            ("on_results", &self.on_results),
+            ("on_no_results", &self.on_no_results),
+        ] {
+            if let Some(hook) = hook {
+                contents.push_str(&format!("{} = {}\n", key, hook));
+            }
+        }
+        contents
+    }
+
+    /// Writes the config file of the config directory, creating the directory if needed.
+    ///
+    /// # Returns
+    ///
+    /// The path of the config file, or an error message if it could not be written.
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let path = Config::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|error| format!("Failed to create {}: {}", parent.display(), error))?;
+        }
+        fs::write(&path, self.render())
+            .map_err(|error| format!("Failed to write {}: {}", path.display(), error))?;
+        Ok(path)
+    }
+
+    /// Asks for each option in turn, offering the current value, and returns the answers. Hooks
+    /// aren't asked for and are kept as they are.
+    ///
+    /// An empty answer keeps the value in brackets and `-` unsets it. Invalid answers are
+    /// reported and asked again.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Where the answers are read from, usually stdin.
+    /// * `output` - Where the questions are written to, usually stdout.
+    ///
+    /// # Returns
+    ///
+    /// The new config, or an error if the input ended before every question was answered.
+    pub fn setup(&self, input: &mut dyn BufRead, output: &mut dyn Write) -> Result<Config, String> {
+        let mut wizard = Wizard { input, output };
+        wizard.say(
+            "Press enter to keep the value in brackets, or enter - to unset it.\n\
+             Options given on the command line always take precedence.",
+        )?;
+        let editor = wizard.ask(
+            "Editor to open results in",
+            self.editor.clone(),
+            "nvim",
+            |answer| Ok(answer.to_string()),
+        )?;
+        let terminal = wizard.ask(
+            "Terminal to open the editor in, with the arguments before the editor, e.g. alacritty -e",
+            self.terminal.clone(),
+            "the platform's",
+            |answer| Ok(answer.to_string()),
+        )?;
+        let theme = wizard.ask(
+            "Theme: auto, dark or light",
+            self.theme.clone(),
+            "auto",
+            parse_theme,
+        )?;
+        let exclude = wizard.ask(
+            "Extensions to leave out of every search, separated by spaces, e.g. lock log",
+            (!self.exclude.is_empty()).then(|| self.exclude.clone()),
+            "none",
+            |answer| Ok(answer.split_whitespace().map(str::to_string).collect()),
+        )?;
+        let learn = wizard.ask(
+            "Rank the results you pick higher in later searches of the same project (yes or no)",
+            self.learn,
+            "yes",
+            parse_bool,
+        )?;
+        Ok(Config {
+            editor,
+            terminal,
+            theme,
+            exclude: exclude.unwrap_or_default(),
+            learn,
+            ..self.clone()
+        })
+    }
+}
+
+/// The questions and answers of [`Config::setup`].
+struct Wizard<'a> {
+    input: &'a mut dyn BufRead,
+    output: &'a mut dyn Write,
+}
+
+impl Wizard<'_> {
+    fn say(&mut self, text: &str) -> Result<(), String> {
+        writeln!(self.output, "{}", text).map_err(|error| error.to_string())
+    }
+
+    fn ask<T: Clone + Describe>(
+        &mut self,
+        question: &str,
+        current: Option<T>,
+        unset: &str,
+        parse: impl Fn(&str) -> Result<T, String>,
+    ) -> Result<Option<T>, String> {
+        let shown = current
+            .as_ref()
+            .map_or(unset.to_string(), Describe::describe);
+        loop {
+            write!(self.output, "{} [{}]: ", question, shown).map_err(|error| error.to_string())?;
+            self.output.flush().map_err(|error| error.to_string())?;
+            let mut answer = String::new();
+            let read = self
+                .input
+                .read_line(&mut answer)
+                .map_err(|error| error.to_string())?;
+            if read == 0 {
+                return Err("The setup was cancelled, so nothing was written.".to_string());
+            }
+            match answer.trim() {
+                "" => return Ok(current),
+                "-" => return Ok(None),
+                answer => match parse(answer) {
+                    Ok(value) => return Ok(Some(value)),
+                    Err(error) => self.say(&error)?,
+                },
+            }
+        }
+    }
+}
+
+/// How an answer is shown in brackets when asked again.
+trait Describe {
+    fn describe(&self) -> String;
+}
+
+impl Describe for String {
+    fn describe(&self) -> String {
+        self.clone()
+    }
+}
+
+impl Describe for Vec<String> {
+    fn describe(&self) -> String {
+        self.join(" ")
+    }
+}
+
+impl Describe for bool {
+    fn describe(&self) -> String {
+        if *self { "yes" } else { "no" }.to_string()
+    }
+}
+
+fn parse_theme(value: &str) -> Result<String, String> {
+    let theme = value.to_ascii_lowercase();
+    match theme.as_str() {
+        "auto" | "dark" | "light" => Ok(theme),
+        _ => Err(format!(
+            "{} is not a theme; use auto, dark or light.",
+            value
+        )),
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "y" => Ok(true),
+        "false" | "no" | "n" => Ok(false),
+        _ => Err(format!("{} is neither yes nor no.", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        let config = Config::parse(
+            "# mine\neditor = code --wait\ntheme = Light\nexclude = lock  log\nlearn = no\n\
+             on_no_results = notify-send \"$FUZZY_LS_QUERY\" = nothing\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                editor: Some("code --wait".to_string()),
+                terminal: None,
+                theme: Some("light".to_string()),
+                exclude: vec!["lock".to_string(), "log".to_string()],
+                learn: Some(false),
+                on_no_results: Some("notify-send \"$FUZZY_LS_QUERY\" = nothing".to_string()),
+                ..Config::default()
+            }
+        );
+        assert_eq!(Config::parse(&config.render()), Ok(config.clone()));
+        assert_eq!(
+            Config::parse("colour = dark\n"),
+            Err("There is no option named colour.".to_string())
+        );
+
+        let mut input = "\nalacritty -e\nblue\ndark\n-\nyes\n".as_bytes();
+        let mut output = Vec::new();
+        let answered = config.setup(&mut input, &mut output).unwrap();
+        assert_eq!(
+            answered,
+            Config {
+                editor: Some("code --wait".to_string()),
+                terminal: Some("alacritty -e".to_string()),
+                theme: Some("dark".to_string()),
+                exclude: Vec::new(),
+                learn: Some(true),
+                on_no_results: config.on_no_results.clone(),
+                ..Config::default()
+            }
+        );
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Editor to open results in [code --wait]: "));
+        assert!(output.contains("blue is not a theme; use auto, dark or light."));
+        assert!(config
+            .setup(&mut "vim\n".as_bytes(), &mut Vec::new())
+            .is_err());
+    }
+}
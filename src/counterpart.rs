@@ -0,0 +1,337 @@
+use crate::storage;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path};
+
+/// The test patterns used for extensions the counterparts file doesn't list, as lines of it.
+const DEFAULT_PATTERNS: &str = "\
+rs {}_test tests/{}
+go {}_test
+py test_{} {}_test
+js {}.test {}.spec
+jsx {}.test {}.spec
+ts {}.test {}.spec
+tsx {}.test {}.spec
+java {}Test
+kt {}Test
+c {}_test
+cc {}_test
+cpp {}_test
+rb {}_spec
+";
+
+/// How the name of a test file is made from the name of the source file it tests, e.g. `{}_test`,
+/// optionally within a directory of tests, e.g. `tests/{}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TestPattern {
+    /// The directory the test files are in, if they are kept apart from the sources.
+    directory: Option<String>,
+    prefix: String,
+    suffix: String,
+}
+
+impl TestPattern {
+    fn parse(pattern: &str) -> Result<TestPattern, String> {
+        let (directory, name) = match pattern.rsplit_once('/') {
+            Some((directory, name)) => (Some(directory.to_string()), name),
+            None => (None, pattern),
+        };
+        let (prefix, suffix) = name
+            .split_once("{}")
+            .ok_or(format!("The test pattern {} has no {{}}.", pattern))?;
+        Ok(TestPattern {
+            directory,
+            prefix: prefix.to_string(),
+            suffix: suffix.to_string(),
+        })
+    }
+
+    /// Returns the name of the source file a test file is named after, if it fits the pattern.
+    fn source_name<'a>(&self, test_name: &'a str, full_path: &str) -> Option<&'a str> {
+        let name = test_name
+            .strip_prefix(self.prefix.as_str())?
+            .strip_suffix(self.suffix.as_str())?;
+        (!name.is_empty() && self.fits_directory(full_path)).then_some(name)
+    }
+
+    /// Whether a file is in the directory of tests of the pattern, if it has one.
+    fn fits_directory(&self, full_path: &str) -> bool {
+        self.directory
+            .as_ref()
+            .is_none_or(|directory| in_directory(full_path, directory))
+    }
+}
+
+/// Whether the parent directories of a file end with a directory, e.g. `tests` or `spec/unit`.
+fn in_directory(full_path: &str, directory: &str) -> bool {
+    Path::new(full_path)
+        .parent()
+        .is_some_and(|parent| parent.ends_with(directory))
+}
+
+/// The test patterns of each extension, from the `counterparts` file in the config directory and
+/// the defaults.
+///
+/// Each line of the file lists an extension and the patterns of its test files, `{}` standing for
+/// the name of the source file, e.g. `rs {}_test tests/{}`. Lines starting with `#` are comments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CounterpartPatterns {
+    patterns: BTreeMap<String, Vec<TestPattern>>,
+}
+
+impl Default for CounterpartPatterns {
+    fn default() -> Self {
+        CounterpartPatterns::parse(DEFAULT_PATTERNS).expect("the default test patterns are valid")
+    }
+}
+
+/// A file a counterpart is searched for by: its name, and what a candidate with the name needs to
+/// be the counterpart.
+#[derive(Debug, PartialEq, Eq)]
+struct CounterpartQuery {
+    /// The name of the counterpart, without its extension.
+    name: String,
+    /// The directory of tests the counterpart has to be in, when looking for a test.
+    directory: Option<String>,
+    /// Whether the counterpart is a source file, which can't be a test itself.
+    source: bool,
+}
+
+impl CounterpartPatterns {
+    /// Loads the patterns of the `counterparts` file in the config directory over the defaults.
+    /// A missing file leaves the defaults.
+    pub fn load() -> Result<CounterpartPatterns, String> {
+        let mut patterns = CounterpartPatterns::default();
+        let Ok(dir) = storage::config_dir() else {
+            return Ok(patterns);
+        };
+        let path = dir.join("counterparts");
+        if let Ok(contents) = fs::read_to_string(&path) {
+            let configured = CounterpartPatterns::parse(&contents)
+                .map_err(|error| format!("{}: {}", path.display(), error))?;
+            patterns.patterns.extend(configured.patterns);
+        }
+        Ok(patterns)
+    }
+
+    fn parse(contents: &str) -> Result<CounterpartPatterns, String> {
+        let mut patterns = BTreeMap::new();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let extension = fields.next().unwrap_or_default().trim_start_matches('.');
+            let test_patterns = fields
+                .map(TestPattern::parse)
+                .collect::<Result<Vec<_>, _>>()?;
+            patterns.insert(extension.to_string(), test_patterns);
+        }
+        Ok(CounterpartPatterns { patterns })
+    }
+
+    /// Returns what the counterparts of a file are searched by: its tests for a source file, or the
+    /// sources it tests for a test file.
+    ///
+    /// # Arguments
+    ///
+    /// * `full_path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// The counterparts in the order of the patterns, or none for extensions without patterns.
+    fn queries(&self, full_path: &str) -> Vec<CounterpartQuery> {
+        let Some((name, patterns)) = self.patterns_of(full_path) else {
+            return Vec::new();
+        };
+        let sources: Vec<CounterpartQuery> = patterns
+            .iter()
+            .filter_map(|pattern| {
+                Some(CounterpartQuery {
+                    name: pattern.source_name(&name, full_path)?.to_string(),
+                    directory: None,
+                    source: true,
+                })
+            })
+            .collect();
+        if !sources.is_empty() {
+            return sources;
+        }
+        patterns
+            .iter()
+            .map(|pattern| CounterpartQuery {
+                name: format!("{}{}{}", pattern.prefix, name, pattern.suffix),
+                directory: pattern.directory.clone(),
+                source: false,
+            })
+            .collect()
+    }
+
+    /// Returns the name of a file without its extension and the test patterns of its extension.
+    fn patterns_of(&self, full_path: &str) -> Option<(String, &[TestPattern])> {
+        let path = Path::new(full_path);
+        let extension = path.extension()?.to_string_lossy();
+        let patterns = self.patterns.get(extension.as_ref())?;
+        Some((path.file_stem()?.to_string_lossy().into_owned(), patterns))
+    }
+
+    /// Whether a file is named like a test by the patterns of its extension.
+    fn is_test(&self, full_path: &str) -> bool {
+        self.patterns_of(full_path).is_some_and(|(name, patterns)| {
+            patterns
+                .iter()
+                .any(|pattern| pattern.source_name(&name, full_path).is_some())
+        })
+    }
+
+    /// Whether a candidate found by the name of a query is the counterpart of a file.
+    fn accepts(&self, query: &CounterpartQuery, full_path: &str, candidate: &str) -> bool {
+        candidate != full_path
+            && Path::new(candidate).extension() == Path::new(full_path).extension()
+            && query
+                .directory
+                .as_ref()
+                .is_none_or(|directory| in_directory(candidate, directory))
+            && !(query.source && self.is_test(candidate))
+    }
+}
+
+/// Searches the candidates for a name, returning its results or why the search failed.
+pub type NameSearch<'a> = dyn Fn(&str) -> Result<Vec<(u32, String, String)>, String> + 'a;
+
+/// The counterparts of a file and the name they were searched by.
+pub struct Counterparts {
+    /// The name of the first counterpart searched for, e.g. `search_test` for `search.rs`.
+    pub query: String,
+    /// The counterparts, the ones closest to the file first.
+    pub potential_hits: Vec<(u32, String, String)>,
+}
+
+/// Finds the counterparts of a file among the candidates: its tests for a source file, or the
+/// sources it tests for a test file.
+///
+/// # Arguments
+///
+/// * `patterns` - The test patterns of each extension.
+/// * `full_path` - The path of the file.
+/// * `search` - Searches the candidates for a name, like the tabs of the results UI.
+///
+/// # Returns
+///
+/// The counterparts, or `None` if the extension of the file has no test patterns.
+pub fn find_counterparts(
+    patterns: &CounterpartPatterns,
+    full_path: &str,
+    search: &NameSearch,
+) -> Result<Option<Counterparts>, String> {
+    let queries = patterns.queries(full_path);
+    let Some(first) = queries.first() else {
+        return Ok(None);
+    };
+    let mut counterparts: Vec<(u32, String, String)> = Vec::new();
+    for query in &queries {
+        for hit in search(&query.name)? {
+            let found = counterparts
+                .iter()
+                .any(|counterpart| counterpart.2 == hit.2);
+            if !found && patterns.accepts(query, full_path, &hit.2) {
+                counterparts.push(hit);
+            }
+        }
+    }
+    // The counterparts sharing the most directories with the file come first.
+    counterparts.sort_by_key(|counterpart| {
+        std::cmp::Reverse(shared_directories(full_path, &counterpart.2))
+    });
+    Ok(Some(Counterparts {
+        query: first.name.clone(),
+        potential_hits: counterparts,
+    }))
+}
+
+/// Counts the leading directories two paths have in common.
+fn shared_directories(path: &str, other: &str) -> usize {
+    directories(path)
+        .zip(directories(other))
+        .take_while(|(component, other_component)| component == other_component)
+        .count()
+}
+
+/// The directories a file is in, from the outermost.
+fn directories(full_path: &str) -> impl Iterator<Item = Component<'_>> {
+    Path::new(full_path)
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_counterparts() {
+        let patterns = CounterpartPatterns::default();
+        let candidates = [
+            "./src/search.rs",
+            "./src/search_test.rs",
+            "./tests/search.rs",
+            "./other/src/search_test.rs",
+            "./src/search_test.go",
+        ];
+        let search = |name: &str| -> Result<Vec<(u32, String, String)>, String> {
+            Ok(candidates
+                .iter()
+                .filter(|path| Path::new(path).file_stem().unwrap() == name)
+                .map(|path| (0, name.to_string(), path.to_string()))
+                .collect())
+        };
+        let found = |full_path: &str| {
+            let counterparts = find_counterparts(&patterns, full_path, &search)
+                .unwrap()
+                .unwrap();
+            let paths: Vec<String> = counterparts
+                .potential_hits
+                .into_iter()
+                .map(|hit| hit.2)
+                .collect();
+            (counterparts.query, paths)
+        };
+        assert_eq!(
+            found("./src/search.rs"),
+            (
+                "search_test".to_string(),
+                vec![
+                    "./src/search_test.rs".to_string(),
+                    "./other/src/search_test.rs".to_string(),
+                    "./tests/search.rs".to_string(),
+                ]
+            )
+        );
+        assert_eq!(
+            found("./src/search_test.rs"),
+            ("search".to_string(), vec!["./src/search.rs".to_string()])
+        );
+        assert_eq!(
+            found("./tests/search.rs"),
+            ("search".to_string(), vec!["./src/search.rs".to_string()])
+        );
+        assert!(find_counterparts(&patterns, "./README.md", &search)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_parse_counterpart_patterns() {
+        let patterns = CounterpartPatterns::parse("# Specs\n.rb spec/{}_spec\n").unwrap();
+        assert_eq!(
+            patterns.queries("./lib/parser.rb"),
+            vec![CounterpartQuery {
+                name: "parser_spec".to_string(),
+                directory: Some("spec".to_string()),
+                source: false,
+            }]
+        );
+        assert!(CounterpartPatterns::parse("rs tests/").is_err());
+    }
+}
@@ -0,0 +1,284 @@
+use crate::adjust::ScoreAdjustments;
+use crate::config::Config;
+use crate::editor::Platform;
+use crate::plugin::{self, ScorerPlugin};
+use crate::scope::Scopes;
+use crate::storage;
+use std::env;
+use std::path::PathBuf;
+
+/// The outcome of a single check.
+enum Status {
+    Ok,
+    /// Something works in a degraded way or is not set up, which only matters for some features.
+    Warn,
+    /// Something fuzzy-ls needs is broken.
+    Fail,
+}
+
+/// A diagnosis of one subsystem, with a hint on how to fix it when it isn't healthy.
+struct Diagnosis {
+    subsystem: &'static str,
+    status: Status,
+    message: String,
+    fix: Option<String>,
+}
+
+impl Diagnosis {
+    fn ok(subsystem: &'static str, message: String) -> Diagnosis {
+        Diagnosis {
+            subsystem,
+            status: Status::Ok,
+            message,
+            fix: None,
+        }
+    }
+
+    fn problem(subsystem: &'static str, status: Status, message: String, fix: &str) -> Diagnosis {
+        Diagnosis {
+            subsystem,
+            status,
+            message,
+            fix: Some(fix.to_string()),
+        }
+    }
+}
+
+/// Finds a program on the `PATH`, like `which`. Programs given as a path are only checked to exist.
+fn find_on_path(program: &str) -> Option<PathBuf> {
+    if program.contains(std::path::is_separator) {
+        let program = PathBuf::from(program);
+        return program.is_file().then_some(program);
+    }
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path)
+        .map(|dir| dir.join(format!("{}{}", program, env::consts::EXE_SUFFIX)))
+        .find(|candidate| candidate.is_file())
+}
+
+fn check_editor(editor: &str) -> Diagnosis {
+    match find_on_path(editor) {
+        Some(path) => Diagnosis::ok("editor", format!("{} found at {}", editor, path.display())),
+        None => Diagnosis::problem(
+            "editor",
+            Status::Fail,
+            format!("{} is not on the PATH", editor),
+            "Install it, or pick another editor with -d, e.g. `fuzzy-ls <query> -d code`.",
+        ),
+    }
+}
+
+fn check_terminal() -> Diagnosis {
+    let terminal_program = Platform::current().terminal_program();
+    match find_on_path(terminal_program) {
+        Some(path) => Diagnosis::ok(
+            "terminal",
+            format!(
+                "files are opened through {} at {}",
+                terminal_program,
+                path.display()
+            ),
+        ),
+        None => Diagnosis::problem(
+            "terminal",
+            Status::Fail,
+            format!(
+                "{} is not on the PATH, so files can't be opened in a new terminal window",
+                terminal_program
+            ),
+            &format!("Install {}.", terminal_program),
+        ),
+    }
+}
+
+#[cfg(feature = "tui")]
+fn check_raw_mode() -> Diagnosis {
+    use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+    match enable_raw_mode().and_then(|_| disable_raw_mode()) {
+        Ok(()) => Diagnosis::ok("raw mode", "the interactive UI can run".to_string()),
+        Err(error) => Diagnosis::problem(
+            "raw mode",
+            Status::Warn,
+            format!("the terminal can't enter raw mode: {}", error),
+            "Run fuzzy-ls from an interactive terminal, or use --prompt.",
+        ),
+    }
+}
+
+#[cfg(not(feature = "tui"))]
+fn check_raw_mode() -> Diagnosis {
+    Diagnosis::ok(
+        "raw mode",
+        "not needed: this build has no interactive UI and always uses the numbered prompt"
+            .to_string(),
+    )
+}
+
+fn check_data_dir() -> Diagnosis {
+    let created = storage::data_dir().and_then(|dir| {
+        std::fs::create_dir_all(&dir)
+            .map(|_| dir.clone())
+            .map_err(|error| format!("Failed to create {}: {}", dir.display(), error))
+    });
+    match created {
+        Ok(dir) => Diagnosis::ok(
+            "data",
+            format!("ranking weights are stored in {}", dir.display()),
+        ),
+        Err(error) => Diagnosis::problem(
+            "data",
+            Status::Warn,
+            format!("ranking weights can't be stored: {}", error),
+            "Set XDG_DATA_HOME (or APPDATA on Windows) to a writable directory.",
+        ),
+    }
+}
+
+fn check_config() -> Diagnosis {
+    let dir = match plugin::plugins_dir() {
+        Ok(dir) => dir,
+        Err(error) => {
+            return Diagnosis::problem(
+                "config",
+                Status::Warn,
+                format!("no config directory: {}", error),
+                "Set XDG_CONFIG_HOME (or APPDATA on Windows).",
+            )
+        }
+    };
+    let plugins = plugin::available_plugins();
+    let broken: Vec<String> = plugins
+        .iter()
+        .filter_map(|name| ScorerPlugin::load(name).err())
+        .collect();
+    if broken.is_empty() {
+        Diagnosis::ok(
+            "config",
+            format!(
+                "{} scorer plugin(s) in {} load correctly",
+                plugins.len(),
+                dir.display()
+            ),
+        )
+    } else {
+        Diagnosis::problem(
+            "config",
+            Status::Fail,
+            broken.join("; "),
+            "Rebuild the plugins against the current plugin ABI or remove them from the plugins directory.",
+        )
+    }
+}
+
+/// Parses the files of the config directory that every search reads, which fail the search when a
+/// line is invalid.
+fn check_config_files() -> Diagnosis {
+    #[cfg(feature = "tui")]
+    let counterparts = crate::counterpart::CounterpartPatterns::load().err();
+    #[cfg(not(feature = "tui"))]
+    let counterparts = None;
+    let errors: Vec<String> = [
+        Config::load().err(),
+        Scopes::load().err(),
+        ScoreAdjustments::load().err(),
+        counterparts,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if errors.is_empty() {
+        Diagnosis::ok(
+            "config files",
+            "config, scopes, adjustments and counterparts parse correctly".to_string(),
+        )
+    } else {
+        Diagnosis::problem(
+            "config files",
+            Status::Fail,
+            errors.join("; "),
+            "Fix or remove the lines named, or run fuzzy-ls setup to rewrite the config file.",
+        )
+    }
+}
+
+fn check_git() -> Diagnosis {
+    match find_on_path("git") {
+        Some(path) => Diagnosis::ok("git", format!("found at {}", path.display())),
+        None => Diagnosis::problem(
+            "git",
+            Status::Warn,
+            "git is not on the PATH".to_string(),
+            "Install git to use --changed-since and the git sources.",
+        ),
+    }
+}
+
+fn check_index() -> Diagnosis {
+    Diagnosis::ok(
+        "index",
+        "not used: candidates are collected from their sources on every run".to_string(),
+    )
+}
+
+fn check_clipboard() -> Diagnosis {
+    let tools = ["pbcopy", "wl-copy", "xclip", "xsel", "clip"];
+    let found: Vec<&str> = tools
+        .into_iter()
+        .filter(|tool| find_on_path(tool).is_some())
+        .collect();
+    if found.is_empty() {
+        Diagnosis::problem(
+            "clipboard",
+            Status::Warn,
+            "no clipboard tool found".to_string(),
+            "fuzzy-ls doesn't use the clipboard yet; install wl-copy, xclip or xsel to pipe results into it.",
+        )
+    } else {
+        Diagnosis::ok(
+            "clipboard",
+            format!("available through {}", found.join(", ")),
+        )
+    }
+}
+
+/// Checks the environment fuzzy-ls runs in and prints a diagnosis for each subsystem.
+///
+/// # Arguments
+///
+/// * `editor` - The editor command files are opened with.
+///
+/// # Returns
+///
+/// An error message if any subsystem fuzzy-ls needs is broken.
+pub fn run(editor: &str) -> Result<(), String> {
+    let diagnoses = [
+        check_editor(editor),
+        check_terminal(),
+        check_raw_mode(),
+        check_data_dir(),
+        check_config(),
+        check_config_files(),
+        check_git(),
+        check_index(),
+        check_clipboard(),
+    ];
+    let mut failures = 0;
+    for diagnosis in &diagnoses {
+        let label = match diagnosis.status {
+            Status::Ok => "\x1b[32m[ok]\x1b[0m  ",
+            Status::Warn => "\x1b[33m[warn]\x1b[0m",
+            Status::Fail => {
+                failures += 1;
+                "\x1b[31m[fail]\x1b[0m"
+            }
+        };
+        println!("{} {}: {}", label, diagnosis.subsystem, diagnosis.message);
+        if let Some(fix) = &diagnosis.fix {
+            println!("       fix: {}", fix);
+        }
+    }
+    match failures {
+        0 => Ok(()),
+        _ => Err(format!("{} problem(s) found", failures)),
+    }
+}
@@ -1,80 +1,525 @@
-use std::io::stdin;
+use fuzzy_ls::search;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{stdin, Write};
+use std::path::{Component, Path};
 use std::process::Command;
-/// Opens files in a new terminal using the specified editor command.
+/// What the user asked for at the numbered prompt.
+#[derive(Debug, PartialEq)]
+pub enum PromptAction {
+    /// Open the results at these indices, in order and without duplicates.
+    Open(Vec<usize>),
+    /// Leave the prompt.
+    Quit,
+}
+
+/// Parses a selection such as `1,3-5`, `a` for all results or `q` to quit.
+///
+/// # Arguments
+///
+/// * `input` - The line entered by the user. An empty line quits.
+/// * `num_results` - The number of results the numbers refer to.
+///
+/// # Returns
+///
+/// The requested action with zero-based indices, or an error message describing the invalid part.
+pub fn parse_selection(input: &str, num_results: usize) -> Result<PromptAction, String> {
+    let input = input.trim();
+    match input {
+        "" | "q" | "Q" => return Ok(PromptAction::Quit),
+        "a" | "A" => return Ok(PromptAction::Open((0..num_results).collect())),
+        _ => {}
+    }
+    let parse_number = |number: &str| -> Result<usize, String> {
+        match number.trim().parse::<usize>() {
+            Ok(number) if number >= 1 && number <= num_results => Ok(number),
+            _ => Err(format!(
+                "Invalid file number {}. Expected 1-{}.",
+                number.trim(),
+                num_results
+            )),
+        }
+    };
+    let mut indices: Vec<usize> = Vec::new();
+    for part in input.split(',').filter(|part| !part.trim().is_empty()) {
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (parse_number(first)?, parse_number(last)?),
+            None => (parse_number(part)?, parse_number(part)?),
+        };
+        if first > last {
+            return Err(format!("Invalid range {}.", part.trim()));
+        }
+        for number in first..=last {
+            if !indices.contains(&(number - 1)) {
+                indices.push(number - 1);
+            }
+        }
+    }
+    Ok(PromptAction::Open(indices))
+}
+
+/// Opens the selected results together in a new terminal using the specified editor command.
 ///
 /// # Arguments
 ///
+/// * `launcher` - Spawns the terminal running the editor.
+/// * `terminal` - When provided, the terminal to open instead of the platform's, with the
+///   arguments preceding the editor command, e.g. `alacritty -e`.
 /// * `default_editor_command` - The command to open the editor.
-/// * `file_number` - The file number to open.
-/// * `potential_hits` - A vector of tuples containing file information (index, name, full path).
+/// * `potential_hits` - A slice of tuples containing file information (score, name, full path).
+/// * `selection` - The indices of the results to open.
+pub fn open_selection(
+    launcher: &dyn TerminalLauncher,
+    terminal: Option<&str>,
+    default_editor_command: &str,
+    potential_hits: &[(u32, String, String)],
+    selection: &[usize],
+) -> Result<(), std::io::Error> {
+    let full_paths: Vec<&str> = selection
+        .iter()
+        .map(|index| potential_hits[*index].2.as_str())
+        .collect();
+    launcher.launch(&match terminal {
+        Some(terminal) => {
+            configured_terminal_command(terminal, default_editor_command, &full_paths)
+        }
+        None => terminal_command(Platform::current(), default_editor_command, &full_paths),
+    })
+}
+
+/// Opens the selected results together in the editor in the current terminal and waits for it to
+/// exit.
+///
+/// # Arguments
+///
+/// * `launcher` - Runs the editor.
+/// * `default_editor_command` - The command to open the editor.
+/// * `potential_hits` - A slice of tuples containing file information (score, name, full path).
+/// * `selection` - The indices of the results to open.
 ///
 /// # Returns
 ///
-/// * `Result<(), Box<dyn std::error::Error>>` - Returns Ok(()) if successful, otherwise returns an error.
+/// * `Result<i32, std::io::Error>` - Returns the exit code of the editor, otherwise returns an error.
+pub fn edit_selection(
+    launcher: &dyn TerminalLauncher,
+    default_editor_command: &str,
+    potential_hits: &[(u32, String, String)],
+    selection: &[usize],
+) -> Result<i32, std::io::Error> {
+    launcher.run(&TerminalCommand {
+        program: default_editor_command.to_string(),
+        args: selection
+            .iter()
+            .map(|index| potential_hits[*index].2.clone())
+            .collect(),
+    })
+}
+
+/// Creates a file named after a query that matched nothing, e.g. `widget.rs`, or at the path of
+/// a query with directories, e.g. `src/ui/widget.rs`, creating the missing directories.
 ///
-/// ``
-pub fn experimental_open_files(
-    default_editor_command: String,
-    file_number: usize,
-    potential_hits: Vec<(u32, String, String)>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    println!("Enter file number to open the file in an editor. Press Enter to exit.");
-    let mut input = String::new();
-    stdin()
-        .read_line(&mut input)
-        .expect("Failed to read the input.");
-
-    let index_number: usize = match input.trim().parse() {
-        Ok(index) => index,
-        Err(_) => return Ok(()),
+/// The file starts out with the template of its extension when there is one, e.g. `templates/rs`
+/// for `widget.rs`, with every `{{name}}` in it replaced by the name of the file without its
+/// extension. Otherwise the file is empty.
+///
+/// # Arguments
+///
+/// * `directory` - The directory the file is created in.
+/// * `query` - The name or relative path of the file.
+/// * `templates` - When provided, the directory holding a template per extension.
+///
+/// # Returns
+///
+/// The path of the created file, or an error if the query isn't a relative path or the file
+/// already exists.
+pub fn create_file(
+    directory: &Path,
+    query: &str,
+    templates: Option<&Path>,
+) -> Result<String, String> {
+    let relative_path = search::normalize_separators(query);
+    let relative_path = Path::new(&relative_path);
+    let is_relative = relative_path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !is_relative || relative_path.file_name().is_none() {
+        return Err(format!("{} is not a file name or a relative path.", query));
+    }
+    let template = match (templates, relative_path.extension()) {
+        (Some(templates), Some(extension)) => fs::read_to_string(templates.join(extension)).ok(),
+        _ => None,
     };
-    if index_number > 0 && index_number <= file_number {
-        let (_, _, full_path) = &potential_hits[index_number - 1];
-        open_in_new_terminal(&default_editor_command, &[full_path])
-            .expect("Failed to open file in the editor.");
-    } else {
-        println!("Invalid file number.");
-    }
-    return Ok(());
+    let full_path = directory.join(relative_path);
+    if let Some(parent) = full_path.parent() {
+        fs::create_dir_all(parent).map_err(|error| format!("{}: {}", parent.display(), error))?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&full_path)
+        .map_err(|error| format!("{}: {}", full_path.display(), error))?;
+    if let Some(template) = template {
+        let name = relative_path
+            .file_stem()
+            .map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+        file.write_all(template.replace("{{name}}", &name).as_bytes())
+            .map_err(|error| format!("{}: {}", full_path.display(), error))?;
+    }
+    Ok(full_path.to_string_lossy().into_owned())
+}
+
+/// Lists the results with their numbers and prompts for the ones to open in an editor.
+///
+/// # Arguments
+///
+/// * `default_editor_command` - The command to open the editor.
+/// * `terminal` - When provided, the terminal to open the editor in instead of the platform's.
+/// * `potential_hits` - A slice of tuples containing file information (score, name, full path).
+/// * `source_labels` - When provided, the sources each result was found in, keyed by full path.
+/// * `wait` - Run the editor in the current terminal and wait for it to exit.
+///
+/// # Returns
+///
+/// * `Result<(Vec<usize>, Option<i32>), Box<dyn std::error::Error>>` - Returns the indices of the opened files and, when waiting, the exit code of the editor, otherwise returns an error.
+pub fn prompt_results(
+    default_editor_command: String,
+    terminal: Option<&str>,
+    potential_hits: &[(u32, String, String)],
+    source_labels: Option<&BTreeMap<String, String>>,
+    wait: bool,
+) -> Result<(Vec<usize>, Option<i32>), Box<dyn std::error::Error>> {
+    if potential_hits.is_empty() {
+        println!("No files found.");
+        return Ok((Vec::new(), None));
+    }
+    println!("{} files found:", potential_hits.len());
+    for (index, (score, file_name, full_path)) in potential_hits.iter().enumerate() {
+        let file_number = index + 1;
+        let full_path = match source_labels.and_then(|labels| labels.get(full_path)) {
+            Some(label) => format!("{} [{}]", full_path, label),
+            None => full_path.clone(),
+        };
+        if *score == 0 {
+            println!(
+                "{}. \x1b[32m{}\x1b[0m - {}",
+                file_number, file_name, full_path
+            ); // Green color for score 0
+        } else {
+            println!(
+                "{}. \x1b[34m{}\x1b[0m - {}",
+                file_number, file_name, full_path
+            ); // Blue color for other scores
+        }
+    }
+    experimental_open_files(default_editor_command, terminal, potential_hits, wait)
 }
 
-/// Opens a command in a new terminal window.
+/// Prompts for results to open in a new terminal, or the current one when waiting, using the
+/// specified editor command.
+///
+/// The prompt accepts numbers and ranges such as `1,3-5`, `a` to open every result and `q` or an
+/// empty line to quit. Invalid input is reported and prompted for again.
 ///
 /// # Arguments
 ///
-/// * `command` - The command to run in the new terminal.
-/// * `args` - The arguments to pass to the command.
+/// * `default_editor_command` - The command to open the editor.
+/// * `terminal` - When provided, the terminal to open the editor in instead of the platform's.
+/// * `potential_hits` - A slice of tuples containing file information (score, name, full path).
+/// * `wait` - Run the editor in the current terminal and wait for it to exit.
 ///
 /// # Returns
 ///
-/// * `Result<(), std::io::Error>` - Returns Ok(()) if successful, otherwise returns an error.
+/// * `Result<(Vec<usize>, Option<i32>), Box<dyn std::error::Error>>` - Returns the indices of the opened files and, when waiting, the exit code of the editor, otherwise returns an error.
+pub fn experimental_open_files(
+    default_editor_command: String,
+    terminal: Option<&str>,
+    potential_hits: &[(u32, String, String)],
+    wait: bool,
+) -> Result<(Vec<usize>, Option<i32>), Box<dyn std::error::Error>> {
+    loop {
+        println!(
+            "Enter file numbers to open in an editor (e.g. 1,3-5), 'a' for all or 'q' to quit."
+        );
+        let mut input = String::new();
+        if stdin().read_line(&mut input)? == 0 {
+            return Ok((Vec::new(), None));
+        }
+        match parse_selection(&input, potential_hits.len()) {
+            Ok(PromptAction::Quit) => return Ok((Vec::new(), None)),
+            Ok(PromptAction::Open(selection)) if wait => {
+                let status = edit_selection(
+                    &SystemLauncher,
+                    &default_editor_command,
+                    potential_hits,
+                    &selection,
+                )?;
+                return Ok((selection, Some(status)));
+            }
+            Ok(PromptAction::Open(selection)) => {
+                open_selection(
+                    &SystemLauncher,
+                    terminal,
+                    &default_editor_command,
+                    potential_hits,
+                    &selection,
+                )?;
+                return Ok((selection, None));
+            }
+            Err(message) => println!("{}", message),
+        }
+    }
+}
+
+/// A platform with its own way of opening a new terminal window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Platform {
+    Windows,
+    Linux,
+    MacOs,
+}
+
+impl Platform {
+    /// The platform fuzzy-ls was built for.
+    pub fn current() -> Platform {
+        if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Linux
+        }
+    }
+
+    /// The program used to open a new terminal window on this platform.
+    pub fn terminal_program(self) -> &'static str {
+        match self {
+            Platform::Windows => "cmd",
+            Platform::Linux => "gnome-terminal", // Or "xterm", "konsole", etc.
+            Platform::MacOs => "open",
+        }
+    }
+}
+
+/// A program to spawn together with its arguments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TerminalCommand {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+/// Builds the command running `command` with `args` in a new terminal window.
 ///
 /// # Platform-specific behavior
 ///
 /// * On Windows, uses `cmd` with `/c start`.
 /// * On Linux, uses `gnome-terminal` with `--`.
 /// * On macOS, uses `open` with `-a Terminal`.
-fn open_in_new_terminal(command: &str, args: &[&str]) -> Result<(), std::io::Error> {
-    #[cfg(target_os = "windows")]
-    let terminal_cmd = "cmd";
-    #[cfg(target_os = "windows")]
-    let terminal_args = &["/c", "start", command];
+pub fn terminal_command(platform: Platform, command: &str, args: &[&str]) -> TerminalCommand {
+    let terminal_args: &[&str] = match platform {
+        Platform::Windows => &["/c", "start", command],
+        Platform::Linux => &["--", command], // Important: "--" separates terminal args from command args
+        Platform::MacOs => &["-a", "Terminal", command],
+    };
+    TerminalCommand {
+        program: platform.terminal_program().to_string(),
+        args: terminal_args
+            .iter()
+            .chain(args)
+            .map(|arg| arg.to_string())
+            .collect(),
+    }
+}
+
+/// Builds the command running `command` with `args` in a terminal given as a program followed by
+/// the arguments preceding the command, e.g. `alacritty -e` or `wezterm start --`.
+pub fn configured_terminal_command(
+    terminal: &str,
+    command: &str,
+    args: &[&str],
+) -> TerminalCommand {
+    let mut words = terminal.split_whitespace().map(str::to_string);
+    TerminalCommand {
+        program: words.next().unwrap_or_default(),
+        args: words
+            .chain([command].iter().chain(args).map(|arg| arg.to_string()))
+            .collect(),
+    }
+}
 
-    #[cfg(target_os = "linux")]
-    let terminal_cmd = "gnome-terminal"; // Or "xterm", "konsole", etc. - see below
-    #[cfg(target_os = "linux")]
-    let terminal_args = &["--", command]; // Important: "--" separates terminal args from command args
+/// Spawns terminal commands, so the launch logic can be exercised without opening windows.
+pub trait TerminalLauncher {
+    /// Spawns the command without waiting for it to finish.
+    fn launch(&self, command: &TerminalCommand) -> Result<(), std::io::Error>;
 
-    #[cfg(target_os = "macos")]
-    let terminal_cmd = "open";
-    #[cfg(target_os = "macos")]
-    let terminal_args = &["-a", "Terminal", command];
+    /// Runs the command attached to the current terminal and waits for it to exit.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<i32, std::io::Error>` - Returns the exit code of the command, otherwise returns an error.
+    fn run(&self, command: &TerminalCommand) -> Result<i32, std::io::Error>;
+}
+
+/// Launches terminal commands as new processes.
+pub struct SystemLauncher;
+
+impl TerminalLauncher for SystemLauncher {
+    fn launch(&self, command: &TerminalCommand) -> Result<(), std::io::Error> {
+        Command::new(&command.program).args(&command.args).spawn()?;
+        Ok(())
+    }
+
+    fn run(&self, command: &TerminalCommand) -> Result<i32, std::io::Error> {
+        let status = Command::new(&command.program)
+            .args(&command.args)
+            .status()?;
+        // A command killed by a signal has no exit code.
+        Ok(status.code().unwrap_or(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Records the commands it is asked to launch or run instead of spawning them.
+    #[derive(Default)]
+    struct MockLauncher {
+        launched: RefCell<Vec<TerminalCommand>>,
+        ran: RefCell<Vec<TerminalCommand>>,
+        exit_code: i32,
+    }
+
+    impl TerminalLauncher for MockLauncher {
+        fn launch(&self, command: &TerminalCommand) -> Result<(), std::io::Error> {
+            self.launched.borrow_mut().push(command.clone());
+            Ok(())
+        }
 
-    let mut cmd = Command::new(terminal_cmd);
-    cmd.args(terminal_args);
-    cmd.args(args); // Add any arguments to your command
+        fn run(&self, command: &TerminalCommand) -> Result<i32, std::io::Error> {
+            self.ran.borrow_mut().push(command.clone());
+            Ok(self.exit_code)
+        }
+    }
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
 
-    cmd.spawn()?; // Execute the command
+    #[test]
+    fn test_terminal_command() {
+        assert_eq!(
+            terminal_command(Platform::Windows, "nvim", &["a.rs", "b.rs"]),
+            TerminalCommand {
+                program: "cmd".to_string(),
+                args: strings(&["/c", "start", "nvim", "a.rs", "b.rs"]),
+            }
+        );
+        assert_eq!(
+            terminal_command(Platform::Linux, "nvim", &["a.rs"]),
+            TerminalCommand {
+                program: "gnome-terminal".to_string(),
+                args: strings(&["--", "nvim", "a.rs"]),
+            }
+        );
+        assert_eq!(
+            terminal_command(Platform::MacOs, "nvim", &[]),
+            TerminalCommand {
+                program: "open".to_string(),
+                args: strings(&["-a", "Terminal", "nvim"]),
+            }
+        );
+        assert_eq!(
+            configured_terminal_command("wezterm start --", "nvim", &["a.rs"]),
+            TerminalCommand {
+                program: "wezterm".to_string(),
+                args: strings(&["start", "--", "nvim", "a.rs"]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_open_selection() {
+        let launcher = MockLauncher::default();
+        let hits = vec![
+            (0, "a".to_string(), "./a.rs".to_string()),
+            (1, "b".to_string(), "./b.rs".to_string()),
+            (2, "c".to_string(), "./c.rs".to_string()),
+        ];
+        open_selection(&launcher, None, "vim", &hits, &[2, 0]).unwrap();
+        assert_eq!(
+            *launcher.launched.borrow(),
+            vec![terminal_command(
+                Platform::current(),
+                "vim",
+                &["./c.rs", "./a.rs"]
+            )]
+        );
+        assert!(launcher.ran.borrow().is_empty());
+    }
 
-    Ok(())
+    #[test]
+    fn test_edit_selection() {
+        let launcher = MockLauncher {
+            exit_code: 3,
+            ..MockLauncher::default()
+        };
+        let hits = vec![
+            (0, "a".to_string(), "./a.rs".to_string()),
+            (1, "b".to_string(), "./b.rs".to_string()),
+        ];
+        assert_eq!(edit_selection(&launcher, "vim", &hits, &[1]).unwrap(), 3);
+        assert_eq!(
+            *launcher.ran.borrow(),
+            vec![TerminalCommand {
+                program: "vim".to_string(),
+                args: strings(&["./b.rs"]),
+            }]
+        );
+        assert!(launcher.launched.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_create_file() {
+        let directory = std::env::temp_dir().join(format!("ffs-create-{}", std::process::id()));
+        let created = create_file(&directory, "src\\ui/widget.rs", None).unwrap();
+        assert!(Path::new(&created).is_file());
+        assert!(create_file(&directory, "src/ui/widget.rs", None).is_err());
+        let templates = directory.join("templates");
+        fs::create_dir_all(&templates).unwrap();
+        fs::write(templates.join("rs"), "//! The {{name}} module.\n").unwrap();
+        let created = create_file(&directory, "src/button.rs", Some(&templates)).unwrap();
+        let contents = fs::read_to_string(&created).unwrap();
+        let created = create_file(&directory, "src/button.md", Some(&templates)).unwrap();
+        let untemplated = fs::read_to_string(&created).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(contents, "//! The button module.\n");
+        assert_eq!(untemplated, "");
+        assert_eq!(
+            create_file(&directory, "../widget.rs", None),
+            Err("../widget.rs is not a file name or a relative path.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(
+            parse_selection("1,3-5", 6),
+            Ok(PromptAction::Open(vec![0, 2, 3, 4]))
+        );
+        assert_eq!(
+            parse_selection(" 2, 1-2 ", 3),
+            Ok(PromptAction::Open(vec![1, 0]))
+        );
+        assert_eq!(
+            parse_selection("a", 3),
+            Ok(PromptAction::Open(vec![0, 1, 2]))
+        );
+        assert_eq!(parse_selection("q", 3), Ok(PromptAction::Quit));
+        assert_eq!(parse_selection("\n", 3), Ok(PromptAction::Quit));
+        assert!(parse_selection("4", 3).is_err());
+        assert!(parse_selection("0", 3).is_err());
+        assert!(parse_selection("3-1", 3).is_err());
+        assert!(parse_selection("x", 3).is_err());
+    }
 }
@@ -0,0 +1,240 @@
+use clap::ValueEnum;
+
+/// Formats the results can be printed in, to load them into other tools or share them.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    /// `path:line:col: text` lines, as read by vim's `:cfile` and `:lfile`.
+    Quickfix,
+    /// A VS Code search editor (`.code-search`) listing the results.
+    Vscode,
+    /// A Markdown table of the results, for issues and pull request descriptions.
+    Markdown,
+    /// A standalone HTML page with a filter box and sortable columns.
+    Html,
+}
+
+/// Renders the results in the given format.
+///
+/// # Arguments
+///
+/// * `format` - The format to render.
+/// * `query` - The query the results were found for.
+/// * `potential_hits` - The results, in the order they are shown.
+pub fn render(
+    format: ExportFormat,
+    query: &str,
+    potential_hits: &[(u32, String, String)],
+) -> String {
+    match format {
+        ExportFormat::Quickfix => quickfix(potential_hits),
+        ExportFormat::Vscode => vscode_search(query, potential_hits),
+        ExportFormat::Markdown => markdown_table(potential_hits),
+        ExportFormat::Html => html_page(query, potential_hits),
+    }
+}
+
+/// Results point at the start of the file, since matches are on file names rather than contents.
+fn quickfix(potential_hits: &[(u32, String, String)]) -> String {
+    potential_hits
+        .iter()
+        .map(|(score, file_name, full_path)| {
+            format!("{}:1:1: {} (score {})\n", full_path, file_name, score)
+        })
+        .collect()
+}
+
+fn vscode_search(query: &str, potential_hits: &[(u32, String, String)]) -> String {
+    let mut contents = format!(
+        "# Query: {}\n\n{} results - {} files\n",
+        query,
+        potential_hits.len(),
+        potential_hits.len()
+    );
+    for (score, file_name, full_path) in potential_hits {
+        // The search editor resolves paths against the workspace folder.
+        let path = full_path.strip_prefix("./").unwrap_or(full_path);
+        contents.push_str(&format!(
+            "\n{}:\n  1: {} (score {})\n",
+            path, file_name, score
+        ));
+    }
+    contents
+}
+
+/// Formats a size in bytes with binary units, e.g. `1.5 KiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// The size of a file in bytes. Directories and files that can't be read have no meaningful size.
+pub fn file_size(full_path: &str) -> Option<u64> {
+    std::fs::metadata(full_path)
+        .ok()
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+}
+
+/// Escapes the characters Markdown would format, and the pipes that would end a table cell.
+fn markdown_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        if "\\`*_[]<>|".contains(character) {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+/// Wraps text in a code span, escaping only the pipes, which end table cells even in code spans.
+fn markdown_code(text: &str) -> String {
+    format!("`{}`", text.replace('|', "\\|"))
+}
+
+fn markdown_table(potential_hits: &[(u32, String, String)]) -> String {
+    let mut table = String::from("| Name | Path | Score | Size |\n| --- | --- | ---: | ---: |\n");
+    for (score, file_name, full_path) in potential_hits {
+        let size = file_size(full_path).map_or("-".to_string(), format_size);
+        table.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            markdown_text(file_name),
+            markdown_code(full_path),
+            score,
+            size
+        ));
+    }
+    table
+}
+
+/// Escapes the characters HTML would interpret.
+fn html_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Filters the rows by the text in the filter box and sorts them when a column header is clicked,
+/// by the `data-sort` value of the cells.
+const HTML_SCRIPT: &str = r#"
+const rows = Array.from(document.querySelectorAll("tbody tr"));
+document.getElementById("filter").addEventListener("input", (event) => {
+  const filter = event.target.value.toLowerCase();
+  for (const row of rows) {
+    row.hidden = !row.textContent.toLowerCase().includes(filter);
+  }
+});
+document.querySelectorAll("th").forEach((header, column) => {
+  header.addEventListener("click", () => {
+    const ascending = header.dataset.order !== "asc";
+    document.querySelectorAll("th").forEach((other) => delete other.dataset.order);
+    header.dataset.order = ascending ? "asc" : "desc";
+    const numeric = header.classList.contains("number");
+    const key = (row) => row.cells[column].dataset.sort;
+    rows.sort((a, b) => {
+      const order = numeric ? key(a) - key(b) : key(a).localeCompare(key(b));
+      return ascending ? order : -order;
+    });
+    const body = document.querySelector("tbody");
+    rows.forEach((row) => body.appendChild(row));
+  });
+});
+"#;
+
+const HTML_STYLE: &str = r#"
+body { font-family: sans-serif; margin: 2em; }
+input { font-size: 1em; margin-bottom: 1em; padding: 0.3em; width: 30em; }
+table { border-collapse: collapse; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.3em 1em; text-align: left; }
+th { cursor: pointer; user-select: none; }
+th[data-order="asc"]::after { content: " \25B2"; }
+th[data-order="desc"]::after { content: " \25BC"; }
+.number { text-align: right; }
+code { font-size: 0.95em; }
+"#;
+
+fn html_page(query: &str, potential_hits: &[(u32, String, String)]) -> String {
+    let title = html_text(&format!("fuzzy-ls results for {}", query));
+    let mut rows = String::new();
+    for (score, file_name, full_path) in potential_hits {
+        let size = file_size(full_path);
+        rows.push_str(&format!(
+            "<tr><td data-sort=\"{name}\">{name}</td><td data-sort=\"{path}\"><code>{path}</code></td>\
+             <td class=\"number\" data-sort=\"{score}\">{score}</td>\
+             <td class=\"number\" data-sort=\"{bytes}\">{size}</td></tr>\n",
+            name = html_text(file_name),
+            path = html_text(full_path),
+            score = score,
+            // Unknown sizes sort before every file.
+            bytes = size.map_or(-1, |size| size as i64),
+            size = size.map_or("-".to_string(), format_size),
+        ));
+    }
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>{style}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<p>{count} results, generated by fuzzy-ls {version}.</p>\n\
+         <input id=\"filter\" type=\"search\" placeholder=\"Filter results\" autofocus>\n\
+         <table>\n<thead><tr><th>Name</th><th>Path</th><th class=\"number\">Score</th><th class=\"number\">Size</th></tr></thead>\n\
+         <tbody>\n{rows}</tbody>\n</table>\n<script>{script}</script>\n</body>\n</html>\n",
+        title = title,
+        style = HTML_STYLE,
+        count = potential_hits.len(),
+        version = env!("CARGO_PKG_VERSION"),
+        rows = rows,
+        script = HTML_SCRIPT,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let hits = vec![
+            (0, "alpha".to_string(), "./src/alpha.rs".to_string()),
+            (1, "alphb".to_string(), "./alphb.rs".to_string()),
+        ];
+        assert_eq!(
+            render(ExportFormat::Quickfix, "alpha", &hits),
+            "./src/alpha.rs:1:1: alpha (score 0)\n./alphb.rs:1:1: alphb (score 1)\n"
+        );
+        assert_eq!(
+            render(ExportFormat::Vscode, "alpha", &hits),
+            "# Query: alpha\n\n2 results - 2 files\n\
+             \nsrc/alpha.rs:\n  1: alpha (score 0)\n\
+             \nalphb.rs:\n  1: alphb (score 1)\n"
+        );
+        assert_eq!(
+            render(ExportFormat::Markdown, "alpha", &hits),
+            "| Name | Path | Score | Size |\n| --- | --- | ---: | ---: |\n\
+             | alpha | `./src/alpha.rs` | 0 | - |\n\
+             | alphb | `./alphb.rs` | 1 | - |\n"
+        );
+        assert_eq!(markdown_text("__init__|x"), "\\_\\_init\\_\\_\\|x");
+        assert_eq!(markdown_code("a|b\\c"), "`a\\|b\\c`");
+        let page = render(
+            ExportFormat::Html,
+            "<a>",
+            &[(2, "a&b".to_string(), "./a&b.rs".to_string())],
+        );
+        assert!(page.contains("<title>fuzzy-ls results for &lt;a&gt;</title>"));
+        assert!(page.contains(
+            "<tr><td data-sort=\"a&amp;b\">a&amp;b</td><td data-sort=\"./a&amp;b.rs\"><code>./a&amp;b.rs</code></td>\
+             <td class=\"number\" data-sort=\"2\">2</td><td class=\"number\" data-sort=\"-1\">-</td></tr>"
+        ));
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+}
@@ -0,0 +1,171 @@
+use crate::search::{self, FuzzySearchAlgorithm};
+use std::ffi::{c_char, CStr};
+
+/// Status returned when ranking succeeded.
+pub const FFS_OK: i32 = 0;
+/// Status returned when a required pointer is null or the algorithm is unknown.
+pub const FFS_INVALID_ARGUMENT: i32 = -1;
+/// Status returned when the selected algorithm failed to score a candidate.
+pub const FFS_SCORER_ERROR: i32 = -2;
+
+/// Options of [`ffs_rank`].
+#[repr(C)]
+pub struct FfsRankOptions {
+    /// `0` Damerau-Levenshtein, `1` Levenshtein, `2` Bitap, `3` Jaro-Winkler,
+    /// `4` subsequence, `5` tokens, `6` Smith-Waterman, `7` trigram.
+    pub algorithm: u32,
+    /// Maximum number of results to return, `0` for no limit.
+    pub max_results: usize,
+}
+
+/// A ranked candidate, referring to the input path by index.
+#[repr(C)]
+pub struct FfsRankResult {
+    pub index: usize,
+    pub score: u32,
+}
+
+/// The results of [`ffs_rank`], which must be released with [`ffs_rank_results_free`].
+#[repr(C)]
+pub struct FfsRankResults {
+    pub results: *mut FfsRankResult,
+    pub len: usize,
+    pub status: i32,
+}
+
+impl FfsRankResults {
+    fn from_status(status: i32) -> FfsRankResults {
+        FfsRankResults {
+            results: std::ptr::null_mut(),
+            len: 0,
+            status,
+        }
+    }
+}
+
+/// Ranks candidate paths against a query exactly like the fuzzy-ls command line does.
+///
+/// # Safety
+///
+/// `query` must be a valid NUL-terminated string, `paths` must point to `n` valid NUL-terminated
+/// strings and `options` must be null or point to valid options. Invalid UTF-8 is replaced.
+#[no_mangle]
+pub unsafe extern "C" fn ffs_rank(
+    query: *const c_char,
+    paths: *const *const c_char,
+    n: usize,
+    options: *const FfsRankOptions,
+) -> FfsRankResults {
+    if query.is_null() || (paths.is_null() && n > 0) {
+        return FfsRankResults::from_status(FFS_INVALID_ARGUMENT);
+    }
+    let (algorithm, max_results) = match options.as_ref() {
+        Some(options) => (options.algorithm, options.max_results),
+        None => (0, 0),
+    };
+    let algorithm = match algorithm {
+        0 => FuzzySearchAlgorithm::DamerauLevenshtein,
+        1 => FuzzySearchAlgorithm::Levenshtein,
+        2 => FuzzySearchAlgorithm::Bitap,
+        3 => FuzzySearchAlgorithm::JaroWinkler,
+        4 => FuzzySearchAlgorithm::Subsequence,
+        5 => FuzzySearchAlgorithm::Tokens,
+        6 => FuzzySearchAlgorithm::SmithWaterman,
+        7 => FuzzySearchAlgorithm::Trigram,
+        _ => return FfsRankResults::from_status(FFS_INVALID_ARGUMENT),
+    };
+    let query = CStr::from_ptr(query).to_string_lossy();
+    let mut candidates: Vec<(String, usize)> = Vec::with_capacity(n);
+    for index in 0..n {
+        let path = *paths.add(index);
+        if path.is_null() {
+            return FfsRankResults::from_status(FFS_INVALID_ARGUMENT);
+        }
+        let path = CStr::from_ptr(path).to_string_lossy();
+        candidates.push((search::candidate_name_of_path(&path), index));
+    }
+    let mut ranked = match search::rank_candidates(&query, candidates, &algorithm) {
+        Ok(ranked) => ranked,
+        Err(_) => return FfsRankResults::from_status(FFS_SCORER_ERROR),
+    };
+    if max_results > 0 {
+        ranked.truncate(max_results);
+    }
+    let results: Box<[FfsRankResult]> = ranked
+        .into_iter()
+        .map(|(score, _, index)| FfsRankResult { index, score })
+        .collect();
+    let len = results.len();
+    FfsRankResults {
+        results: Box::into_raw(results) as *mut FfsRankResult,
+        len,
+        status: FFS_OK,
+    }
+}
+
+/// Releases results returned by [`ffs_rank`].
+///
+/// # Safety
+///
+/// `results` must have been returned by [`ffs_rank`] and not been freed before.
+#[no_mangle]
+pub unsafe extern "C" fn ffs_rank_results_free(results: FfsRankResults) {
+    if !results.results.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+            results.results,
+            results.len,
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_ffs_rank() {
+        let query = CString::new("serch").unwrap();
+        let paths: Vec<CString> = ["src/main.rs", "src/search.rs", "README.md"]
+            .iter()
+            .map(|path| CString::new(*path).unwrap())
+            .collect();
+        let path_pointers: Vec<*const c_char> = paths.iter().map(|path| path.as_ptr()).collect();
+        unsafe {
+            let results = ffs_rank(
+                query.as_ptr(),
+                path_pointers.as_ptr(),
+                path_pointers.len(),
+                std::ptr::null(),
+            );
+            assert_eq!(results.status, FFS_OK);
+            assert_eq!(results.len, 1);
+            assert_eq!((*results.results).index, 1);
+            assert_eq!((*results.results).score, 1);
+            ffs_rank_results_free(results);
+            let invalid = ffs_rank(
+                std::ptr::null(),
+                path_pointers.as_ptr(),
+                3,
+                std::ptr::null(),
+            );
+            assert_eq!(invalid.status, FFS_INVALID_ARGUMENT);
+            for algorithm in [6, 7] {
+                let options = FfsRankOptions {
+                    algorithm,
+                    max_results: 1,
+                };
+                let results = ffs_rank(
+                    query.as_ptr(),
+                    path_pointers.as_ptr(),
+                    path_pointers.len(),
+                    &options,
+                );
+                assert_eq!(results.status, FFS_OK, "algorithm {}", algorithm);
+                assert_eq!(results.len, 1, "algorithm {}", algorithm);
+                assert_eq!((*results.results).index, 1, "algorithm {}", algorithm);
+                ffs_rank_results_free(results);
+            }
+        }
+    }
+}
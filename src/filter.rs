@@ -0,0 +1,876 @@
+use crate::glob::{glob_match, PathPattern};
+use crate::throttle::{paced, IoThrottle};
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// An entry found while walking a directory, as seen by a [`CandidateFilter`].
+pub struct CandidateEntry<'a> {
+    /// The path of the entry, starting with the walked root.
+    pub path: &'a Path,
+    /// The path of the entry relative to the walked root.
+    pub relative_path: &'a Path,
+    /// Whether the entry is a directory.
+    pub is_dir: bool,
+    /// The throttle of the walk, which the file system reads of filters wait for too.
+    pub throttle: Option<&'a IoThrottle>,
+}
+
+impl CandidateEntry<'_> {
+    /// The relative path with `/` separators, the form matched by globs and ignore patterns.
+    pub fn relative_slash_path(&self) -> String {
+        self.relative_path.to_string_lossy().replace('\\', "/")
+    }
+}
+
+/// Decides which walked entries become search candidates.
+///
+/// Rejecting a directory prunes it, so nothing below it is walked.
+pub trait CandidateFilter {
+    fn accepts(&self, entry: &CandidateEntry) -> bool;
+}
+
+/// Keeps files by extension: only the focused extensions when any are given, otherwise every
+/// extension except the excluded ones. Directories are always kept.
+///
+/// Extensions may be compound, so `tar.gz` keeps or drops `archive.tar.gz` like `gz` does, and
+/// files without an extension, like `Makefile` or `.gitignore`, are never focused.
+pub struct ExtensionFilter {
+    pub exclude: BTreeSet<String>,
+    pub focus: BTreeSet<String>,
+}
+
+impl CandidateFilter for ExtensionFilter {
+    fn accepts(&self, entry: &CandidateEntry) -> bool {
+        if entry.is_dir {
+            return true;
+        }
+        let file_name = entry
+            .path
+            .file_name()
+            .map_or(String::new(), |name| name.to_string_lossy().to_string());
+        let mut extensions = extensions(&file_name);
+        if self.focus.is_empty() {
+            !extensions.any(|extension| self.exclude.contains(extension))
+        } else {
+            extensions.any(|extension| self.focus.contains(extension))
+        }
+    }
+}
+
+/// Returns the extensions of a file name from the final one to the longest, e.g. `gz` and `tar.gz`
+/// for `archive.tar.gz`. A leading dot doesn't start an extension, so `.gitignore` has none and
+/// `.env.local` has `local`, like [`Path::extension`].
+pub fn extensions(file_name: &str) -> impl Iterator<Item = &str> {
+    let name = file_name.strip_prefix('.').unwrap_or(file_name);
+    name.match_indices('.')
+        .rev()
+        .map(move |(index, _)| &name[index + 1..])
+}
+
+/// Drops hidden entries, whose names start with `.`, unless `hidden` is set. `.git` directories
+/// are never descended into, unless the walk starts inside one.
+pub struct HiddenFilter {
+    pub hidden: bool,
+}
+
+impl CandidateFilter for HiddenFilter {
+    fn accepts(&self, entry: &CandidateEntry) -> bool {
+        let Some(name) = entry.path.file_name() else {
+            return true;
+        };
+        if entry.is_dir && name == ".git" {
+            return false;
+        }
+        self.hidden || !name.to_string_lossy().starts_with('.')
+    }
+}
+
+/// Prunes the directories with any of the given names, e.g. `node_modules`, so nothing below them
+/// is walked. Files are always kept.
+pub struct ExcludeDirFilter {
+    pub names: BTreeSet<String>,
+}
+
+impl CandidateFilter for ExcludeDirFilter {
+    fn accepts(&self, entry: &CandidateEntry) -> bool {
+        if !entry.is_dir {
+            return true;
+        }
+        entry
+            .path
+            .file_name()
+            .is_none_or(|name| !self.names.contains(name.to_string_lossy().as_ref()))
+    }
+}
+
+/// Keeps files at most `max_depth` levels below the walked root, where the files directly in the
+/// root are at depth 1. Directories holding only deeper files are pruned, so they aren't read.
+pub struct DepthFilter {
+    pub max_depth: usize,
+}
+
+impl CandidateFilter for DepthFilter {
+    fn accepts(&self, entry: &CandidateEntry) -> bool {
+        let depth = entry
+            .relative_path
+            .components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .count();
+        if entry.is_dir {
+            depth < self.max_depth
+        } else {
+            depth <= self.max_depth
+        }
+    }
+}
+
+/// Keeps files whose size in bytes lies within the bounds. Directories are always kept and files
+/// whose size can't be read are dropped when a bound is set.
+pub struct SizeFilter {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl CandidateFilter for SizeFilter {
+    fn accepts(&self, entry: &CandidateEntry) -> bool {
+        if entry.is_dir || (self.min.is_none() && self.max.is_none()) {
+            return true;
+        }
+        let Ok(metadata) = paced(entry.throttle, || std::fs::metadata(entry.path)) else {
+            return false;
+        };
+        self.min.is_none_or(|min| metadata.len() >= min)
+            && self.max.is_none_or(|max| metadata.len() <= max)
+    }
+}
+
+/// Parses a size like `512`, `10k`, `1.5M` or `2GiB` into bytes, with binary units: `k` is 1024
+/// bytes. Units are case-insensitive and may end with `b` or `ib`.
+pub fn parse_size(size: &str) -> Result<u64, String> {
+    let size = size.trim();
+    let digits = size
+        .find(|character: char| !character.is_ascii_digit() && character != '.')
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(digits);
+    let unit = unit.trim().to_ascii_lowercase();
+    let unit = unit
+        .strip_suffix("ib")
+        .or_else(|| unit.strip_suffix('b'))
+        .unwrap_or(&unit);
+    let exponent = match unit {
+        "" => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        _ => return Err(format!("{} has an unknown unit; use k, M, G or T.", size)),
+    };
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("{} is not a size like 10k or 5M.", size))?;
+    Ok((number * 1024f64.powi(exponent)).round() as u64)
+}
+
+/// Keeps files last modified within the bounds. Directories are always kept and files whose
+/// modification time can't be read are dropped when a bound is set.
+pub struct ModifiedFilter {
+    pub newer_than: Option<SystemTime>,
+    pub older_than: Option<SystemTime>,
+}
+
+impl CandidateFilter for ModifiedFilter {
+    fn accepts(&self, entry: &CandidateEntry) -> bool {
+        if entry.is_dir || (self.newer_than.is_none() && self.older_than.is_none()) {
+            return true;
+        }
+        let metadata = paced(entry.throttle, || std::fs::metadata(entry.path));
+        let Ok(modified) = metadata.and_then(|metadata| metadata.modified()) else {
+            return false;
+        };
+        self.newer_than.is_none_or(|time| modified >= time)
+            && self.older_than.is_none_or(|time| modified <= time)
+    }
+}
+
+/// Parses a point in time given as a duration before now, like `30m`, `3h`, `2d`, `1w`, `6mo` or
+/// `1y`, or as a UTC date like `2024-05-01` or `2024-05-01T14:30`.
+pub fn parse_time(time: &str) -> Result<SystemTime, String> {
+    parse_time_from(time, SystemTime::now())
+}
+
+fn parse_time_from(time: &str, now: SystemTime) -> Result<SystemTime, String> {
+    let time = time.trim();
+    if let Some(seconds) = parse_duration_seconds(time) {
+        return now
+            .checked_sub(Duration::from_secs(seconds))
+            .ok_or(format!("{} reaches too far back.", time));
+    }
+    let invalid = || {
+        format!(
+            "{} is neither a duration like 2d nor a date like 2024-05-01.",
+            time
+        )
+    };
+    let (date, clock) = time.split_once(['T', ' ']).unwrap_or((time, "00:00"));
+    let date: Vec<u64> = date
+        .split('-')
+        .map(|part| part.parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    let clock: Vec<u64> = clock
+        .split(':')
+        .map(|part| part.parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    let (&[year, month, day], &[hour, minute, ..]) = (date.as_slice(), clock.as_slice()) else {
+        return Err(invalid());
+    };
+    let second = clock.get(2).copied().unwrap_or(0);
+    let in_range = (1..=12).contains(&month)
+        && (1..=31).contains(&day)
+        && hour < 24
+        && minute < 60
+        && second < 60;
+    if !in_range || clock.len() > 3 {
+        return Err(invalid());
+    }
+    let days = days_since_epoch(year as i64, month as i64, day as i64);
+    let seconds = days * 86_400 + (hour * 3600 + minute * 60 + second) as i64;
+    u64::try_from(seconds)
+        .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds))
+        .map_err(|_| format!("{} is before 1970.", time))
+}
+
+/// Returns the seconds of a duration like `2d`, or `None` if the input isn't one.
+fn parse_duration_seconds(duration: &str) -> Option<u64> {
+    let unit_index = duration.find(|character: char| !character.is_ascii_digit())?;
+    let (amount, unit) = duration.split_at(unit_index);
+    let amount: u64 = amount.parse().ok()?;
+    let unit = match unit {
+        "s" => 1,
+        "m" | "min" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        "mo" => 30 * 86_400,
+        "y" => 365 * 86_400,
+        _ => return None,
+    };
+    amount.checked_mul(unit)
+}
+
+/// Returns the days from 1970-01-01 to a date of the proleptic Gregorian calendar.
+fn days_since_epoch(year: i64, month: i64, day: i64) -> i64 {
+    // Counts years from March, so the leap day ends the year.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Drops entries matching any of a set of gitignore-style patterns, relative to the walked root.
+pub struct IgnoreFilter {
+    patterns: Vec<PathPattern>,
+}
+
+impl IgnoreFilter {
+    /// Builds the filter from the lines of an ignore file. Blank lines and comments are skipped.
+    pub fn parse(contents: &str) -> IgnoreFilter {
+        IgnoreFilter {
+            patterns: contents.lines().filter_map(PathPattern::parse).collect(),
+        }
+    }
+}
+
+impl CandidateFilter for IgnoreFilter {
+    fn accepts(&self, entry: &CandidateEntry) -> bool {
+        let relative_path = entry.relative_slash_path();
+        !self
+            .patterns
+            .iter()
+            .any(|pattern| pattern.matches(&relative_path, entry.is_dir))
+    }
+}
+
+/// The patterns of an ignore file in order, each with whether it is negated by a leading `!`.
+type IgnoreRules = Rc<Vec<(PathPattern, bool)>>;
+
+/// Parses the lines of an ignore file into its rules. Blank lines and comments are skipped.
+fn parse_ignore_rules(contents: &str) -> Vec<(PathPattern, bool)> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            match line.strip_prefix('!') {
+                Some(negated) => Some((PathPattern::parse(negated)?, true)),
+                None => Some((PathPattern::parse(line)?, false)),
+            }
+        })
+        .collect()
+}
+
+/// The ignore files of one name, e.g. `.gitignore`, that apply to a walk: the ones of the walked
+/// directories, read as the walk reaches them, and the ones above the root up to the repository.
+///
+/// Like git, a deeper ignore file takes precedence over the ones above it, and the last pattern
+/// matching an entry decides, so `!` patterns can keep entries ignored by earlier ones. Paths
+/// outside the root, e.g. absolute paths piped in, are never ignored.
+struct IgnoreFiles {
+    file_name: &'static str,
+    /// The rules of the ignore files above the root, each with the path of the root relative to
+    /// the directory of the file, ending in `/`.
+    above_root: Vec<(String, IgnoreRules)>,
+    /// The rules of the ignore file of each directory read so far.
+    directories: RefCell<HashMap<PathBuf, IgnoreRules>>,
+}
+
+impl IgnoreFiles {
+    /// Reads the ignore files above `root`, and `.git/info/exclude` when `git_exclude` is set, at
+    /// the pace of the throttle.
+    fn new(
+        root: &Path,
+        file_name: &'static str,
+        git_exclude: bool,
+        throttle: Option<&IoThrottle>,
+    ) -> IgnoreFiles {
+        let read = |path: PathBuf| paced(throttle, || std::fs::read_to_string(path));
+        let mut above_root = Vec::new();
+        let root = root.canonicalize().unwrap_or(root.to_path_buf());
+        let mut prefix = String::new();
+        for (index, directory) in root.ancestors().enumerate() {
+            if index > 0 {
+                let rules =
+                    parse_ignore_rules(&read(directory.join(file_name)).unwrap_or_default());
+                above_root.push((prefix.clone(), Rc::new(rules)));
+            }
+            if paced(throttle, || std::fs::metadata(directory.join(".git"))).is_ok() {
+                if git_exclude {
+                    let exclude = read(directory.join(".git/info/exclude"));
+                    let rules = parse_ignore_rules(&exclude.unwrap_or_default());
+                    above_root.push((prefix, Rc::new(rules)));
+                }
+                // The ignore files of the repository apply from its root down.
+                above_root.reverse();
+                return IgnoreFiles {
+                    file_name,
+                    above_root,
+                    directories: RefCell::default(),
+                };
+            }
+            if let Some(name) = directory.file_name() {
+                prefix = format!("{}/{}", name.to_string_lossy(), prefix);
+            }
+        }
+        // Outside of a repository, only the ignore files of the walked directories apply.
+        IgnoreFiles {
+            file_name,
+            above_root: Vec::new(),
+            directories: RefCell::default(),
+        }
+    }
+
+    /// Returns the rules of the ignore file of a directory, reading it the first time at the pace
+    /// of the throttle.
+    fn rules_of(&self, directory: &Path, throttle: Option<&IoThrottle>) -> IgnoreRules {
+        if let Some(rules) = self.directories.borrow().get(directory) {
+            return Rc::clone(rules);
+        }
+        let contents = paced(throttle, || {
+            std::fs::read_to_string(directory.join(self.file_name))
+        });
+        let rules = Rc::new(parse_ignore_rules(&contents.unwrap_or_default()));
+        self.directories
+            .borrow_mut()
+            .insert(directory.to_path_buf(), Rc::clone(&rules));
+        rules
+    }
+
+    /// Whether the ignore files ignore an entry.
+    fn ignores(&self, entry: &CandidateEntry) -> bool {
+        let inside_root = entry
+            .relative_path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !inside_root {
+            return false;
+        }
+        let relative_path = entry.relative_slash_path();
+        let relative_path = relative_path.trim_start_matches("./");
+        let mut ignored = false;
+        let mut decide = |rules: &IgnoreRules, path: &str| {
+            for (pattern, negated) in rules.iter() {
+                if pattern.matches(path, entry.is_dir) {
+                    ignored = !negated;
+                }
+            }
+        };
+        for (prefix, rules) in &self.above_root {
+            decide(rules, &format!("{}{}", prefix, relative_path));
+        }
+        // The directories from the root down to the one holding the entry, and the path of the
+        // entry relative to each of them.
+        let depth = relative_path.split('/').count();
+        let mut directories: Vec<&Path> = entry.path.ancestors().skip(1).take(depth).collect();
+        directories.reverse();
+        let mut start = 0;
+        for directory in directories {
+            decide(
+                &self.rules_of(directory, entry.throttle),
+                &relative_path[start..],
+            );
+            start += relative_path[start..]
+                .find('/')
+                .map_or(0, |slash| slash + 1);
+        }
+        ignored
+    }
+}
+
+/// Drops entries ignored by git: by the `.gitignore` files of the walked directories, and of the
+/// directories above the root up to the repository, and by `.git/info/exclude`.
+///
+/// Ignore files are read as the walk reaches their directory, and apply like in git.
+pub struct GitignoreFilter {
+    files: IgnoreFiles,
+}
+
+impl GitignoreFilter {
+    /// Builds the filter for a walk of `root`, reading the ignore files above it at the pace of the
+    /// walk's throttle.
+    pub fn new(root: &Path, throttle: Option<&IoThrottle>) -> GitignoreFilter {
+        GitignoreFilter {
+            files: IgnoreFiles::new(root, ".gitignore", true, throttle),
+        }
+    }
+}
+
+impl CandidateFilter for GitignoreFilter {
+    fn accepts(&self, entry: &CandidateEntry) -> bool {
+        !self.files.ignores(entry)
+    }
+}
+
+/// Drops entries matching the `.ffsignore` files of the walked directories, and of the directories
+/// above the root up to the repository, the persistent exclusions of a project.
+///
+/// `.ffsignore` files hold gitignore-style patterns and apply like `.gitignore` files. Every
+/// directory walk applies them, whatever its other filters.
+pub struct FfsignoreFilter {
+    files: IgnoreFiles,
+}
+
+impl FfsignoreFilter {
+    /// Builds the filter for a walk of `root`, reading the `.ffsignore` files above it at the pace
+    /// of the walk's throttle.
+    pub fn new(root: &Path, throttle: Option<&IoThrottle>) -> FfsignoreFilter {
+        FfsignoreFilter {
+            files: IgnoreFiles::new(root, ".ffsignore", false, throttle),
+        }
+    }
+}
+
+impl CandidateFilter for FfsignoreFilter {
+    fn accepts(&self, entry: &CandidateEntry) -> bool {
+        !self.files.ignores(entry)
+    }
+}
+
+/// Gitignore-style patterns of files written by tools rather than people: minified bundles,
+/// source maps, generated protobuf code, bytecode caches, build output and lockfiles.
+pub const GENERATED_PATTERNS: &[&str] = &[
+    "*.min.js",
+    "*.min.css",
+    "*.map",
+    "*.pb.go",
+    "*_pb2.py",
+    "*_pb2_grpc.py",
+    "*.pb.h",
+    "*.pb.cc",
+    "*.g.dart",
+    "*.generated.*",
+    "*.pyc",
+    "__pycache__/",
+    "dist/",
+    "Cargo.lock",
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "poetry.lock",
+    "Pipfile.lock",
+    "Gemfile.lock",
+    "composer.lock",
+    "go.sum",
+];
+
+/// Drops generated files, matching [`GENERATED_PATTERNS`] or any of the extra patterns, wherever
+/// they are. Generated directories like `dist/` are pruned.
+pub struct GeneratedFilter {
+    patterns: IgnoreFilter,
+}
+
+impl GeneratedFilter {
+    /// Builds the filter from the built-in patterns and the lines of `extra`, gitignore-style
+    /// patterns like the ones of a `.gitignore` file.
+    pub fn new(extra: &str) -> GeneratedFilter {
+        let patterns = format!("{}\n{}", GENERATED_PATTERNS.join("\n"), extra);
+        GeneratedFilter {
+            patterns: IgnoreFilter::parse(&patterns),
+        }
+    }
+}
+
+impl CandidateFilter for GeneratedFilter {
+    fn accepts(&self, entry: &CandidateEntry) -> bool {
+        self.patterns.accepts(entry)
+    }
+}
+
+/// Keeps files whose path relative to the walked root matches any of the globs. Directories are
+/// always kept since files below them may still match.
+pub struct PathGlobFilter {
+    pub globs: Vec<String>,
+}
+
+impl CandidateFilter for PathGlobFilter {
+    fn accepts(&self, entry: &CandidateEntry) -> bool {
+        if entry.is_dir || self.globs.is_empty() {
+            return true;
+        }
+        let relative_path = entry.relative_slash_path();
+        self.globs
+            .iter()
+            .any(|glob| glob_match(glob, &relative_path))
+    }
+}
+
+/// Drops entries whose path relative to the walked root matches any of the globs. A directory is
+/// pruned when it matches a glob, or when a glob ending in `/**` covers everything below it, e.g.
+/// `**/generated/**` prunes `src/generated`.
+pub struct ExcludeGlobFilter {
+    pub globs: Vec<String>,
+}
+
+impl CandidateFilter for ExcludeGlobFilter {
+    fn accepts(&self, entry: &CandidateEntry) -> bool {
+        if self.globs.is_empty() {
+            return true;
+        }
+        let relative_path = entry.relative_slash_path();
+        !self.globs.iter().any(|glob| {
+            glob_match(glob, &relative_path)
+                || (entry.is_dir
+                    && glob
+                        .strip_suffix("/**")
+                        .is_some_and(|directory| glob_match(directory, &relative_path)))
+        })
+    }
+}
+
+/// A sequence of filters an entry has to pass, applied in order.
+#[derive(Default)]
+pub struct FilterChain {
+    filters: Vec<Box<dyn CandidateFilter>>,
+}
+
+impl FilterChain {
+    /// A chain without filters, which accepts every entry.
+    pub fn new() -> FilterChain {
+        FilterChain::default()
+    }
+
+    /// Appends a filter to the chain.
+    pub fn with(mut self, filter: impl CandidateFilter + 'static) -> FilterChain {
+        self.filters.push(Box::new(filter));
+        self
+    }
+}
+
+impl CandidateFilter for FilterChain {
+    fn accepts(&self, entry: &CandidateEntry) -> bool {
+        self.filters.iter().all(|filter| filter.accepts(entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accepts(chain: &FilterChain, path: &str, is_dir: bool) -> bool {
+        chain.accepts(&CandidateEntry {
+            path: &Path::new(".").join(path),
+            relative_path: Path::new(path),
+            is_dir,
+            throttle: None,
+        })
+    }
+
+    #[test]
+    fn test_filter_chain() {
+        let chain = FilterChain::new()
+            .with(ExtensionFilter {
+                exclude: BTreeSet::from(["md".to_string()]),
+                focus: BTreeSet::new(),
+            })
+            .with(IgnoreFilter::parse("# build output\ntarget/\n"))
+            .with(PathGlobFilter {
+                globs: vec!["src/**".to_string(), "*.toml".to_string()],
+            });
+        assert!(accepts(&chain, "src/main.rs", false));
+        assert!(accepts(&chain, "Cargo.toml", false));
+        assert!(accepts(&chain, "src", true));
+        assert!(!accepts(&chain, "src/README.md", false));
+        assert!(!accepts(&chain, "target", true));
+        assert!(!accepts(&chain, "benches/walk.rs", false));
+
+        let focused = ExtensionFilter {
+            exclude: BTreeSet::new(),
+            focus: BTreeSet::from(["tar.gz".to_string(), "rs".to_string()]),
+        };
+        let focuses = |path: &str| {
+            focused.accepts(&CandidateEntry {
+                path: Path::new(path),
+                relative_path: Path::new(path),
+                is_dir: false,
+                throttle: None,
+            })
+        };
+        assert!(focuses("dist/archive.tar.gz"));
+        assert!(!focuses("dist/archive.gz"));
+        assert!(focuses("src/main.rs"));
+        assert!(!focuses(".rs"));
+        assert!(!focuses("Makefile"));
+        assert_eq!(
+            extensions("app.min.js").collect::<Vec<_>>(),
+            ["js", "min.js"]
+        );
+        assert_eq!(extensions(".env.local").collect::<Vec<_>>(), ["local"]);
+    }
+
+    #[test]
+    fn test_exclude_glob_filter() {
+        let chain = FilterChain::new().with(ExcludeGlobFilter {
+            globs: vec!["**/generated/**".to_string(), "*.min.js".to_string()],
+        });
+        assert!(accepts(&chain, "src/main.rs", false));
+        assert!(accepts(&chain, "src/app.js", false));
+        assert!(accepts(&chain, "src/app.min.js", false));
+        assert!(!accepts(&chain, "app.min.js", false));
+        assert!(!accepts(&chain, "src/generated", true));
+        assert!(!accepts(&chain, "src/generated/schema.rs", false));
+        assert!(accepts(&chain, "src/generator", true));
+    }
+
+    #[test]
+    fn test_hidden_filter() {
+        let chain = FilterChain::new().with(HiddenFilter { hidden: false });
+        assert!(accepts(&chain, "src/main.rs", false));
+        assert!(!accepts(&chain, ".env", false));
+        assert!(!accepts(&chain, ".github", true));
+        let chain = FilterChain::new().with(HiddenFilter { hidden: true });
+        assert!(accepts(&chain, ".env", false));
+        assert!(accepts(&chain, ".github", true));
+        assert!(!accepts(&chain, ".git", true));
+        assert!(!accepts(&chain, "vendor/lib/.git", true));
+    }
+
+    #[test]
+    fn test_exclude_dir_filter() {
+        let chain = FilterChain::new().with(ExcludeDirFilter {
+            names: BTreeSet::from(["node_modules".to_string(), "target".to_string()]),
+        });
+        assert!(accepts(&chain, "src", true));
+        assert!(accepts(&chain, "src/target", false));
+        assert!(!accepts(&chain, "target", true));
+        assert!(!accepts(&chain, "web/node_modules", true));
+    }
+
+    #[test]
+    fn test_generated_filter() {
+        let chain = FilterChain::new().with(GeneratedFilter::new("# protobuf\n*_grpc.ts\n"));
+        assert!(accepts(&chain, "src/app.js", false));
+        assert!(accepts(&chain, "Cargo.toml", false));
+        assert!(!accepts(&chain, "web/app.min.js", false));
+        assert!(!accepts(&chain, "api/user.pb.go", false));
+        assert!(!accepts(&chain, "web/dist", true));
+        assert!(!accepts(&chain, "app/__pycache__", true));
+        assert!(!accepts(&chain, "Cargo.lock", false));
+        assert!(!accepts(&chain, "web/package-lock.json", false));
+        assert!(!accepts(&chain, "web/api/user_grpc.ts", false));
+    }
+
+    #[test]
+    fn test_size_filter() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("10k"), Ok(10 * 1024));
+        assert_eq!(parse_size("1.5M"), Ok(1536 * 1024));
+        assert_eq!(parse_size("2GiB"), Ok(2 << 30));
+        assert_eq!(parse_size("5 mb"), Ok(5 << 20));
+        assert!(parse_size("5x").is_err());
+        assert!(parse_size("M").is_err());
+        let directory = std::env::temp_dir().join(format!("ffs-size-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("small.log"), [0; 10]).unwrap();
+        std::fs::write(directory.join("big.log"), [0; 2048]).unwrap();
+        let filter = SizeFilter {
+            min: Some(1024),
+            max: Some(4096),
+        };
+        let accepts = |name: &str, is_dir: bool| {
+            filter.accepts(&CandidateEntry {
+                path: &directory.join(name),
+                relative_path: Path::new(name),
+                is_dir,
+                throttle: None,
+            })
+        };
+        let (small, big, missing) = (
+            accepts("small.log", false),
+            accepts("big.log", false),
+            accepts("missing.log", false),
+        );
+        let dir = accepts(".", true);
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(!small);
+        assert!(big);
+        assert!(!missing);
+        assert!(dir);
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(
+            parse_time_from("2d", now),
+            Ok(now - Duration::from_secs(2 * 86_400))
+        );
+        assert_eq!(
+            parse_time_from("90m", now),
+            Ok(now - Duration::from_secs(90 * 60))
+        );
+        assert_eq!(
+            parse_time_from("2024-02-29", now),
+            Ok(UNIX_EPOCH + Duration::from_secs(1_709_164_800))
+        );
+        assert_eq!(
+            parse_time_from("2024-02-29T14:30", now),
+            Ok(UNIX_EPOCH + Duration::from_secs(1_709_164_800 + 14 * 3600 + 30 * 60))
+        );
+        assert_eq!(parse_time_from("1970-01-01", now), Ok(UNIX_EPOCH));
+        assert!(parse_time_from("yesterday", now).is_err());
+        assert!(parse_time_from("2024-13-01", now).is_err());
+        assert!(parse_time_from("2d4h", now).is_err());
+        let directory = std::env::temp_dir().join(format!("ffs-modified-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("notes.md"), "").unwrap();
+        let accepts = |filter: ModifiedFilter| {
+            filter.accepts(&CandidateEntry {
+                path: &directory.join("notes.md"),
+                relative_path: Path::new("notes.md"),
+                is_dir: false,
+                throttle: None,
+            })
+        };
+        let recent = accepts(ModifiedFilter {
+            newer_than: parse_time("1h").ok(),
+            older_than: None,
+        });
+        let old = accepts(ModifiedFilter {
+            newer_than: None,
+            older_than: parse_time("1h").ok(),
+        });
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(recent);
+        assert!(!old);
+    }
+
+    #[test]
+    fn test_depth_filter() {
+        let chain = FilterChain::new().with(DepthFilter { max_depth: 2 });
+        assert!(accepts(&chain, "Cargo.toml", false));
+        assert!(accepts(&chain, "src/main.rs", false));
+        assert!(!accepts(&chain, "src/snapshots", true));
+        assert!(!accepts(&chain, "src/snapshots/main.snap", false));
+    }
+
+    #[test]
+    fn test_gitignore_filter() {
+        let repository = std::env::temp_dir().join(format!("ffs-gitignore-{}", std::process::id()));
+        let files = [
+            (".git/info/exclude", "*.tmp\n"),
+            (".gitignore", "target/\n*.log\n# comment\n!keep.log\n"),
+            ("app/.gitignore", "generated.rs\n!debug.log\n"),
+            ("app/main.rs", ""),
+            ("app/generated.rs", ""),
+            ("app/debug.log", ""),
+            ("app/trace.log", ""),
+            ("app/keep.log", ""),
+            ("app/scratch.tmp", ""),
+            ("app/target/debug/app", ""),
+            ("app/lib/generated.rs", ""),
+        ];
+        for (path, contents) in files {
+            let path = repository.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        // Walking a directory of the repository still applies the ignore files above it.
+        let root = repository.join("app");
+        let chain = FilterChain::new().with(GitignoreFilter::new(&root, None));
+        let mut found: Vec<String> = crate::search::walk_directory(&root, &chain)
+            .into_iter()
+            .map(|(_, full_path)| {
+                let relative = Path::new(&full_path).strip_prefix(&root).unwrap();
+                relative.to_string_lossy().replace('\\', "/")
+            })
+            .collect();
+        found.sort();
+        std::fs::remove_dir_all(&repository).unwrap();
+        assert_eq!(found, [".gitignore", "debug.log", "keep.log", "main.rs"]);
+        assert!(chain.accepts(&CandidateEntry {
+            path: Path::new("/tmp/trace.log"),
+            relative_path: Path::new("/tmp/trace.log"),
+            is_dir: false,
+            throttle: None,
+        }));
+    }
+
+    #[test]
+    fn test_ffsignore_filter() {
+        let project = std::env::temp_dir().join(format!("ffs-ffsignore-{}", std::process::id()));
+        let files = [
+            (".ffsignore", "fixtures/\n*.snap\n"),
+            ("src/.ffsignore", "!keep.snap\n"),
+            ("src/main.rs", ""),
+            ("src/main.snap", ""),
+            ("src/keep.snap", ""),
+            ("src/fixtures/big.json", ""),
+        ];
+        for (path, contents) in files {
+            let path = project.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        // Walks apply the .ffsignore files without being given the filter.
+        let mut found: Vec<String> = crate::search::walk_directory(&project, &FilterChain::new())
+            .into_iter()
+            .map(|(_, full_path)| {
+                let relative = Path::new(&full_path).strip_prefix(&project).unwrap();
+                relative.to_string_lossy().replace('\\', "/")
+            })
+            .collect();
+        found.sort();
+        std::fs::remove_dir_all(&project).unwrap();
+        assert_eq!(
+            found,
+            [
+                ".ffsignore",
+                "src/.ffsignore",
+                "src/keep.snap",
+                "src/main.rs"
+            ]
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use fuzzy_ls::filter::FilterChain;
+use fuzzy_ls::search::candidate_name_of_path;
+use fuzzy_ls::source::{accepts_file, CandidateSource};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::Command;
+
+/// Runs a git command in the current directory and returns the non-empty lines of its output.
+///
+/// # Arguments
+///
+/// * `args` - The arguments passed to `git`.
+///
+/// # Returns
+///
+/// The trimmed, non-empty output lines, or an error message if git could not be run or failed.
+fn run_git(args: &[&str]) -> Result<Vec<String>, String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|error| format!("Failed to run git: {}", error))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Converts a shorthand duration such as `2d` or `3h` into git's approxidate syntax (`2.days.ago`).
+///
+/// # Returns
+///
+/// `None` if the input is not a duration, in which case it should be treated as a git ref.
+fn duration_to_approxidate(spec: &str) -> Option<String> {
+    let unit_index = spec.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = spec.split_at(unit_index);
+    if amount.is_empty() {
+        return None;
+    }
+    let unit = match unit {
+        "s" => "seconds",
+        "m" | "min" => "minutes",
+        "h" => "hours",
+        "d" => "days",
+        "w" => "weeks",
+        "mo" => "months",
+        "y" => "years",
+        _ => return None,
+    };
+    Some(format!("{}.{}.ago", amount, unit))
+}
+
+/// Normalizes a walked path (e.g. `./src/main.rs`) into the relative, forward-slash form git prints.
+pub fn normalize_path(full_path: &str) -> String {
+    full_path
+        .trim_start_matches("./")
+        .trim_start_matches(".\\")
+        .replace('\\', "/")
+}
+
+/// Returns the root directory of the git repository containing the current directory.
+pub fn repository_root() -> Result<String, String> {
+    run_git(&["rev-parse", "--show-toplevel"])?
+        .into_iter()
+        .next()
+        .ok_or("Not inside a git repository".to_string())
+}
+
+/// Returns the path of the current directory relative to the repository root (e.g. `src/`).
+pub fn current_prefix() -> Result<String, String> {
+    Ok(run_git(&["rev-parse", "--show-prefix"])?
+        .into_iter()
+        .next()
+        .unwrap_or_default())
+}
+
+/// Collects the files changed since a git ref or a duration.
+///
+/// For a ref, files changed between the merge base of the ref and `HEAD` are returned along
+/// with uncommitted changes. For a duration, files touched by commits in that window are returned
+/// along with uncommitted changes. Untracked files are always included.
+///
+/// # Arguments
+///
+/// * `since` - A git ref (e.g. `origin/main`) or a duration (e.g. `2d`, `3h`, `1w`).
+///
+/// # Returns
+///
+/// The changed paths relative to the current directory, or an error message if git failed.
+pub fn changed_since(since: &str) -> Result<BTreeSet<String>, String> {
+    let mut changed_files: BTreeSet<String> = BTreeSet::new();
+    match duration_to_approxidate(since) {
+        Some(approxidate) => {
+            let since_arg = format!("--since={}", approxidate);
+            changed_files.extend(run_git(&[
+                "log",
+                "--relative",
+                "--name-only",
+                "--pretty=format:",
+                &since_arg,
+            ])?);
+            changed_files.extend(run_git(&["diff", "--relative", "--name-only", "HEAD"])?);
+        }
+        None => {
+            let merge_base = run_git(&["merge-base", since, "HEAD"])?
+                .into_iter()
+                .next()
+                .ok_or(format!("No merge base found for {}", since))?;
+            changed_files.extend(run_git(&[
+                "diff",
+                "--relative",
+                "--name-only",
+                &merge_base,
+            ])?);
+        }
+    }
+    changed_files.extend(run_git(&["ls-files", "--others", "--exclude-standard"])?);
+    Ok(changed_files)
+}
+
+/// The last commit that changed a file, shown by the results UI.
+#[cfg(feature = "tui")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastCommit {
+    pub author: String,
+    /// When it was committed, in seconds since the Unix epoch.
+    pub time: i64,
+}
+
+#[cfg(feature = "tui")]
+impl LastCommit {
+    /// Reads the last commit of a file from the repository it is in.
+    ///
+    /// # Returns
+    ///
+    /// The commit, or `None` if the file isn't in a repository or was never committed.
+    pub fn of(full_path: &str) -> Option<LastCommit> {
+        let path = Path::new(full_path);
+        let directory = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let file_name = path.file_name()?.to_string_lossy();
+        let line = run_git(&[
+            "-C",
+            &directory.to_string_lossy(),
+            "log",
+            "-1",
+            "--format=%ct %an",
+            "--",
+            &file_name,
+        ])
+        .ok()?
+        .into_iter()
+        .next()?;
+        let (time, author) = line.split_once(' ')?;
+        Some(LastCommit {
+            author: author.to_string(),
+            time: time.parse().ok()?,
+        })
+    }
+
+    /// Describes the commit by its author and age, e.g. `Ada Lovelace, 3d ago`.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time, in seconds since the Unix epoch.
+    pub fn label(&self, now: i64) -> String {
+        format!("{}, {}", self.author, age(now - self.time))
+    }
+}
+
+/// Describes how long ago something happened in its largest unit, e.g. `3d ago` or `2mo ago`.
+#[cfg(feature = "tui")]
+fn age(seconds: i64) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    let (amount, unit) = if seconds < MINUTE {
+        return "just now".to_string();
+    } else if seconds < HOUR {
+        (seconds / MINUTE, "min")
+    } else if seconds < DAY {
+        (seconds / HOUR, "h")
+    } else if seconds < 7 * DAY {
+        (seconds / DAY, "d")
+    } else if seconds < 30 * DAY {
+        (seconds / (7 * DAY), "w")
+    } else if seconds < 365 * DAY {
+        (seconds / (30 * DAY), "mo")
+    } else {
+        (seconds / (365 * DAY), "y")
+    };
+    format!("{}{} ago", amount, unit)
+}
+
+/// Candidates from the files git knows about below the current directory: tracked files and
+/// untracked files that are not ignored.
+pub struct TrackedFiles<'a> {
+    pub filters: &'a FilterChain,
+}
+
+impl CandidateSource for TrackedFiles<'_> {
+    fn candidates(&mut self) -> Result<Vec<(String, String)>, String> {
+        Ok(
+            run_git(&["ls-files", "--cached", "--others", "--exclude-standard"])?
+                .into_iter()
+                .map(|path| Path::new(".").join(path).to_string_lossy().to_string())
+                .filter(|full_path| Path::new(full_path).is_file())
+                .filter(|full_path| accepts_file(self.filters, full_path))
+                .map(|full_path| (candidate_name_of_path(&full_path), full_path))
+                .collect(),
+        )
+    }
+}
+
+/// Candidates from the files below the current directory that were changed in the git history, most
+/// recently changed first. Files that no longer exist are skipped.
+pub struct HistoryFiles<'a> {
+    pub filters: &'a FilterChain,
+}
+
+impl CandidateSource for HistoryFiles<'_> {
+    fn candidates(&mut self) -> Result<Vec<(String, String)>, String> {
+        let mut seen: BTreeSet<String> = BTreeSet::new();
+        Ok(
+            run_git(&["log", "--relative", "--name-only", "--pretty=format:"])?
+                .into_iter()
+                .filter(|path| seen.insert(path.clone()))
+                .map(|path| Path::new(".").join(path).to_string_lossy().to_string())
+                .filter(|full_path| Path::new(full_path).is_file())
+                .filter(|full_path| accepts_file(self.filters, full_path))
+                .map(|full_path| (candidate_name_of_path(&full_path), full_path))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_to_approxidate() {
+        assert_eq!(
+            duration_to_approxidate("2d"),
+            Some("2.days.ago".to_string())
+        );
+        assert_eq!(
+            duration_to_approxidate("12h"),
+            Some("12.hours.ago".to_string())
+        );
+        assert_eq!(duration_to_approxidate("origin/main"), None);
+        assert_eq!(duration_to_approxidate("HEAD~3"), None);
+        assert_eq!(duration_to_approxidate("15"), None);
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn test_last_commit_label() {
+        let commit = LastCommit {
+            author: "Ada Lovelace".to_string(),
+            time: 1_700_000_000,
+        };
+        assert_eq!(commit.label(1_700_000_030), "Ada Lovelace, just now");
+        assert_eq!(
+            commit.label(1_700_000_000 + 3 * 3600),
+            "Ada Lovelace, 3h ago"
+        );
+        assert_eq!(
+            commit.label(1_700_000_000 + 10 * 86400),
+            "Ada Lovelace, 1w ago"
+        );
+        assert_eq!(
+            commit.label(1_700_000_000 + 400 * 86400),
+            "Ada Lovelace, 1y ago"
+        );
+        assert_eq!(LastCommit::of("./no/such/file.rs"), None);
+    }
+}
@@ -0,0 +1,245 @@
+/// Matches a `/`-separated path against a glob pattern.
+///
+/// Supported syntax:
+///
+/// * `*` matches any run of characters except `/`.
+/// * `?` matches a single character except `/`.
+/// * `**` matches across directories; `**/` matches zero or more leading directories.
+/// * `[abc]`, `[a-z]` and `[!abc]` match character classes.
+/// * `\` escapes the next character.
+///
+/// Malformed patterns (e.g. an unterminated `[`) never panic; the offending character is matched literally.
+///
+/// # Arguments
+///
+/// * `pattern` - The glob pattern.
+/// * `path` - The path to test, using `/` as separator.
+///
+/// # Returns
+///
+/// `true` if the whole path matches the pattern.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    let mut matcher = GlobMatcher {
+        memo: vec![None; (pattern.len() + 1) * (path.len() + 1)],
+        pattern: &pattern,
+        path: &path,
+    };
+    matcher.match_from(0, 0)
+}
+
+/// Backtracking glob matcher. Results are memoized per pattern/path position so adversarial
+/// patterns such as `*a*a*a*b` stay polynomial, and only wildcards recurse so long literal runs
+/// cannot exhaust the stack.
+struct GlobMatcher<'a> {
+    pattern: &'a [char],
+    path: &'a [char],
+    memo: Vec<Option<bool>>,
+}
+
+impl GlobMatcher<'_> {
+    fn match_from(&mut self, pattern_index: usize, path_index: usize) -> bool {
+        let key = pattern_index * (self.path.len() + 1) + path_index;
+        if let Some(matched) = self.memo[key] {
+            return matched;
+        }
+        let matched = self.compute(pattern_index, path_index);
+        self.memo[key] = Some(matched);
+        matched
+    }
+
+    fn compute(&mut self, mut pattern_index: usize, mut path_index: usize) -> bool {
+        let path_len = self.path.len();
+        loop {
+            let Some(&current) = self.pattern.get(pattern_index) else {
+                return path_index == path_len;
+            };
+            if current == '*' && self.pattern.get(pattern_index + 1) == Some(&'*') {
+                let mut rest = pattern_index + 2;
+                while self.pattern.get(rest) == Some(&'*') {
+                    rest += 1;
+                }
+                if rest == self.pattern.len() {
+                    return true;
+                }
+                if self.pattern[rest] == '/' {
+                    if self.match_from(rest + 1, path_index) {
+                        return true;
+                    }
+                    return (path_index..path_len)
+                        .filter(|&index| self.path[index] == '/')
+                        .any(|index| self.match_from(rest + 1, index + 1));
+                }
+                return (path_index..=path_len).any(|index| self.match_from(rest, index));
+            }
+            if current == '*' {
+                for index in path_index..=path_len {
+                    if self.match_from(pattern_index + 1, index) {
+                        return true;
+                    }
+                    if index < path_len && self.path[index] == '/' {
+                        break;
+                    }
+                }
+                return false;
+            }
+            let Some(&candidate) = self.path.get(path_index) else {
+                return false;
+            };
+            let consumed = match current {
+                '?' if candidate != '/' => 1,
+                '?' => return false,
+                '[' => match parse_class(&self.pattern[pattern_index..]) {
+                    Some((matcher, consumed)) if candidate != '/' && matcher(candidate) => consumed,
+                    Some(_) => return false,
+                    None if candidate == '[' => 1,
+                    None => return false,
+                },
+                '\\' if pattern_index + 1 < self.pattern.len() => {
+                    if candidate != self.pattern[pattern_index + 1] {
+                        return false;
+                    }
+                    2
+                }
+                literal if literal == candidate => 1,
+                _ => return false,
+            };
+            pattern_index += consumed;
+            path_index += 1;
+        }
+    }
+}
+
+/// Parses a `[...]` character class at the start of `pattern`.
+///
+/// # Returns
+///
+/// A predicate for the class and the number of pattern characters consumed, or `None` if the class is unterminated.
+fn parse_class(pattern: &[char]) -> Option<(impl Fn(char) -> bool, usize)> {
+    let mut index = 1;
+    let negated = matches!(pattern.get(index), Some('!') | Some('^'));
+    if negated {
+        index += 1;
+    }
+    let mut ranges: Vec<(char, char)> = Vec::new();
+    let mut first = true;
+    loop {
+        let &current = pattern.get(index)?;
+        if current == ']' && !first {
+            break;
+        }
+        first = false;
+        if pattern.get(index + 1) == Some(&'-') && pattern.get(index + 2).is_some_and(|&c| c != ']')
+        {
+            ranges.push((current, pattern[index + 2]));
+            index += 3;
+        } else {
+            ranges.push((current, current));
+            index += 1;
+        }
+    }
+    let matcher = move |c: char| ranges.iter().any(|&(low, high)| low <= c && c <= high) != negated;
+    Some((matcher, index + 1))
+}
+
+/// A gitignore-style path pattern, as used by `.gitignore` and `CODEOWNERS` files.
+#[derive(Debug, Clone)]
+pub struct PathPattern {
+    glob: String,
+    anchored: bool,
+    directory_only: bool,
+}
+
+impl PathPattern {
+    /// Parses a single pattern line. Blank lines and comments yield `None`.
+    ///
+    /// A trailing `/` restricts the pattern to directories, and a `/` anywhere else anchors it to
+    /// the root; unanchored patterns match at any depth.
+    pub fn parse(line: &str) -> Option<PathPattern> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let directory_only = line.ends_with('/');
+        let line = line.trim_end_matches('/');
+        let anchored = line.contains('/');
+        let glob = line.trim_start_matches('/').to_string();
+        if glob.is_empty() {
+            return None;
+        }
+        Some(PathPattern {
+            glob,
+            anchored,
+            directory_only,
+        })
+    }
+
+    /// Tests the pattern against a single path without considering its parent directories.
+    ///
+    /// # Arguments
+    ///
+    /// * `relative_path` - The path relative to the pattern's root, using `/` as separator.
+    /// * `is_dir` - Whether the path is a directory.
+    pub fn matches_exact(&self, relative_path: &str, is_dir: bool) -> bool {
+        if self.directory_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            glob_match(&self.glob, relative_path)
+        } else {
+            let name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+            glob_match(&self.glob, name)
+        }
+    }
+
+    /// Tests the pattern against a path or any of its parent directories, so a pattern matching a
+    /// directory also matches everything below it.
+    ///
+    /// # Arguments
+    ///
+    /// * `relative_path` - The path relative to the pattern's root, using `/` as separator.
+    /// * `is_dir` - Whether the path is a directory.
+    pub fn matches(&self, relative_path: &str, is_dir: bool) -> bool {
+        if self.matches_exact(relative_path, is_dir) {
+            return true;
+        }
+        relative_path
+            .char_indices()
+            .filter(|&(_, c)| c == '/')
+            .any(|(index, _)| self.matches_exact(&relative_path[..index], true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(!glob_match("*.rs", "src/main.rs"));
+        assert!(glob_match("**/*.rs", "src/main.rs"));
+        assert!(glob_match("**/*.rs", "main.rs"));
+        assert!(glob_match("src/**", "src/a/b.rs"));
+        assert!(glob_match("**/generated/**", "a/generated/b/c.rs"));
+        assert!(glob_match("file?.[ch]", "file1.c"));
+        assert!(!glob_match("file[!0-9].c", "file1.c"));
+        assert!(glob_match("[", "["));
+        assert!(!glob_match("[a", "a"));
+        assert!(!glob_match(&"*a".repeat(30), &"a".repeat(29)));
+    }
+
+    #[test]
+    fn test_path_pattern() {
+        let unanchored = PathPattern::parse("*.log").unwrap();
+        assert!(unanchored.matches("logs/today.log", false));
+        let directory = PathPattern::parse("target/").unwrap();
+        assert!(directory.matches("target/debug/ffs", false));
+        assert!(!directory.matches("target", false));
+        let anchored = PathPattern::parse("/docs/*.md").unwrap();
+        assert!(anchored.matches("docs/index.md", false));
+        assert!(!anchored.matches("src/docs/index.md", false));
+        assert!(PathPattern::parse("# comment").is_none());
+    }
+}
@@ -0,0 +1,94 @@
+use std::io::{stdin, IsTerminal, Write};
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// The most entries a home directory may hold, counted down to this many, before searching all of
+/// it needs confirmation.
+pub const HOME_ENTRY_LIMIT: usize = 100_000;
+
+/// Returns why walking a directory would likely take very long, if it would: it is the root of the
+/// file system, e.g. `/` or `C:\`, or a home directory holding more than `home_entry_limit` entries.
+///
+/// # Arguments
+///
+/// * `root` - The directory to walk.
+/// * `home` - The home directory, if known.
+/// * `home_entry_limit` - The most entries the home directory may hold.
+pub fn risky_root(root: &Path, home: Option<&Path>, home_entry_limit: usize) -> Option<String> {
+    let root = root.canonicalize().ok()?;
+    if root.parent().is_none() {
+        return Some(format!("{} is the root of the file system", root.display()));
+    }
+    let home = home.and_then(|home| home.canonicalize().ok())?;
+    if root != home {
+        return None;
+    }
+    // The walk yields the directory itself first.
+    let entries = WalkDir::new(&root)
+        .into_iter()
+        .take(home_entry_limit + 2)
+        .count();
+    (entries > home_entry_limit + 1).then(|| {
+        format!(
+            "{} is a home directory holding more than {} files",
+            root.display(),
+            home_entry_limit
+        )
+    })
+}
+
+/// Asks whether to walk a risky directory anyway, when stdin is a terminal.
+///
+/// # Arguments
+///
+/// * `reason` - Why walking the directory is risky, as returned by [`risky_root`].
+///
+/// # Returns
+///
+/// Whether the walk was confirmed, or an error asking for `--force` when there is no one to ask.
+pub fn confirm_risky_root(reason: &str) -> Result<bool, String> {
+    if !stdin().is_terminal() {
+        return Err(format!(
+            "{}, so searching it may take very long. Pass --force to search it anyway.",
+            reason
+        ));
+    }
+    eprint!(
+        "{}, so searching it may take very long. Search it anyway? [y/N] ",
+        reason
+    );
+    std::io::stderr()
+        .flush()
+        .map_err(|error| error.to_string())?;
+    let mut answer = String::new();
+    stdin()
+        .read_line(&mut answer)
+        .map_err(|error| error.to_string())?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_risky_root() {
+        let home = std::env::temp_dir().join(format!("ffs-home-{}", std::process::id()));
+        std::fs::create_dir_all(home.join("src")).unwrap();
+        for name in ["notes.md", "todo.md", "src/main.rs"] {
+            std::fs::write(home.join(name), "").unwrap();
+        }
+        let small = risky_root(&home, Some(&home), 4);
+        let large = risky_root(&home, Some(&home), 3);
+        let other = risky_root(&home.join("src"), Some(&home), 0);
+        std::fs::remove_dir_all(&home).unwrap();
+        assert_eq!(small, None);
+        assert!(large.is_some_and(|reason| reason.ends_with("holding more than 3 files")));
+        assert_eq!(other, None);
+        #[cfg(unix)]
+        assert_eq!(
+            risky_root(Path::new("/"), None, HOME_ENTRY_LIMIT),
+            Some("/ is the root of the file system".to_string())
+        );
+    }
+}
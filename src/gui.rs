@@ -1,17 +1,1464 @@
+use crate::checksum::HashAlgorithm;
+use crate::codeowners::CodeOwners;
+use crate::counterpart::{self, CounterpartPatterns};
+use crate::editor;
+use crate::theme::{Theme, ThemeChoice};
+use crate::usage::{self, DiskUsage};
+use clap::ValueEnum;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use fuzzy_ls::filter;
+use fuzzy_ls::search::{self, FuzzySearchAlgorithm, ScoreFunction, SearchMode};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::Stdout;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use tui::{
-    backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Block, Borders, Paragraph, Row, Table},
-    Terminal,
+    backend::{Backend, CrosstermBackend},
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table, Widget, Wrap},
+    Frame, Terminal,
 };
-use std::time::Duration;
+
+/// Options controlling what the results UI shows and how it is drawn.
+#[derive(Default)]
+pub struct UiOptions<'a> {
+    /// When provided, an additional column lists the owners of each result.
+    pub code_owners: Option<&'a CodeOwners>,
+    /// When provided, an additional column lists the sources each result was found in, keyed by full path.
+    pub source_labels: Option<&'a BTreeMap<String, String>>,
+    /// When provided, an additional column describes the last commit of each result, read for the
+    /// rows on screen only.
+    pub last_commit: Option<&'a LastCommitLabel<'a>>,
+    /// When provided, an additional column visualizes the relative match quality.
+    pub score_column: Option<ScoreColumn>,
+    /// Replace unicode borders and glyphs with ASCII equivalents.
+    pub ascii: bool,
+    /// The color theme, detected from the terminal background by default.
+    pub theme: ThemeChoice,
+    /// Run the editor in this terminal and return to the results once it exits, instead of
+    /// opening it in a new terminal window and leaving.
+    pub wait: bool,
+    /// When provided, the terminal to open the editor in instead of the platform's, e.g.
+    /// `alacritty -e`.
+    pub terminal: Option<&'a str>,
+    /// When provided, a note about the results shown above the help line, e.g. that they were cut.
+    pub notice: Option<&'a str>,
+    /// Keys handled before any typed ones, e.g. from `--keys`.
+    pub keys: Vec<KeyCode>,
+    /// The query of the results, shown in the tab bar once more tabs are opened.
+    pub query: &'a str,
+    /// The mode of the query, shown in the tab bar once a tab is switched to another mode.
+    pub mode: SearchMode,
+    /// When provided, searches the same candidates for another query or in another mode, so `t`
+    /// opens a tab with the results of another query and `s` switches the mode of a tab.
+    pub search: Option<&'a TabSearch<'a>>,
+    /// When provided, walks another directory and searches it, so `:cd` re-roots a tab.
+    pub search_directory: Option<&'a DirectorySearch<'a>>,
+    /// The test patterns `c` finds the test of a source file, or the source of a test, by.
+    pub counterparts: Option<&'a CounterpartPatterns>,
+    /// When provided, rates how similar the results of a query in a mode are to it, from 0 to 100,
+    /// for the similarity score column.
+    pub similarity: Option<&'a Similarity<'a>>,
+    /// Show a panel summing the sizes of the results of each tab by extension and directory.
+    pub disk_usage: bool,
+}
+
+/// Searches for a query in a mode, returning its results or why the search failed.
+pub type TabSearch<'a> =
+    dyn Fn(&str, SearchMode) -> Result<Vec<(u32, String, String)>, String> + 'a;
+
+/// Walks a directory and searches its files for a query in a mode, like [`TabSearch`] does among
+/// the candidates of the UI.
+pub type DirectorySearch<'a> =
+    dyn Fn(&Path, &str, SearchMode) -> Result<Vec<(u32, String, String)>, String> + 'a;
+
+/// Describes the last commit of a result by its full path, e.g. `Ada Lovelace, 3d ago`, or returns
+/// `None` if it has none.
+pub type LastCommitLabel<'a> = dyn Fn(&str) -> Option<String> + 'a;
+
+/// Returns the similarity of each result to a query in a mode, or `None` if it can't be rated.
+pub type Similarity<'a> =
+    dyn Fn(&str, SearchMode, &[(u32, String, String)]) -> Option<Vec<u32>> + 'a;
+
+/// The keys of the results UI and what they do, as documented in the man page.
+pub const KEYBINDINGS: &[(&str, &str)] = &[
+    ("Down, j", "Select the next result."),
+    ("Up, k", "Select the previous result."),
+    (
+        "Enter",
+        "Open the selected result in the editor. With --wait, return to the results once it exits.",
+    ),
+    (
+        "Right, l",
+        "Descend into the next directory towards the selected result, showing only the results below it.",
+    ),
+    ("Backspace, Left, h", "Go back up to the parent directory."),
+    (
+        "g",
+        "Group the results by extension. Enter on a group header collapses or expands it.",
+    ),
+    (
+        "x",
+        "Show or hide the extensions of the results. In the panel, Space shows or hides the results with the extension under the cursor.",
+    ),
+    (
+        "m",
+        "Start recording a macro of the following keys, or stop recording it.",
+    ),
+    ("@", "Replay the recorded macro."),
+    (
+        "t",
+        "Open a tab with the results of another query. Each tab keeps its own selection, grouping and scope.",
+    ),
+    ("1-9", "Switch to the tab with that number."),
+    (
+        "c",
+        "Open a tab with the counterparts of the selected result: the tests of a source file, or the sources a test covers, the closest first.",
+    ),
+    (
+        "s",
+        "Search the query of the tab again in the next mode: exact, contains, fuzzy, regex or phonetic, skipping modes the query is invalid in.",
+    ),
+    ("#", "Show the SHA-256 checksum of the selected result."),
+    (
+        ":",
+        "Run a command. :cd <directory> searches the query of the tab in another directory, walked with the same options; Tab completes the directory.",
+    ),
+    (
+        "Ctrl-K",
+        "Open the command palette: type to fuzzy-search the actions of the UI and press Enter to run the selected one.",
+    ),
+    ("q, Esc", "Quit."),
+];
+
+/// The key opening the command palette, Ctrl-K, as the control character it types.
+const PALETTE_KEY: char = '\u{b}';
+
+/// The actions of the command palette, with the keys they press. Actions the UI can't run, like
+/// new tabs without a search, are left out of the palette.
+const PALETTE_ACTIONS: &[(&str, &[KeyCode])] = &[
+    ("Open the selected result", &[KeyCode::Enter]),
+    ("Descend into the next directory", &[KeyCode::Right]),
+    ("Go back up to the parent directory", &[KeyCode::Left]),
+    ("Group by extension", &[KeyCode::Char('g')]),
+    ("Show or hide extensions", &[KeyCode::Char('x')]),
+    ("New tab with another query", &[KeyCode::Char('t')]),
+    ("Switch search mode", &[KeyCode::Char('s')]),
+    ("Open counterparts: tests or sources", &[KeyCode::Char('c')]),
+    ("Show checksum", &[KeyCode::Char('#')]),
+    (
+        "Change directory",
+        &[
+            KeyCode::Char(':'),
+            KeyCode::Char('c'),
+            KeyCode::Char('d'),
+            KeyCode::Char(' '),
+        ],
+    ),
+    ("Record a macro, or stop recording", &[KeyCode::Char('m')]),
+    ("Replay the macro", &[KeyCode::Char('@')]),
+    ("Quit", &[KeyCode::Char('q')]),
+];
+
+/// A sequence of keys given on the command line, like `jjj<enter>`.
+///
+/// Characters stand for their keys, and `<enter>`, `<esc>`, `<up>`, `<down>`, `<left>`,
+/// `<right>`, `<backspace>`, `<space>` and `<tab>` for the named ones. `<lt>` is a literal `<`
+/// and `<c-k>` is Ctrl-K, like any other letter after `c-`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySequence(pub Vec<KeyCode>);
+
+impl FromStr for KeySequence {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut keys = Vec::new();
+        let mut rest = value;
+        while let Some(character) = rest.chars().next() {
+            if character != '<' {
+                keys.push(KeyCode::Char(character));
+                rest = &rest[character.len_utf8()..];
+                continue;
+            }
+            let Some((name, after)) = rest[1..].split_once('>') else {
+                return Err(format!(
+                    "Unterminated key name in {}, write <lt> for <",
+                    value
+                ));
+            };
+            keys.push(match name.to_lowercase().as_str() {
+                "enter" | "cr" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "backspace" | "bs" => KeyCode::Backspace,
+                "space" => KeyCode::Char(' '),
+                "tab" => KeyCode::Tab,
+                "lt" => KeyCode::Char('<'),
+                name => match name.strip_prefix("c-").map(str::as_bytes) {
+                    Some(&[letter]) if letter.is_ascii_lowercase() => {
+                        KeyCode::Char(control_character(letter as char))
+                    }
+                    _ => return Err(format!("Unknown key <{}> in {}", name, value)),
+                },
+            });
+            rest = after;
+        }
+        Ok(KeySequence(keys))
+    }
+}
+
+/// Returns the control character Ctrl and a letter type, e.g. `\u{b}` for Ctrl-K.
+fn control_character(letter: char) -> char {
+    (letter.to_ascii_lowercase() as u8 & 0x1f) as char
+}
+
+/// Where the keys of the results UI come from: pending keys, which are the ones given up front
+/// and replayed macros, before the terminal.
+#[derive(Default)]
+struct KeyInput {
+    pending: VecDeque<KeyCode>,
+    /// The keys recorded so far while recording a macro.
+    recording: Option<Vec<KeyCode>>,
+    /// The last recorded macro.
+    recorded: Vec<KeyCode>,
+}
+
+impl KeyInput {
+    /// Returns the next pending key, or reads one from the terminal. Returns `None` for other
+    /// terminal events, like resizes.
+    fn read(&mut self) -> std::io::Result<Option<KeyCode>> {
+        if let Some(key) = self.pending.pop_front() {
+            return Ok(Some(key));
+        }
+        Ok(match event::read()? {
+            Event::Key(key_event) => match key_event.code {
+                KeyCode::Char(letter @ ('a'..='z' | 'A'..='Z'))
+                    if key_event.modifiers.contains(KeyModifiers::CONTROL) =>
+                {
+                    Some(KeyCode::Char(control_character(letter)))
+                }
+                code => Some(code),
+            },
+            _ => None,
+        })
+    }
+
+    /// Handles the macro keys and records the others while recording.
+    ///
+    /// # Returns
+    ///
+    /// The key, unless it was a macro key.
+    fn filter(&mut self, key: KeyCode) -> Option<KeyCode> {
+        match key {
+            KeyCode::Char('m') => {
+                match self.recording.take() {
+                    Some(keys) => self.recorded = keys,
+                    None => self.recording = Some(Vec::new()),
+                }
+                None
+            }
+            KeyCode::Char('@') => {
+                // The macro keys are never recorded, so replaying can't replay itself.
+                for key in self.recorded.iter().rev() {
+                    self.pending.push_front(*key);
+                }
+                None
+            }
+            _ => Some(self.record(key)),
+        }
+    }
+
+    /// Records a key while recording, e.g. one typed into the query of a new tab, which is never
+    /// a macro key.
+    fn record(&mut self, key: KeyCode) -> KeyCode {
+        if let Some(keys) = &mut self.recording {
+            keys.push(key);
+        }
+        key
+    }
+}
+
+/// Width of the extension panel, including its borders.
+const EXTENSION_PANEL_WIDTH: u16 = 24;
+
+/// Width of the disk usage panel, including its borders.
+const DISK_USAGE_PANEL_WIDTH: u16 = 36;
+
+/// Width of the mini bar rendered in the score column.
+const SCORE_BAR_WIDTH: usize = 8;
+
+/// Rows built below the viewport of the results table, in case it is taller than estimated.
+const VIEWPORT_MARGIN: usize = 4;
+
+/// Presentation of the optional score column.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ScoreColumn {
+    /// The raw score colored on a green (best) to red (worst) gradient.
+    Heat,
+    /// A mini bar proportional to the match quality, colored on the same gradient.
+    Bar,
+    /// The similarity to the query from 0 to 100 for an exact match, colored on the same
+    /// gradient. Unlike raw scores, it compares across query lengths and algorithms.
+    Similarity,
+}
+
+/// Normalizes the scores of the results into match qualities in `0.0..=1.0`, where `1.0` is the
+/// best possible match and the worst result in the set is close to `0.0`.
+fn match_qualities(potential_hits: &[(u32, String, String)]) -> Vec<f64> {
+    let max_score = potential_hits.iter().map(|hit| hit.0).max().unwrap_or(0);
+    potential_hits
+        .iter()
+        .map(|(score, _, _)| 1.0 - *score as f64 / (max_score as f64 + 1.0))
+        .collect()
+}
+
+/// Renders the score cell of a result in the requested presentation.
+fn score_cell(theme: &Theme, score_column: ScoreColumn, score: u32, quality: f64) -> Span<'static> {
+    let style = Style::default().fg(theme.heat_color(quality));
+    match score_column {
+        ScoreColumn::Heat | ScoreColumn::Similarity => {
+            Span::styled(score.to_string(), style.add_modifier(Modifier::BOLD))
+        }
+        ScoreColumn::Bar => {
+            let filled =
+                ((quality * SCORE_BAR_WIDTH as f64).round() as usize).clamp(1, SCORE_BAR_WIDTH);
+            let empty = SCORE_BAR_WIDTH - filled;
+            Span::styled(
+                format!("{}{}", "█".repeat(filled), "░".repeat(empty)),
+                style,
+            )
+        }
+    }
+}
+
+/// Returns the ASCII equivalent of a unicode glyph used by the UI, if it has one.
+fn ascii_equivalent(symbol: &str) -> Option<&'static str> {
+    match symbol {
+        "─" | "═" | "━" => Some("-"),
+        "│" | "║" | "┃" => Some("|"),
+        "┌" | "┐" | "└" | "┘" | "╭" | "╮" | "╰" | "╯" | "╔" | "╗" | "╚" | "╝" | "┏" | "┓" | "┗"
+        | "┛" | "├" | "┤" | "┬" | "┴" | "┼" => Some("+"),
+        "█" => Some("#"),
+        "░" => Some("."),
+        "↑" => Some("^"),
+        "▸" => Some(">"),
+        "▾" => Some("v"),
+        "↓" => Some("v"),
+        "▲" => Some("+"),
+        "▼" => Some("-"),
+        "…" => Some("~"),
+        _ => None,
+    }
+}
+
+/// Renders a widget and then replaces the unicode glyphs in its area with ASCII equivalents.
+struct AsciiOnly<W: Widget>(W);
+
+impl<W: Widget> Widget for AsciiOnly<W> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.0.render(area, buf);
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+                if let Some(replacement) = ascii_equivalent(&cell.symbol) {
+                    cell.set_symbol(replacement);
+                }
+            }
+        }
+    }
+}
+
+/// Renders a widget, restricted to ASCII glyphs when `ascii` is set.
+fn render_glyphs<B: Backend, W: Widget>(f: &mut Frame<B>, widget: W, area: Rect, ascii: bool) {
+    if ascii {
+        f.render_widget(AsciiOnly(widget), area);
+    } else {
+        f.render_widget(widget, area);
+    }
+}
+
+/// An extension present in the results, and whether the results with it are shown.
+struct ExtensionToggle {
+    /// The extension with its leading dot, or empty for files without one.
+    extension: String,
+    count: usize,
+    shown: bool,
+    /// Whether the group of the extension is collapsed while grouping.
+    collapsed: bool,
+}
+
+impl ExtensionToggle {
+    /// The extension as shown in the UI.
+    fn label(&self) -> &str {
+        if self.extension.is_empty() {
+            "(none)"
+        } else {
+            &self.extension
+        }
+    }
+}
+
+/// A row of the results table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ResultRow {
+    /// The header of the results with the extension at this index, while grouping.
+    Group(usize),
+    /// The result at this index.
+    Hit(usize),
+}
+
+/// Returns the extensions of a path with their leading dot, from the final one to the longest like
+/// [`filter::extensions`], e.g. `.js` and `.min.js`, or only an empty one when it has none.
+fn extensions_of(full_path: &str) -> Vec<String> {
+    let file_name = std::path::Path::new(full_path)
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let extensions: Vec<String> = filter::extensions(&file_name)
+        .map(|extension| format!(".{}", extension))
+        .collect();
+    if extensions.is_empty() {
+        vec![String::new()]
+    } else {
+        extensions
+    }
+}
+
+/// Returns the directories a path lies in, without the leading `.`.
+fn directories_of(full_path: &str) -> Vec<String> {
+    std::path::Path::new(full_path)
+        .parent()
+        .map(|parent| {
+            parent
+                .components()
+                .filter(|component| *component != std::path::Component::CurDir)
+                .map(|component| component.as_os_str().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Everything needed to draw one frame of the results UI.
+struct ResultsView<'a> {
+    potential_hits: Cow<'a, [(u32, String, String)]>,
+    /// The indices of the results that are shown, in order.
+    visible: Vec<usize>,
+    /// The rows of the table: the shown results, under group headers while grouping.
+    rows: Vec<ResultRow>,
+    /// The position of the selected row within `rows`.
+    selected_index: usize,
+    /// The position within `rows` of the first row in the viewport of the table, moved by drawing
+    /// to keep the selected row in view.
+    scroll_offset: Cell<usize>,
+    /// Whether the results are grouped by extension.
+    grouped: bool,
+    extensions: Vec<ExtensionToggle>,
+    /// The indices into `extensions` of the extensions of each result, like [`extensions_of`].
+    /// A result is shown while all of them are, like `--exclude`, and grouped by its longest.
+    extension_indices: Vec<Vec<usize>>,
+    /// The extension under the cursor while the extension panel is open.
+    extension_cursor: Option<usize>,
+    /// The directories descended into; only the results below them are shown.
+    scope: Vec<String>,
+    owner_labels: Option<Vec<String>>,
+    source_labels: Option<Vec<String>>,
+    last_commit: Option<&'a LastCommitLabel<'a>>,
+    /// The last commits of the results drawn so far, by index.
+    last_commits: RefCell<HashMap<usize, String>>,
+    qualities: Vec<f64>,
+    /// The similarity of each result to the query, shown by the similarity score column.
+    similarities: Option<Vec<u32>>,
+    theme: Theme,
+    score_column: Option<ScoreColumn>,
+    ascii: bool,
+    notice: Option<&'a str>,
+    /// Whether a macro is being recorded.
+    recording: bool,
+    /// The mode `s` switches to, when the candidates can be searched again.
+    next_mode: Option<SearchMode>,
+    /// How many candidates were found so far while they are still being collected.
+    scanning: Option<usize>,
+    /// The sizes of the results, summed when the disk usage panel is shown.
+    disk_usage: Option<DiskUsage>,
+    /// The checksum of the selected result shown by `#`, until the next key.
+    checksum: Option<String>,
+}
+
+impl<'a> ResultsView<'a> {
+    fn new(
+        potential_hits: impl Into<Cow<'a, [(u32, String, String)]>>,
+        options: &UiOptions<'a>,
+        theme: Theme,
+    ) -> ResultsView<'a> {
+        let potential_hits = potential_hits.into();
+        let owner_labels = options.code_owners.map(|code_owners| {
+            potential_hits
+                .iter()
+                .map(|(_, _, full_path)| code_owners.owners_of(full_path).join(" "))
+                .collect()
+        });
+        let source_labels = options.source_labels.map(|source_labels| {
+            potential_hits
+                .iter()
+                .map(|(_, _, full_path)| source_labels.get(full_path).cloned().unwrap_or_default())
+                .collect()
+        });
+        let mut extensions: Vec<ExtensionToggle> = Vec::new();
+        let extension_indices = potential_hits
+            .iter()
+            .map(|(_, _, full_path)| {
+                let mut indices = Vec::new();
+                for extension in extensions_of(full_path) {
+                    match extensions
+                        .iter()
+                        .position(|toggle| toggle.extension == extension)
+                    {
+                        Some(position) => {
+                            extensions[position].count += 1;
+                            indices.push(position);
+                        }
+                        None => {
+                            extensions.push(ExtensionToggle {
+                                extension,
+                                count: 1,
+                                shown: true,
+                                collapsed: false,
+                            });
+                            indices.push(extensions.len() - 1);
+                        }
+                    }
+                }
+                indices
+            })
+            .collect();
+        let disk_usage = options.disk_usage.then(|| DiskUsage::of(&potential_hits));
+        ResultsView {
+            visible: (0..potential_hits.len()).collect(),
+            rows: (0..potential_hits.len()).map(ResultRow::Hit).collect(),
+            qualities: match_qualities(&potential_hits),
+            similarities: None,
+            potential_hits,
+            selected_index: 0,
+            scroll_offset: Cell::new(0),
+            grouped: false,
+            extensions,
+            extension_indices,
+            extension_cursor: None,
+            scope: Vec::new(),
+            owner_labels,
+            source_labels,
+            last_commit: options.last_commit,
+            last_commits: RefCell::default(),
+            theme,
+            score_column: options.score_column,
+            ascii: options.ascii,
+            notice: options.notice,
+            recording: false,
+            next_mode: options.search.map(|_| options.mode.next()),
+            scanning: None,
+            disk_usage,
+            checksum: None,
+        }
+    }
+
+    /// Rates the similarity of the results to the query they were found by, when the similarity
+    /// score column is shown.
+    fn rate_similarity(&mut self, options: &UiOptions, query: &str, mode: SearchMode) {
+        if let (Some(ScoreColumn::Similarity), Some(similarity)) =
+            (self.score_column, options.similarity)
+        {
+            self.similarities = similarity(query, mode, &self.potential_hits);
+        }
+    }
+
+    /// Scrolls the table just enough to show the selected row, given how many rows fit in it.
+    ///
+    /// # Returns
+    ///
+    /// The range of `rows` in the viewport.
+    fn scroll_to_selection(&self, height: usize) -> std::ops::Range<usize> {
+        let height = height.max(1);
+        let mut offset = self
+            .scroll_offset
+            .get()
+            .min(self.rows.len().saturating_sub(height));
+        if self.selected_index < offset {
+            offset = self.selected_index;
+        } else if self.selected_index >= offset + height {
+            offset = self.selected_index + 1 - height;
+        }
+        self.scroll_offset.set(offset);
+        offset..(offset + height).min(self.rows.len())
+    }
+
+    /// The index of the selected result, if any result is shown.
+    fn selected_hit(&self) -> Option<usize> {
+        match self.rows.get(self.selected_index) {
+            Some(ResultRow::Hit(hit)) => Some(*hit),
+            _ => None,
+        }
+    }
+
+    /// Computes the checksum of the selected result to show it in the status line, or why it
+    /// couldn't be computed.
+    fn show_checksum(&mut self) {
+        let Some(hit) = self.selected_hit() else {
+            return;
+        };
+        let algorithm = HashAlgorithm::Sha256;
+        let full_path = &self.potential_hits[hit].2;
+        self.checksum = Some(match algorithm.file_checksum(full_path) {
+            Ok(checksum) => format!("{} {}", algorithm.name(), checksum),
+            Err(error) => error,
+        });
+    }
+
+    /// The index into `extensions` of the group of a result: its longest extension.
+    fn group_of(&self, hit: usize) -> usize {
+        *self.extension_indices[hit].last().unwrap()
+    }
+
+    /// Groups the results by extension, or lists them by rank again.
+    fn toggle_grouping(&mut self) {
+        self.grouped = !self.grouped;
+        self.refilter();
+    }
+
+    /// Collapses or expands the group whose header is selected.
+    fn toggle_group(&mut self) {
+        if let Some(ResultRow::Group(extension)) = self.rows.get(self.selected_index) {
+            let toggle = &mut self.extensions[*extension];
+            toggle.collapsed = !toggle.collapsed;
+            self.refilter();
+        }
+    }
+
+    /// Shows or hides the results with the extension under the cursor of the extension panel,
+    /// keeping the selection on the same result when it stays visible.
+    fn toggle_extension(&mut self) {
+        let Some(cursor) = self.extension_cursor else {
+            return;
+        };
+        self.extensions[cursor].shown = !self.extensions[cursor].shown;
+        self.refilter();
+    }
+
+    /// Descends one directory further towards the selected result.
+    fn descend(&mut self) {
+        let Some(hit) = self.selected_hit() else {
+            return;
+        };
+        let directories = directories_of(&self.potential_hits[hit].2);
+        if let Some(directory) = directories.get(self.scope.len()) {
+            self.scope.push(directory.clone());
+            self.refilter();
+        }
+    }
+
+    /// Goes back up to the parent of the current scope.
+    fn ascend(&mut self) {
+        if self.scope.pop().is_some() {
+            self.refilter();
+        }
+    }
+
+    /// Recomputes the shown results from the extension toggles and the scope, and the rows from
+    /// the grouping, keeping the selection on the same row when it stays visible.
+    fn refilter(&mut self) {
+        let selected_row = self.rows.get(self.selected_index).copied();
+        self.visible = (0..self.potential_hits.len())
+            .filter(|index| {
+                self.extension_indices[*index]
+                    .iter()
+                    .all(|extension| self.extensions[*extension].shown)
+            })
+            .filter(|index| directories_of(&self.potential_hits[*index].2).starts_with(&self.scope))
+            .collect();
+        self.rows = if self.grouped {
+            // Groups are ordered by their best ranked result, since extensions are collected in
+            // the order of the results.
+            let mut rows = Vec::new();
+            for (extension, toggle) in self.extensions.iter().enumerate() {
+                let mut members = self
+                    .visible
+                    .iter()
+                    .filter(|index| self.group_of(**index) == extension)
+                    .peekable();
+                if members.peek().is_none() {
+                    continue;
+                }
+                rows.push(ResultRow::Group(extension));
+                if !toggle.collapsed {
+                    rows.extend(members.map(|index| ResultRow::Hit(*index)));
+                }
+            }
+            rows
+        } else {
+            self.visible
+                .iter()
+                .map(|index| ResultRow::Hit(*index))
+                .collect()
+        };
+        self.selected_index = match selected_row {
+            // A result in a collapsed group leaves the selection on the group's header, and a
+            // hidden one on the next result.
+            Some(ResultRow::Hit(hit)) => self
+                .rows
+                .iter()
+                .position(|row| *row == ResultRow::Hit(hit))
+                .or_else(|| {
+                    let group = ResultRow::Group(self.group_of(hit));
+                    self.rows.iter().position(|row| *row == group)
+                })
+                .or_else(|| {
+                    self.rows
+                        .iter()
+                        .position(|row| matches!(row, ResultRow::Hit(index) if *index >= hit))
+                }),
+            Some(group) => self.rows.iter().position(|row| *row == group),
+            None => None,
+        }
+        .unwrap_or(self.rows.len().saturating_sub(1));
+    }
+}
+
+/// Draws the panel listing the extensions of the results with checkboxes.
+fn draw_extension_panel<B: Backend>(f: &mut Frame<B>, view: &ResultsView, area: Rect) {
+    let lines: Vec<Spans> = view
+        .extensions
+        .iter()
+        .enumerate()
+        .map(|(position, toggle)| {
+            let label = format!(
+                "[{}] {} ({})",
+                if toggle.shown { "x" } else { " " },
+                toggle.label(),
+                toggle.count
+            );
+            if view.extension_cursor == Some(position) {
+                Spans::from(Span::styled(label, view.theme.selected))
+            } else {
+                Spans::from(Span::raw(label))
+            }
+        })
+        .collect();
+    let panel =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Extensions"));
+    render_glyphs(f, panel, area, view.ascii);
+}
+
+/// Draws the panel summing the sizes of the results by extension and directory.
+fn draw_disk_usage_panel<B: Backend>(
+    f: &mut Frame<B>,
+    view: &ResultsView,
+    disk_usage: &DiskUsage,
+    area: Rect,
+) {
+    let lines: Vec<Spans> = disk_usage
+        .summary(usage::SUMMARY_TOP)
+        .into_iter()
+        .map(Spans::from)
+        .collect();
+    let panel =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Disk usage"));
+    render_glyphs(f, panel, area, view.ascii);
+}
+
+/// Builds the header row of the results with an extension, while grouping.
+fn group_header<'a>(view: &ResultsView, extension: usize, selected: bool) -> Row<'a> {
+    let toggle = &view.extensions[extension];
+    let members = view
+        .visible
+        .iter()
+        .filter(|index| view.group_of(**index) == extension)
+        .count();
+    let mut style = view.theme.header;
+    if selected {
+        style = style.patch(view.theme.selected);
+    }
+    let mut cells = vec![Span::raw("")];
+    if view.score_column.is_some() {
+        cells.push(Span::raw(""));
+    }
+    cells.push(Span::styled(
+        format!(
+            "{} {} ({})",
+            if toggle.collapsed { "▸" } else { "▾" },
+            toggle.label(),
+            members
+        ),
+        style,
+    ));
+    Row::new(cells)
+}
+
+/// Draws the results table and help line of the results UI in an area of the frame.
+fn draw_results<B: Backend>(f: &mut Frame<B>, view: &ResultsView, size: Rect) {
+    let status = match view.scanning {
+        Some(scanned) if view.ascii => Some(format!("Scanning... {} files", scanned)),
+        Some(scanned) => Some(format!("Scanning… {} files", scanned)),
+        None => view.checksum.clone().or(view.notice.map(str::to_string)),
+    };
+    // Layout for the table and help line
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Min(1),
+                Constraint::Length(2 + status.as_ref().map_or(0, |_| 1)), // For the notice and help line
+            ]
+            .as_ref(),
+        )
+        .split(size);
+
+    let mut results_area = chunks[0];
+    if !view.scope.is_empty() {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1)].as_ref())
+            .split(results_area);
+        let separator = if view.ascii { " > " } else { " › " };
+        let breadcrumbs = Paragraph::new(Span::styled(
+            format!(".{}{}", separator, view.scope.join(separator)),
+            view.theme.header,
+        ));
+        render_glyphs(f, breadcrumbs, rows[0], view.ascii);
+        results_area = rows[1];
+    }
+    if let Some(disk_usage) = &view.disk_usage {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Min(1),
+                    Constraint::Length(DISK_USAGE_PANEL_WIDTH),
+                ]
+                .as_ref(),
+            )
+            .split(results_area);
+        results_area = columns[0];
+        draw_disk_usage_panel(f, view, disk_usage, columns[1]);
+    }
+    if view.extension_cursor.is_some() {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Min(1),
+                    Constraint::Length(EXTENSION_PANEL_WIDTH),
+                ]
+                .as_ref(),
+            )
+            .split(results_area);
+        results_area = columns[0];
+        draw_extension_panel(f, view, columns[1]);
+    }
+
+    if view.visible.is_empty() {
+        let message = match view.next_mode {
+            _ if view.scanning.is_some() => "No results found yet.".to_string(),
+            _ if !view.potential_hits.is_empty() => {
+                "All results are hidden by the extension panel.".to_string()
+            }
+            Some(mode) => format!(
+                "No results found. Press s to search in {} mode.",
+                mode.name()
+            ),
+            None => "No results found.".to_string(),
+        };
+        let no_results = Paragraph::new(Span::styled(
+            message,
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        render_glyphs(f, no_results, results_area, view.ascii);
+    } else {
+        // Only the rows in the viewport are built, inside the borders and below the header.
+        let viewport = view.scroll_to_selection(results_area.height.saturating_sub(3) as usize);
+        let built = viewport.start..(viewport.end + VIEWPORT_MARGIN).min(view.rows.len());
+        let rows: Vec<Row> = view.rows[built.clone()]
+            .iter()
+            .zip(built)
+            .map(|(row, position)| {
+                let index = match *row {
+                    ResultRow::Hit(index) => index,
+                    ResultRow::Group(extension) => {
+                        return group_header(view, extension, position == view.selected_index);
+                    }
+                };
+                let (score, file_name, full_path) = &view.potential_hits[index];
+                let mut style = if *score == 0 {
+                    view.theme.exact
+                } else {
+                    view.theme.fuzzy
+                };
+                if position == view.selected_index {
+                    style = style.patch(view.theme.selected);
+                }
+                let mut cells = vec![Span::raw((index + 1).to_string())];
+                if let Some(score_column) = view.score_column {
+                    // Without similarities, the similarity column falls back to the raw scores.
+                    let (score, quality) = match &view.similarities {
+                        Some(similarities) => {
+                            (similarities[index], similarities[index] as f64 / 100.0)
+                        }
+                        None => (*score, view.qualities[index]),
+                    };
+                    cells.push(score_cell(&view.theme, score_column, score, quality));
+                }
+                if search::is_directory_path(full_path) {
+                    let name = format!("{}{}", file_name, std::path::MAIN_SEPARATOR);
+                    cells.push(Span::styled(name, style.add_modifier(Modifier::BOLD)));
+                } else {
+                    cells.push(Span::styled(file_name.clone(), style));
+                }
+                cells.push(Span::raw(full_path.clone()));
+                if let Some(owner_labels) = &view.owner_labels {
+                    cells.push(Span::raw(owner_labels[index].clone()));
+                }
+                // Only the rows on screen are drawn, so git is asked about those alone.
+                if let Some(last_commit) = view.last_commit {
+                    let mut last_commits = view.last_commits.borrow_mut();
+                    let label = last_commits
+                        .entry(index)
+                        .or_insert_with(|| last_commit(full_path).unwrap_or_default());
+                    cells.push(Span::raw(label.clone()));
+                }
+                if let Some(source_labels) = &view.source_labels {
+                    cells.push(Span::raw(source_labels[index].clone()));
+                }
+                Row::new(cells)
+            })
+            .collect();
+
+        let header_style = view.theme.header;
+        let mut header = vec![Span::styled("No.", header_style)];
+        let mut widths = vec![Constraint::Length(5)];
+        let mut name_percentage = 30;
+        let mut path_percentage = 65;
+        if let Some(score_column) = view.score_column {
+            header.push(Span::styled("Score", header_style));
+            widths.push(Constraint::Length(match score_column {
+                ScoreColumn::Heat | ScoreColumn::Similarity => 6,
+                ScoreColumn::Bar => SCORE_BAR_WIDTH as u16 + 1,
+            }));
+            path_percentage -= 5;
+        }
+        header.push(Span::styled("File Name", header_style));
+        header.push(Span::styled("Full Path", header_style));
+        if view.owner_labels.is_some() {
+            header.push(Span::styled("Owners", header_style));
+            name_percentage -= 5;
+            path_percentage -= 15;
+        }
+        if view.last_commit.is_some() {
+            header.push(Span::styled("Last Commit", header_style));
+            name_percentage -= 5;
+            path_percentage -= 25;
+        }
+        if view.source_labels.is_some() {
+            header.push(Span::styled("Source", header_style));
+            path_percentage -= 20;
+        }
+        widths.push(Constraint::Percentage(name_percentage));
+        widths.push(Constraint::Percentage(path_percentage));
+        if view.owner_labels.is_some() {
+            widths.push(Constraint::Percentage(20));
+        }
+        if view.last_commit.is_some() {
+            widths.push(Constraint::Percentage(30));
+        }
+        if view.source_labels.is_some() {
+            widths.push(Constraint::Percentage(20));
+        }
+
+        // Table widget
+        let table = Table::new(rows)
+            .header(Row::new(header))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Search Results"),
+            )
+            .widths(&widths);
+
+        render_glyphs(f, table, results_area, view.ascii);
+    }
+
+    // Help/instructions line
+    let mut help_area = chunks[1];
+    if let Some(status) = status {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1)].as_ref())
+            .split(help_area);
+        let notice = Paragraph::new(Span::styled(
+            status,
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        render_glyphs(f, notice, rows[0], view.ascii);
+        help_area = rows[1];
+    }
+    let keys = match (view.extension_cursor, view.ascii) {
+        // Only quitting is possible until the results are complete.
+        _ if view.scanning.is_some() => "q/Esc: Quit",
+        (Some(_), true) => "Up/Down or j/k: Move  Space: Show/hide  x/Esc: Close",
+        (Some(_), false) => "↑/↓ or j/k: Move  Space: Show/hide  x/Esc: Close",
+        (None, true) => "Up/Down or j/k: Move  Right/l: Descend  Enter: Open  g: Group  x: Extensions  q/Esc: Quit",
+        (None, false) => "↑/↓ or j/k: Move  →/l: Descend  Enter: Open  g: Group  x: Extensions  q/Esc: Quit",
+    };
+    let mut help = Vec::new();
+    if view.recording {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        help.push(Span::styled("Recording, m: Stop  ", bold));
+    }
+    help.push(Span::raw(keys));
+    let help = Paragraph::new(Spans::from(help))
+        // The help line wraps onto the second line of its area in narrow terminals.
+        .wrap(Wrap { trim: true });
+    render_glyphs(f, help, help_area, view.ascii);
+}
+
+/// A search shown in the results UI, with its own results and state.
+struct ResultTab<'a> {
+    query: String,
+    mode: SearchMode,
+    /// The directory the tab was moved to with `:cd`, searched instead of the candidates of the UI.
+    root: Option<PathBuf>,
+    view: ResultsView<'a>,
+}
+
+/// The tabs of the results UI.
+struct ResultTabs<'a> {
+    tabs: Vec<ResultTab<'a>>,
+    /// The mode the UI was started in. Tabs in other modes show theirs in the tab bar.
+    mode: SearchMode,
+    /// The index of the shown tab.
+    active: usize,
+    /// The query of a new tab while it's typed, and why its last search failed.
+    new_tab: Option<(String, Option<String>)>,
+    /// The command after `:` while it's typed, and why it last failed.
+    command: Option<(String, Option<String>)>,
+    /// The command palette while it's open.
+    palette: Option<Palette>,
+}
+
+/// The command palette, fuzzy-searching the actions of the UI by name.
+struct Palette {
+    query: String,
+    /// The actions the UI can run, with their keys.
+    actions: Vec<(&'static str, &'static [KeyCode])>,
+    /// The index of the selected action among the matching ones.
+    selected: usize,
+}
+
+impl Palette {
+    /// Opens the palette with the actions the UI can run.
+    fn new(options: &UiOptions) -> Palette {
+        let actions = PALETTE_ACTIONS
+            .iter()
+            .copied()
+            .filter(|(_, keys)| match keys[0] {
+                KeyCode::Char('t') | KeyCode::Char('s') => options.search.is_some(),
+                KeyCode::Char(':') => options.search_directory.is_some(),
+                KeyCode::Char('c') => options.counterparts.is_some(),
+                _ => true,
+            })
+            .collect();
+        Palette {
+            query: String::new(),
+            actions,
+            selected: 0,
+        }
+    }
+
+    /// Returns the actions matching the query, best first, or all of them without a query.
+    fn matches(&self) -> Vec<(&'static str, &'static [KeyCode])> {
+        if self.query.is_empty() {
+            return self.actions.clone();
+        }
+        let query = self.query.to_lowercase();
+        let scorer = FuzzySearchAlgorithm::Subsequence;
+        let threshold = scorer.threshold(&query);
+        let mut matches: Vec<(u32, (&'static str, &'static [KeyCode]))> = self
+            .actions
+            .iter()
+            .filter_map(|action| {
+                let score = scorer.score(&query, &action.0.to_lowercase()).ok()?;
+                (score <= threshold).then_some((score, *action))
+            })
+            .collect();
+        matches.sort_by_key(|(score, _)| *score);
+        matches.into_iter().map(|(_, action)| action).collect()
+    }
+
+    /// Handles a key typed into the palette.
+    ///
+    /// # Returns
+    ///
+    /// The keys of the action picked with Enter, to be pressed in place of it.
+    fn type_key(&mut self, key: KeyCode) -> Option<&'static [KeyCode]> {
+        match key {
+            KeyCode::Down => {
+                self.selected = (self.selected + 1).min(self.matches().len().saturating_sub(1));
+            }
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Enter => {
+                return self.matches().get(self.selected).map(|(_, keys)| *keys);
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.selected = 0;
+            }
+            KeyCode::Char(character) => {
+                self.query.push(character);
+                self.selected = 0;
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+impl<'a> ResultTabs<'a> {
+    /// Returns the view of the shown tab.
+    fn view(&mut self) -> &mut ResultsView<'a> {
+        &mut self.tabs[self.active].view
+    }
+
+    /// Handles a key typed into the query of a new tab.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The typed key.
+    /// * `options` - The options of the UI, with the search the new tab runs.
+    /// * `theme` - The theme of the new tab.
+    fn type_query(&mut self, key: KeyCode, options: &UiOptions<'a>, theme: Theme) {
+        let Some((query, error)) = &mut self.new_tab else {
+            return;
+        };
+        // The new tab searches in the mode and directory of the shown one.
+        let mode = self.tabs[self.active].mode;
+        let root = self.tabs[self.active].root.clone();
+        match key {
+            KeyCode::Esc => self.new_tab = None,
+            KeyCode::Enter if !query.is_empty() => {
+                match search_in(options, root.as_deref(), query, mode) {
+                    Ok(potential_hits) => {
+                        let view = searched_view(potential_hits, query, mode, options, theme);
+                        self.tabs.push(ResultTab {
+                            query: std::mem::take(query),
+                            mode,
+                            root,
+                            view,
+                        });
+                        self.active = self.tabs.len() - 1;
+                        self.new_tab = None;
+                    }
+                    Err(message) => *error = Some(message),
+                }
+            }
+            KeyCode::Backspace => {
+                query.pop();
+            }
+            KeyCode::Char(character) => {
+                query.push(character);
+                *error = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Opens a tab with the counterparts of the selected result, found by name among the same
+    /// candidates: the tests of a source file, or the sources of a test. Results without test
+    /// patterns for their extension are left alone.
+    fn open_counterpart(&mut self, options: &UiOptions<'a>, theme: Theme) {
+        let Some(patterns) = options.counterparts else {
+            return;
+        };
+        let tab = &self.tabs[self.active];
+        let Some(hit) = tab.view.selected_hit() else {
+            return;
+        };
+        let full_path = tab.view.potential_hits[hit].2.clone();
+        let root = tab.root.clone();
+        let exact = |name: &str| search_in(options, root.as_deref(), name, SearchMode::Exact);
+        if let Ok(Some(counterparts)) = counterpart::find_counterparts(patterns, &full_path, &exact)
+        {
+            let view = searched_view(
+                counterparts.potential_hits,
+                &counterparts.query,
+                SearchMode::Exact,
+                options,
+                theme,
+            );
+            self.tabs.push(ResultTab {
+                query: counterparts.query,
+                mode: SearchMode::Exact,
+                root,
+                view,
+            });
+            self.active = self.tabs.len() - 1;
+        }
+    }
+
+    /// Searches the query of the shown tab again in the next mode it's valid in, e.g. in contains
+    /// mode once an exact search found nothing, replacing the results of the tab.
+    fn switch_mode(&mut self, options: &UiOptions<'a>, theme: Theme) {
+        let tab = &mut self.tabs[self.active];
+        let mut mode = tab.mode.next();
+        // Modes the query is invalid in, e.g. regex mode for `c++`, are skipped.
+        while mode != tab.mode {
+            if let Ok(potential_hits) = search_in(options, tab.root.as_deref(), &tab.query, mode) {
+                tab.mode = mode;
+                tab.view = searched_view(potential_hits, &tab.query, mode, options, theme);
+                return;
+            }
+            mode = mode.next();
+        }
+    }
+
+    /// Handles a key typed into the command after `:`. `cd <directory>` moves the shown tab to
+    /// another directory, relative to the one it searches, and searches its query there.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The typed key. Tab completes the directory of `cd`.
+    /// * `options` - The options of the UI, with the search of other directories.
+    /// * `theme` - The theme of the searched results.
+    fn type_command(&mut self, key: KeyCode, options: &UiOptions<'a>, theme: Theme) {
+        let Some((command, error)) = &mut self.command else {
+            return;
+        };
+        let tab = &mut self.tabs[self.active];
+        let base = tab.root.clone().unwrap_or_else(|| PathBuf::from("."));
+        match key {
+            KeyCode::Esc => self.command = None,
+            KeyCode::Tab => {
+                if let Some(completed) = command
+                    .strip_prefix("cd ")
+                    .and_then(|partial| complete_directory(&base, partial.trim_start()))
+                {
+                    *command = format!("cd {}", completed);
+                    *error = None;
+                }
+            }
+            KeyCode::Enter => {
+                let result = match command.split_once(' ') {
+                    Some(("cd", directory)) if !directory.trim().is_empty() => {
+                        let root = base.join(expand_home(directory.trim()));
+                        match options.search_directory {
+                            Some(_) if !root.is_dir() => {
+                                Err(format!("{} is not a directory.", root.display()))
+                            }
+                            Some(search_directory) => search_directory(&root, &tab.query, tab.mode)
+                                .map(|potential_hits| (root, potential_hits)),
+                            None => Err("Other directories can't be searched here.".to_string()),
+                        }
+                    }
+                    _ => Err(format!("Unknown command {}. Try cd <directory>.", command)),
+                };
+                match result {
+                    Ok((root, potential_hits)) => {
+                        tab.view =
+                            searched_view(potential_hits, &tab.query, tab.mode, options, theme);
+                        tab.root = Some(root);
+                        self.command = None;
+                    }
+                    Err(message) => *error = Some(message),
+                }
+            }
+            KeyCode::Backspace => {
+                command.pop();
+                *error = None;
+            }
+            KeyCode::Char(character) => {
+                command.push(character);
+                *error = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Searches for a query among the candidates of the UI, or in the directory a tab was moved to.
+fn search_in(
+    options: &UiOptions,
+    root: Option<&Path>,
+    query: &str,
+    mode: SearchMode,
+) -> Result<Vec<(u32, String, String)>, String> {
+    match (root, options.search, options.search_directory) {
+        (Some(root), _, Some(search_directory)) => search_directory(root, query, mode),
+        (None, Some(search), _) => search(query, mode),
+        _ => Err("The results can't be searched again.".to_string()),
+    }
+}
+
+/// Replaces a leading `~` with the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), crate::storage::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest.trim_start_matches(['/', '\\'])),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Completes the last component of a directory typed after `:cd`, e.g. `../fzls` to
+/// `../fuzzy-ls/`, with the subdirectory its letters best match in order. Hidden directories are
+/// only completed once a `.` is typed.
+///
+/// # Arguments
+///
+/// * `base` - The directory relative paths start from.
+/// * `partial` - The directory typed so far.
+///
+/// # Returns
+///
+/// The completed directory, or `None` if no subdirectory matches.
+fn complete_directory(base: &Path, partial: &str) -> Option<String> {
+    let split = partial.rfind(['/', '\\']).map_or(0, |index| index + 1);
+    let (parent, fragment) = partial.split_at(split);
+    if fragment.is_empty() {
+        return None;
+    }
+    let fragment = fragment.to_lowercase();
+    let scorer = FuzzySearchAlgorithm::Subsequence;
+    let threshold = scorer.threshold(&fragment);
+    let entries = std::fs::read_dir(base.join(expand_home(parent))).ok()?;
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !entry.path().is_dir() || (name.starts_with('.') && !fragment.starts_with('.')) {
+                return None;
+            }
+            let score = scorer.score(&fragment, &name.to_lowercase()).ok()?;
+            (score <= threshold).then_some((score, name.len(), name))
+        })
+        .min()
+        .map(|(_, _, name)| format!("{}{}/", parent, name))
+}
+
+/// Builds the view of results searched for in the UI, for `query` in `mode`.
+fn searched_view<'a>(
+    potential_hits: Vec<(u32, String, String)>,
+    query: &str,
+    mode: SearchMode,
+    options: &UiOptions<'a>,
+    theme: Theme,
+) -> ResultsView<'a> {
+    let mut view = ResultsView::new(potential_hits, options, theme);
+    view.rate_similarity(options, query, mode);
+    // The notice is about the results of the first search.
+    view.notice = None;
+    view.next_mode = Some(mode.next());
+    view
+}
+
+/// Draws the tab bar, or the query of a new tab or a command while it's typed, above the results
+/// of the shown tab.
+fn draw_tabs<B: Backend>(f: &mut Frame<B>, tabs: &ResultTabs) {
+    let view = &tabs.tabs[tabs.active].view;
+    let mut area = f.size();
+    let switched = tabs
+        .tabs
+        .iter()
+        .any(|tab| tab.mode != tabs.mode || tab.root.is_some());
+    if tabs.tabs.len() > 1 || tabs.new_tab.is_some() || tabs.command.is_some() || switched {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1)].as_ref())
+            .split(area);
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let prompt = match (&tabs.new_tab, &tabs.command) {
+            (Some((query, error)), _) => Some(("New tab: ", query, "  Enter: Search", error)),
+            (None, Some((command, error))) => {
+                Some((":", command, "  Tab: Complete  Enter: Run", error))
+            }
+            (None, None) => None,
+        };
+        let line = match prompt {
+            Some((label, text, keys, error)) => {
+                let mut line = vec![
+                    Span::styled(label, bold),
+                    Span::raw(format!("{}_", text)),
+                    Span::raw(format!("{}  Esc: Cancel", keys)),
+                ];
+                if let Some(error) = error {
+                    line.push(Span::styled(format!("  {}", error), view.theme.header));
+                }
+                line
+            }
+            None => tabs
+                .tabs
+                .iter()
+                .enumerate()
+                .map(|(index, tab)| {
+                    let mut title = format!(" {} {}", index + 1, tab.query);
+                    if tab.mode != tabs.mode {
+                        title.push_str(&format!(" ({})", tab.mode.name()));
+                    }
+                    if let Some(root) = &tab.root {
+                        title.push_str(&format!(" in {}", root.display()));
+                    }
+                    title.push(' ');
+                    if index == tabs.active {
+                        Span::styled(title, view.theme.selected)
+                    } else {
+                        Span::raw(title)
+                    }
+                })
+                .collect(),
+        };
+        render_glyphs(f, Paragraph::new(Spans::from(line)), rows[0], view.ascii);
+        area = rows[1];
+    }
+    draw_results(f, view, area);
+    if let Some(palette) = &tabs.palette {
+        draw_palette(f, palette, view);
+    }
+}
+
+/// Draws the command palette over the middle of the results.
+fn draw_palette<B: Backend>(f: &mut Frame<B>, palette: &Palette, view: &ResultsView) {
+    let matches = palette.matches();
+    let size = f.size();
+    let width = size.width.min(48);
+    let height = size.height.min(matches.len() as u16 + 3);
+    let area = Rect::new(
+        (size.width - width) / 2,
+        (size.height - height) / 2,
+        width,
+        height,
+    );
+    let mut lines = vec![Spans::from(vec![
+        Span::styled("> ", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(format!("{}_", palette.query)),
+    ])];
+    lines.extend(matches.iter().enumerate().map(|(index, (name, _))| {
+        if index == palette.selected {
+            Spans::from(Span::styled(format!(" {} ", name), view.theme.selected))
+        } else {
+            Spans::from(Span::raw(format!(" {} ", name)))
+        }
+    }));
+    let block = Block::default().borders(Borders::ALL).title(" Commands ");
+    f.render_widget(Clear, area);
+    render_glyphs(f, Paragraph::new(lines).block(block), area, view.ascii);
+}
 
 fn flush_input_events() -> std::io::Result<()> {
     while event::poll(Duration::from_millis(0))? {
@@ -20,95 +1467,193 @@ fn flush_input_events() -> std::io::Result<()> {
     Ok(())
 }
 
+/// The terminal of the results UI, in raw mode on the alternate screen until it is restored or
+/// dropped.
+pub struct ResultsTerminal {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+    active: bool,
+    /// How long setting up the terminal took.
+    pub setup_time: Duration,
+}
+
+impl ResultsTerminal {
+    /// Switches the terminal to raw mode and the alternate screen.
+    pub fn setup() -> std::io::Result<ResultsTerminal> {
+        let start = Instant::now();
+        enable_raw_mode()?;
+        let mut stdout = std::io::stdout();
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+        let terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+        Ok(ResultsTerminal {
+            terminal,
+            active: true,
+            setup_time: start.elapsed(),
+        })
+    }
+
+    /// Gives the terminal back to the shell.
+    fn restore(&mut self) -> std::io::Result<()> {
+        self.active = false;
+        disable_raw_mode()?;
+        execute!(
+            self.terminal.backend_mut(),
+            LeaveAlternateScreen,
+            DisableMouseCapture
+        )?;
+        self.terminal.show_cursor()
+    }
+}
+
+impl Drop for ResultsTerminal {
+    fn drop(&mut self) {
+        // Errors and panics before the UI ran must not leave the shell in raw mode.
+        if self.active {
+            let _ = self.restore();
+        }
+    }
+}
+
+/// A [`ResultsTerminal`] being set up on a background thread, so it's ready once the candidates
+/// have been collected.
+pub struct PendingTerminal {
+    handle: Option<JoinHandle<std::io::Result<ResultsTerminal>>>,
+}
+
+impl PendingTerminal {
+    /// Starts setting up the terminal.
+    pub fn start() -> PendingTerminal {
+        PendingTerminal {
+            handle: Some(std::thread::spawn(ResultsTerminal::setup)),
+        }
+    }
+
+    /// Waits until the terminal is set up.
+    pub fn wait(mut self) -> std::io::Result<ResultsTerminal> {
+        match self.handle.take().map(JoinHandle::join) {
+            Some(Ok(terminal)) => terminal,
+            Some(Err(panic)) => std::panic::resume_unwind(panic),
+            None => unreachable!("the terminal is only waited for once"),
+        }
+    }
+}
+
+impl Drop for PendingTerminal {
+    fn drop(&mut self) {
+        // Wait for the setup to finish, so the terminal it set up is restored before exiting.
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Shows the results found while the candidates are still being collected, e.g. during the walk of
+/// a large tree, until the complete results are shown by [`display_results_ui`].
+pub struct ScanScreen<'a> {
+    results_terminal: ResultsTerminal,
+    options: UiOptions<'a>,
+    theme: Theme,
+    quit: bool,
+}
+
+impl<'a> ScanScreen<'a> {
+    /// Takes over the terminal to show the results found so far.
+    ///
+    /// # Arguments
+    ///
+    /// * `results_terminal` - The terminal to draw in.
+    /// * `options` - The options of the UI; the search and keys don't apply while scanning.
+    pub fn new(results_terminal: ResultsTerminal, options: UiOptions<'a>) -> ScanScreen<'a> {
+        ScanScreen {
+            results_terminal,
+            theme: Theme::resolve(options.theme),
+            options,
+            quit: false,
+        }
+    }
+
+    /// Draws the results found so far and reads the keys typed since the last draw.
+    ///
+    /// # Arguments
+    ///
+    /// * `potential_hits` - The results found so far, best first.
+    /// * `scanned` - How many candidates were found so far.
+    ///
+    /// # Returns
+    ///
+    /// Whether q or Esc was pressed to quit.
+    pub fn draw(
+        &mut self,
+        potential_hits: &[(u32, String, String)],
+        scanned: usize,
+    ) -> std::io::Result<bool> {
+        let mut view = ResultsView::new(potential_hits, &self.options, self.theme);
+        view.scanning = Some(scanned);
+        self.results_terminal
+            .terminal
+            .draw(|f| draw_results(f, &view, f.size()))?;
+        while event::poll(Duration::from_millis(0))? {
+            if let Event::Key(key_event) = event::read()? {
+                if matches!(key_event.code, KeyCode::Char('q') | KeyCode::Esc) {
+                    self.quit = true;
+                }
+            }
+        }
+        Ok(self.quit)
+    }
+
+    /// Whether q or Esc was pressed to quit.
+    pub fn quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Gives the terminal back, once the results are complete.
+    pub fn finish(self) -> ResultsTerminal {
+        self.results_terminal
+    }
+}
+
 /// Displays the results of the search in a TUI interface.
 /// The results are displayed in a table format with columns for the file name and full path.
 /// The user can exit the interface by pressing 'q' or 'Esc'.
+/// Additional columns and the glyphs used are controlled by `options`.
+/// Returns the indices of the results of the first search opened in the editor, to learn from, and
+/// the full paths of every file opened, from any tab.
 pub fn display_results_ui(
+    mut results_terminal: ResultsTerminal,
     potential_hits: Vec<(u32, String, String)>,
     default_editor_command: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    enable_raw_mode()?;
-    let mut stdout = std::io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    options: UiOptions,
+) -> Result<(Vec<usize>, Vec<String>), Box<dyn std::error::Error>> {
+    let theme = Theme::resolve(options.theme);
+    let terminal = &mut results_terminal.terminal;
 
-    let mut selected_index: usize = 0;
-    let num_results = potential_hits.len();
+    let mut picked: Vec<usize> = Vec::new();
+    let mut opened: Vec<String> = Vec::new();
+    let mut view = ResultsView::new(&potential_hits, &options, theme);
+    view.rate_similarity(&options, options.query, options.mode);
+    let mut tabs = ResultTabs {
+        tabs: vec![ResultTab {
+            query: options.query.to_string(),
+            mode: options.mode,
+            root: None,
+            view,
+        }],
+        mode: options.mode,
+        active: 0,
+        new_tab: None,
+        command: None,
+        palette: None,
+    };
+    let mut input = KeyInput {
+        pending: options.keys.iter().copied().collect(),
+        ..KeyInput::default()
+    };
 
     // Flush input events before starting the main loop
     flush_input_events()?;
 
     loop {
-        terminal.draw(|f| {
-            let size = f.size();
-
-            // Layout for the table and help line
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([
-                    Constraint::Min(1),
-                    Constraint::Length(2), // For help line
-                ].as_ref())
-                .split(size);
-
-            if potential_hits.is_empty() {
-                let no_results = Paragraph::new(Span::styled(
-                    "No results found.",
-                    Style::default().add_modifier(Modifier::BOLD),
-                ));
-                f.render_widget(no_results, chunks[0]);
-            } else {
-                // Table rows
-                let rows: Vec<Row> = potential_hits
-                    .iter()
-                    .enumerate()
-                    .map(|(index, (score, file_name, full_path))| {
-                        let mut style = if *score == 0 {
-                            Style::default()
-                                .fg(Color::Green)
-                                .add_modifier(Modifier::BOLD)
-                        } else {
-                            Style::default().fg(Color::Blue)
-                        };
-                        if index == selected_index {
-                            style = style.bg(Color::Yellow).fg(Color::Black);
-                        }
-                        Row::new(vec![
-                            Span::raw((index + 1).to_string()),
-                            Span::styled(file_name.clone(), style),
-                            Span::raw(full_path.clone()),
-                        ])
-                    })
-                    .collect();
-
-                // Table widget
-                let table = Table::new(rows)
-                    .header(Row::new(vec![
-                        Span::styled("No.", Style::default().add_modifier(Modifier::BOLD)),
-                        Span::styled("File Name", Style::default().add_modifier(Modifier::BOLD)),
-                        Span::styled("Full Path", Style::default().add_modifier(Modifier::BOLD)),
-                    ]))
-                    .block(
-                        Block::default()
-                            .borders(Borders::ALL)
-                            .title("Search Results"),
-                    )
-                    .widths(&[
-                        Constraint::Length(5),
-                        Constraint::Percentage(30),
-                        Constraint::Percentage(65),
-                    ]);
-
-                f.render_widget(table, chunks[0]);
-            }
-
-            // Help/instructions line
-            let help = Paragraph::new(Span::raw(
-                "↑/↓ or j/k: Move  Enter: Open  q/Esc: Quit"
-            ));
-            f.render_widget(help, chunks[1]);
-        })?;
+        terminal.draw(|f| draw_tabs(f, &tabs))?;
 
         // Flush any remaining input events to prevent key repeat issues on Windows
         // Also add a small delay to prevent rapid key processing
@@ -116,26 +1661,137 @@ pub fn display_results_ui(
         std::thread::sleep(Duration::from_millis(10));
 
         // Handle user input for navigation
-        if let Event::Key(key_event) = event::read()? {
-            match key_event.code {
-                KeyCode::Char('q') | KeyCode::Esc => break, // Exit
-                KeyCode::Down | KeyCode::Char('j') => {
-                    if selected_index + 1 < num_results {
-                        selected_index += 1;
+        let key = match input.read()? {
+            Some(key) if tabs.new_tab.is_some() => {
+                tabs.type_query(input.record(key), &options, theme);
+                None
+            }
+            Some(key) if tabs.command.is_some() => {
+                tabs.type_command(input.record(key), &options, theme);
+                None
+            }
+            // The palette's own keys aren't recorded, only the keys of the action it runs.
+            Some(key) if tabs.palette.is_some() => {
+                let palette = tabs.palette.as_mut().expect("the palette is open");
+                match key {
+                    KeyCode::Esc | KeyCode::Char(PALETTE_KEY) => tabs.palette = None,
+                    KeyCode::Enter => {
+                        for key in palette.type_key(key).unwrap_or_default().iter().rev() {
+                            input.pending.push_front(*key);
+                        }
+                        tabs.palette = None;
+                    }
+                    key => {
+                        palette.type_key(key);
+                    }
+                }
+                None
+            }
+            Some(KeyCode::Char(PALETTE_KEY)) => {
+                tabs.palette = Some(Palette::new(&options));
+                None
+            }
+            key => key.and_then(|key| input.filter(key)),
+        };
+        tabs.view().recording = input.recording.is_some();
+        // Picks are only learned from the results of the query the UI was started with, in the mode
+        // it was started in.
+        let first_tab = tabs.active == 0 && tabs.tabs[0].mode == tabs.mode;
+        let view = tabs.view();
+        if let Some(key) = key {
+            view.checksum = None;
+            let num_rows = view.rows.len();
+            let selected_hit = view.selected_hit();
+            if let Some(cursor) = view.extension_cursor {
+                match key {
+                    KeyCode::Char('x') | KeyCode::Esc => view.extension_cursor = None,
+                    KeyCode::Down | KeyCode::Char('j') if cursor + 1 < view.extensions.len() => {
+                        view.extension_cursor = Some(cursor + 1);
                     }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        view.extension_cursor = Some(cursor.saturating_sub(1));
+                    }
+                    KeyCode::Char(' ') => view.toggle_extension(),
+                    _ => {}
+                }
+                continue;
+            }
+            match key {
+                KeyCode::Char('q') | KeyCode::Esc => break, // Exit
+                KeyCode::Down | KeyCode::Char('j') if view.selected_index + 1 < num_rows => {
+                    view.selected_index += 1;
                 }
                 KeyCode::Up | KeyCode::Char('k') => {
-                    if selected_index > 0 {
-                        selected_index -= 1;
+                    view.selected_index = view.selected_index.saturating_sub(1);
+                }
+                KeyCode::Right | KeyCode::Char('l') => view.descend(),
+                KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => view.ascend(),
+                KeyCode::Char('g') => view.toggle_grouping(),
+                KeyCode::Char('x') if !view.extensions.is_empty() => {
+                    view.extension_cursor = Some(0);
+                }
+                KeyCode::Char('t') if options.search.is_some() => {
+                    tabs.new_tab = Some((String::new(), None));
+                }
+                KeyCode::Char('s') if options.search.is_some() => {
+                    tabs.switch_mode(&options, theme);
+                }
+                KeyCode::Char(':') if options.search_directory.is_some() => {
+                    tabs.command = Some((String::new(), None));
+                }
+                KeyCode::Char('c') => tabs.open_counterpart(&options, theme),
+                KeyCode::Char('#') => view.show_checksum(),
+                KeyCode::Char(digit @ '1'..='9') => {
+                    let index = digit as usize - '1' as usize;
+                    if index < tabs.tabs.len() {
+                        tabs.active = index;
+                    }
+                }
+                KeyCode::Enter if selected_hit.is_none() => view.toggle_group(),
+                KeyCode::Enter if options.wait => {
+                    let selected_hit = selected_hit.unwrap_or_default();
+                    // Hand the terminal over to the editor until it exits.
+                    disable_raw_mode()?;
+                    execute!(
+                        terminal.backend_mut(),
+                        LeaveAlternateScreen,
+                        DisableMouseCapture
+                    )?;
+                    terminal.show_cursor()?;
+                    let edited = editor::edit_selection(
+                        &editor::SystemLauncher,
+                        default_editor_command,
+                        &view.potential_hits,
+                        &[selected_hit],
+                    );
+                    enable_raw_mode()?;
+                    execute!(
+                        terminal.backend_mut(),
+                        EnterAlternateScreen,
+                        EnableMouseCapture
+                    )?;
+                    terminal.clear()?;
+                    edited?;
+                    opened.push(view.potential_hits[selected_hit].2.clone());
+                    if first_tab && !picked.contains(&selected_hit) {
+                        picked.push(selected_hit);
                     }
                 }
                 KeyCode::Enter => {
-                    if num_results > 0 {
-                        let (_score, _file_name, full_path) = &potential_hits[selected_index];
-                        open_in_new_terminal(default_editor_command, &[full_path])
-                            .expect("Failed to open file in the editor.");
-                        break;
+                    let selected_hit = selected_hit.unwrap_or_default();
+                    editor::open_selection(
+                        &editor::SystemLauncher,
+                        options.terminal,
+                        default_editor_command,
+                        &view.potential_hits,
+                        &[selected_hit],
+                    )
+                    .expect("Failed to open file in the editor.");
+                    opened.push(view.potential_hits[selected_hit].2.clone());
+                    if first_tab {
+                        picked = vec![selected_hit];
                     }
+                    break;
                 }
                 _ => {}
             }
@@ -147,38 +1803,930 @@ pub fn display_results_ui(
         std::thread::sleep(Duration::from_millis(10));
     }
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    results_terminal.restore()?;
 
-    Ok(())
+    Ok((picked, opened))
 }
 
-fn open_in_new_terminal(command: &str, args: &[&str]) -> Result<(), std::io::Error> {
-    #[cfg(target_os = "windows")]
-    let terminal_cmd = "cmd";
-    #[cfg(target_os = "windows")]
-    let terminal_args = &["/c", "start", command];
+/// The results of one of the algorithms compared by [`display_comparison_ui`].
+struct ComparisonSide<'a> {
+    algorithm: &'a str,
+    potential_hits: &'a [(u32, String, String)],
+    /// How many places higher each result ranks than with the other algorithm, or `None` if the
+    /// other algorithm didn't find it.
+    deltas: Vec<Option<isize>>,
+}
 
-    #[cfg(target_os = "linux")]
-    let terminal_cmd = "gnome-terminal";
-    #[cfg(target_os = "linux")]
-    let terminal_args = &["--", command];
+/// Everything needed to draw one frame of the comparison UI.
+struct ComparisonView<'a> {
+    sides: [ComparisonSide<'a>; 2],
+    /// The position of the first row in the viewport of both tables.
+    scroll_offset: usize,
+    theme: Theme,
+    ascii: bool,
+}
 
-    #[cfg(target_os = "macos")]
-    let terminal_cmd = "open";
-    #[cfg(target_os = "macos")]
-    let terminal_args = &["-a", "Terminal", command];
+/// Returns how many places higher each result ranks in `potential_hits` than in `other`, or `None`
+/// for results missing from `other`.
+fn rank_deltas(
+    potential_hits: &[(u32, String, String)],
+    other: &[(u32, String, String)],
+) -> Vec<Option<isize>> {
+    let other_ranks: HashMap<&str, usize> = other
+        .iter()
+        .enumerate()
+        .map(|(rank, (_, _, full_path))| (full_path.as_str(), rank))
+        .collect();
+    potential_hits
+        .iter()
+        .enumerate()
+        .map(|(rank, (_, _, full_path))| {
+            let other_rank = other_ranks.get(full_path.as_str())?;
+            Some(*other_rank as isize - rank as isize)
+        })
+        .collect()
+}
 
-    let mut cmd = std::process::Command::new(terminal_cmd);
-    cmd.args(terminal_args);
-    cmd.args(args);
-    cmd.spawn()?;
+impl<'a> ComparisonView<'a> {
+    fn new(
+        left: (&'a str, &'a [(u32, String, String)]),
+        right: (&'a str, &'a [(u32, String, String)]),
+        theme: Theme,
+        ascii: bool,
+    ) -> ComparisonView<'a> {
+        let side = |(algorithm, potential_hits): (&'a str, &'a [(u32, String, String)]), other| {
+            ComparisonSide {
+                algorithm,
+                potential_hits,
+                deltas: rank_deltas(potential_hits, other),
+            }
+        };
+        ComparisonView {
+            sides: [side(left, right.1), side(right, left.1)],
+            scroll_offset: 0,
+            theme,
+            ascii,
+        }
+    }
+
+    /// The number of rows of the longer table.
+    fn len(&self) -> usize {
+        self.sides
+            .iter()
+            .map(|side| side.potential_hits.len())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Shows how far a result moved compared with the other algorithm: up in green, down in red, and
+/// `new` for results only this algorithm found.
+fn delta_cell(theme: &Theme, delta: Option<isize>) -> Span<'static> {
+    match delta {
+        None => Span::styled(
+            "new",
+            Style::default()
+                .fg(theme.heat_color(0.5))
+                .add_modifier(Modifier::BOLD),
+        ),
+        Some(0) => Span::raw("="),
+        Some(delta) if delta > 0 => Span::styled(
+            format!("▲{}", delta),
+            Style::default().fg(theme.heat_color(1.0)),
+        ),
+        Some(delta) => Span::styled(
+            format!("▼{}", -delta),
+            Style::default().fg(theme.heat_color(0.0)),
+        ),
+    }
+}
+
+/// Draws the results of both algorithms side by side and the help line of the comparison UI.
+fn draw_comparison<B: Backend>(f: &mut Frame<B>, view: &ComparisonView) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+        .split(f.size());
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(chunks[0]);
+    // Inside the borders and below the header.
+    let height = chunks[0].height.saturating_sub(3) as usize;
+    for (side, area) in view.sides.iter().zip(columns.iter()) {
+        let shown = view.scroll_offset.min(side.potential_hits.len())
+            ..(view.scroll_offset + height).min(side.potential_hits.len());
+        let rows: Vec<Row> = shown
+            .map(|index| {
+                let (score, _, full_path) = &side.potential_hits[index];
+                let style = if *score == 0 {
+                    view.theme.exact
+                } else {
+                    view.theme.fuzzy
+                };
+                Row::new(vec![
+                    Span::raw((index + 1).to_string()),
+                    Span::raw(score.to_string()),
+                    delta_cell(&view.theme, side.deltas[index]),
+                    Span::styled(full_path.clone(), style),
+                ])
+            })
+            .collect();
+        let header_style = view.theme.header;
+        let header = ["No.", "Score", "Moved", "Full Path"]
+            .into_iter()
+            .map(|title| Span::styled(title, header_style));
+        let title = format!("{} ({} results)", side.algorithm, side.potential_hits.len());
+        let table = Table::new(rows)
+            .header(Row::new(header))
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .widths(&[
+                Constraint::Length(5),
+                Constraint::Length(6),
+                Constraint::Length(6),
+                Constraint::Percentage(100),
+            ]);
+        render_glyphs(f, table, *area, view.ascii);
+    }
+    let help = Paragraph::new(Span::raw(if view.ascii {
+        "Up/Down or j/k: Scroll  q/Esc: Quit"
+    } else {
+        "↑/↓ or j/k: Scroll  q/Esc: Quit"
+    }));
+    render_glyphs(f, help, chunks[1], view.ascii);
+}
+
+/// Displays the results of the same search with two algorithms side by side, marking how far each
+/// result moved between them, to tell how a scorer ranks differently from another.
+///
+/// # Arguments
+///
+/// * `results_terminal` - The terminal to draw in.
+/// * `left` - The name of the first algorithm and its results, best first.
+/// * `right` - The name of the second algorithm and its results, best first.
+/// * `options` - The glyphs, theme and keys pressed up front; other options don't apply.
+pub fn display_comparison_ui(
+    mut results_terminal: ResultsTerminal,
+    left: (&str, &[(u32, String, String)]),
+    right: (&str, &[(u32, String, String)]),
+    options: UiOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let terminal = &mut results_terminal.terminal;
+    let mut view = ComparisonView::new(left, right, Theme::resolve(options.theme), options.ascii);
+    let mut input = KeyInput {
+        pending: options.keys.iter().copied().collect(),
+        ..KeyInput::default()
+    };
+    flush_input_events()?;
+    loop {
+        terminal.draw(|f| draw_comparison(f, &view))?;
+        flush_input_events()?;
+        std::thread::sleep(Duration::from_millis(10));
+        match input.read()? {
+            Some(KeyCode::Char('q') | KeyCode::Esc) => break,
+            Some(KeyCode::Down | KeyCode::Char('j')) if view.scroll_offset + 1 < view.len() => {
+                view.scroll_offset += 1;
+            }
+            Some(KeyCode::Up | KeyCode::Char('k')) => {
+                view.scroll_offset = view.scroll_offset.saturating_sub(1);
+            }
+            _ => {}
+        }
+    }
+    results_terminal.restore()?;
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codeowners::CodeOwners;
+    use tui::backend::TestBackend;
+    use tui::style::Color;
+
+    /// Renders a frame of `view` headlessly and serializes it as the text grid followed by the
+    /// runs of styled cells, so snapshots capture both layout and highlighting.
+    fn render(view: &ResultsView, width: u16, height: u16) -> String {
+        render_frame(width, height, |f| draw_results(f, view, f.size()))
+    }
+
+    /// Renders a frame drawn by `draw` like [`render`].
+    fn render_frame(width: u16, height: u16, draw: impl FnOnce(&mut Frame<TestBackend>)) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(draw).unwrap();
+        let buffer = terminal.backend().buffer();
+        let mut lines: Vec<String> = Vec::new();
+        for y in 0..height {
+            let row: String = (0..width)
+                .map(|x| buffer.get(x, y).symbol.as_str())
+                .collect();
+            lines.push(row.trim_end().to_string());
+        }
+        lines.push("--- styles ---".to_string());
+        for y in 0..height {
+            let mut x = 0;
+            while x < width {
+                let style = buffer.get(x, y).style();
+                let start = x;
+                while x < width && buffer.get(x, y).style() == style {
+                    x += 1;
+                }
+                if style != Style::default().fg(Color::Reset).bg(Color::Reset) {
+                    lines.push(format!(
+                        "row {}, cols {}-{}: fg={:?} bg={:?} mod={:?}",
+                        y,
+                        start,
+                        x - 1,
+                        style.fg.unwrap_or(Color::Reset),
+                        style.bg.unwrap_or(Color::Reset),
+                        style.add_modifier
+                    ));
+                }
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn hits() -> Vec<(u32, String, String)> {
+        vec![
+            (0, "main".to_string(), "./src/main.rs".to_string()),
+            (1, "mains".to_string(), "./tests/mains.rs".to_string()),
+            (2, "gain".to_string(), "./src/gain.rs".to_string()),
+        ]
+    }
+
+    #[test]
+    fn snapshot_empty_results() {
+        let view = ResultsView::new(&[], &UiOptions::default(), Theme::dark());
+        insta::assert_snapshot!(render(&view, 60, 6));
+    }
+
+    #[test]
+    fn snapshot_long_paths() {
+        let potential_hits = vec![(
+            1,
+            "component".to_string(),
+            "./packages/frontend/src/features/dashboard/widgets/charts/component.tsx".to_string(),
+        )];
+        let view = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        insta::assert_snapshot!(render(&view, 60, 6));
+    }
+
+    #[test]
+    fn snapshot_selection_highlight() {
+        let potential_hits = hits();
+        let mut view = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        view.selected_index = 1;
+        insta::assert_snapshot!(render(&view, 60, 8));
+    }
+
+    #[test]
+    fn snapshot_score_and_owner_columns() {
+        let potential_hits = hits();
+        let code_owners = CodeOwners::parse("/src/ @core\n", "");
+        let options = UiOptions {
+            code_owners: Some(&code_owners),
+            score_column: Some(ScoreColumn::Bar),
+            ..UiOptions::default()
+        };
+        let view = ResultsView::new(&potential_hits, &options, Theme::light());
+        insta::assert_snapshot!(render(&view, 80, 8));
+    }
+
+    #[test]
+    fn snapshot_similarity_column() {
+        let potential_hits = hits();
+        let similarity = |query: &str, _: SearchMode, hits: &[(u32, String, String)]| {
+            let similarities = hits
+                .iter()
+                .map(|(score, name, _)| search::edit_similarity(query, name, *score))
+                .collect();
+            Some(similarities)
+        };
+        let options = UiOptions {
+            score_column: Some(ScoreColumn::Similarity),
+            similarity: Some(&similarity),
+            ..UiOptions::default()
+        };
+        let mut view = ResultsView::new(&potential_hits, &options, Theme::dark());
+        view.rate_similarity(&options, "main", SearchMode::Fuzzy);
+        insta::assert_snapshot!(render(&view, 60, 8));
+    }
+
+    #[test]
+    fn snapshot_last_commit_column() {
+        let potential_hits = hits();
+        let asked = RefCell::new(Vec::new());
+        let last_commit = |full_path: &str| {
+            asked.borrow_mut().push(full_path.to_string());
+            full_path
+                .starts_with("./src/")
+                .then(|| "Ada Lovelace, 3d ago".to_string())
+        };
+        let options = UiOptions {
+            last_commit: Some(&last_commit),
+            ..UiOptions::default()
+        };
+        let view = ResultsView::new(&potential_hits, &options, Theme::dark());
+        insta::assert_snapshot!(render(&view, 90, 8));
+        render(&view, 90, 8);
+        // Each result is only looked up once.
+        assert_eq!(asked.borrow().len(), 3);
+    }
+
+    #[test]
+    fn snapshot_ascii() {
+        let potential_hits = hits();
+        let options = UiOptions {
+            score_column: Some(ScoreColumn::Bar),
+            ascii: true,
+            ..UiOptions::default()
+        };
+        let view = ResultsView::new(&potential_hits, &options, Theme::dark());
+        insta::assert_snapshot!(render(&view, 60, 8));
+    }
+
+    #[test]
+    fn snapshot_source_column() {
+        let potential_hits = hits();
+        let source_labels = BTreeMap::from([
+            ("./src/main.rs".to_string(), "fs git".to_string()),
+            ("./tests/mains.rs".to_string(), "git-history".to_string()),
+        ]);
+        let options = UiOptions {
+            source_labels: Some(&source_labels),
+            ..UiOptions::default()
+        };
+        let view = ResultsView::new(&potential_hits, &options, Theme::dark());
+        insta::assert_snapshot!(render(&view, 80, 8));
+    }
+
+    #[test]
+    fn test_viewport_follows_selection() {
+        let potential_hits: Vec<(u32, String, String)> = (0..100_000)
+            .map(|index| (1, format!("file{}", index), format!("./file{}.rs", index)))
+            .collect();
+        let mut view = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        // A 12 line terminal leaves 10 lines for the table, 7 of them for rows.
+        view.selected_index = 99_999;
+        let frame = render(&view, 60, 12);
+        assert_eq!(view.scroll_offset.get(), 99_993);
+        assert!(frame.contains("./file99999.rs") && !frame.contains("./file99992.rs"));
+        view.selected_index = 99_994;
+        render(&view, 60, 12);
+        assert_eq!(view.scroll_offset.get(), 99_993);
+        view.selected_index = 10;
+        let frame = render(&view, 60, 12);
+        assert_eq!(view.scroll_offset.get(), 10);
+        assert!(frame.contains("./file10.rs") && frame.contains("./file16.rs"));
+    }
+
+    #[test]
+    fn snapshot_scrolled() {
+        let potential_hits: Vec<(u32, String, String)> = (0..20)
+            .map(|index| {
+                (
+                    index,
+                    format!("file{}", index),
+                    format!("./file{}.rs", index),
+                )
+            })
+            .collect();
+        let mut view = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        // Selecting below the viewport scrolls just far enough to show the selection.
+        view.scroll_offset.set(3);
+        view.selected_index = 12;
+        insta::assert_snapshot!(render(&view, 60, 10));
+    }
+
+    #[test]
+    fn test_toggle_extension() {
+        let mut potential_hits = hits();
+        potential_hits.push((3, "Makefile".to_string(), "./Makefile".to_string()));
+        potential_hits.push((4, "main".to_string(), "./src/main.py".to_string()));
+        let mut view = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        let extensions: Vec<(&str, usize)> = view
+            .extensions
+            .iter()
+            .map(|toggle| (toggle.extension.as_str(), toggle.count))
+            .collect();
+        assert_eq!(extensions, vec![(".rs", 3), ("", 1), (".py", 1)]);
+
+        view.selected_index = 1;
+        view.extension_cursor = Some(1);
+        view.toggle_extension();
+        assert_eq!(view.visible, vec![0, 1, 2, 4]);
+        assert_eq!(view.selected_hit(), Some(1));
+
+        view.extension_cursor = Some(0);
+        view.toggle_extension();
+        assert_eq!(view.visible, vec![4]);
+        assert_eq!(view.selected_hit(), Some(4));
+
+        view.toggle_extension();
+        assert_eq!(view.visible, vec![0, 1, 2, 4]);
+
+        // Compound extensions toggle like `--exclude`: `.js` also hides `app.min.js`, which is
+        // grouped by `.min.js`.
+        potential_hits.push((5, "app.min".to_string(), "./dist/app.min.js".to_string()));
+        let mut minified = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        let labels: Vec<&str> = minified
+            .extensions
+            .iter()
+            .map(ExtensionToggle::label)
+            .collect();
+        assert_eq!(labels, vec![".rs", "(none)", ".py", ".js", ".min.js"]);
+        assert_eq!(minified.group_of(5), 4);
+        for extension in [3, 4] {
+            minified.extension_cursor = Some(extension);
+            minified.toggle_extension();
+            assert_eq!(minified.visible, vec![0, 1, 2, 3, 4]);
+            minified.toggle_extension();
+            assert_eq!(minified.visible, vec![0, 1, 2, 3, 4, 5]);
+        }
+    }
+
+    #[test]
+    fn test_descend_and_ascend() {
+        let mut potential_hits = hits();
+        potential_hits.push((3, "main".to_string(), "./src/bin/main.rs".to_string()));
+        let mut view = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        view.selected_index = 3;
+        view.descend();
+        assert_eq!(view.scope, vec!["src"]);
+        assert_eq!(view.visible, vec![0, 2, 3]);
+        assert_eq!(view.selected_hit(), Some(3));
+        view.descend();
+        assert_eq!(view.scope, vec!["src", "bin"]);
+        assert_eq!(view.visible, vec![3]);
+        // The selected result lies directly in the scope, so there is nowhere further to go.
+        view.descend();
+        assert_eq!(view.scope, vec!["src", "bin"]);
+        view.ascend();
+        view.ascend();
+        view.ascend();
+        assert!(view.scope.is_empty());
+        assert_eq!(view.visible, vec![0, 1, 2, 3]);
+        assert_eq!(view.selected_hit(), Some(3));
+    }
+
+    #[test]
+    fn snapshot_breadcrumbs() {
+        let mut potential_hits = hits();
+        potential_hits.push((3, "main".to_string(), "./src/bin/main.rs".to_string()));
+        let mut view = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        view.selected_index = 3;
+        view.descend();
+        view.descend();
+        insta::assert_snapshot!(render(&view, 80, 8));
+    }
+
+    #[test]
+    fn test_grouping() {
+        let mut potential_hits = hits();
+        potential_hits.insert(1, (1, "main".to_string(), "./src/main.py".to_string()));
+        let mut view = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        view.selected_index = 2;
+        view.toggle_grouping();
+        assert_eq!(
+            view.rows,
+            vec![
+                ResultRow::Group(0),
+                ResultRow::Hit(0),
+                ResultRow::Hit(2),
+                ResultRow::Hit(3),
+                ResultRow::Group(1),
+                ResultRow::Hit(1),
+            ]
+        );
+        assert_eq!(view.selected_hit(), Some(2));
+
+        view.selected_index = 0;
+        view.toggle_group();
+        assert_eq!(
+            view.rows,
+            vec![ResultRow::Group(0), ResultRow::Group(1), ResultRow::Hit(1)]
+        );
+        assert_eq!(view.selected_index, 0);
+
+        view.selected_index = 2;
+        view.toggle_grouping();
+        assert_eq!(view.rows.len(), 4);
+        assert_eq!(view.selected_hit(), Some(1));
+    }
+
+    #[test]
+    fn snapshot_grouped() {
+        let mut potential_hits = hits();
+        potential_hits.insert(1, (1, "main".to_string(), "./src/main.py".to_string()));
+        let mut view = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        view.toggle_grouping();
+        view.selected_index = 4;
+        view.toggle_group();
+        insta::assert_snapshot!(render(&view, 80, 10));
+    }
+
+    #[test]
+    fn snapshot_notice() {
+        let potential_hits = hits();
+        let options = UiOptions {
+            notice: Some("Only the best 3 of 7 results are shown."),
+            ..UiOptions::default()
+        };
+        let view = ResultsView::new(&potential_hits, &options, Theme::dark());
+        insta::assert_snapshot!(render(&view, 60, 9));
+    }
+
+    #[test]
+    fn snapshot_scanning() {
+        let potential_hits = hits();
+        let mut view = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        view.scanning = Some(1234);
+        insta::assert_snapshot!(render(&view, 60, 9));
+        let mut view = ResultsView::new(Vec::new(), &UiOptions::default(), Theme::dark());
+        view.scanning = Some(0);
+        insta::assert_snapshot!(render(&view, 60, 6));
+    }
+
+    #[test]
+    fn test_key_sequence() {
+        assert_eq!(
+            "jk<Enter><lt><space><C-k>".parse(),
+            Ok(KeySequence(vec![
+                KeyCode::Char('j'),
+                KeyCode::Char('k'),
+                KeyCode::Enter,
+                KeyCode::Char('<'),
+                KeyCode::Char(' '),
+                KeyCode::Char(PALETTE_KEY),
+            ]))
+        );
+        assert!("j<enter".parse::<KeySequence>().is_err());
+        assert!("<f1>".parse::<KeySequence>().is_err());
+        assert!("<c-1>".parse::<KeySequence>().is_err());
+    }
+
+    #[test]
+    fn test_key_macros() {
+        let KeySequence(keys) = "mjxmk@@q".parse().unwrap();
+        let mut input = KeyInput {
+            pending: keys.into(),
+            ..KeyInput::default()
+        };
+        let mut handled = Vec::new();
+        while let Some(key) = input.pending.pop_front() {
+            handled.extend(input.filter(key));
+        }
+        let expected: Vec<KeyCode> = "jxkjxjxq".chars().map(KeyCode::Char).collect();
+        assert_eq!(handled, expected);
+        assert_eq!(input.recorded, [KeyCode::Char('j'), KeyCode::Char('x')]);
+    }
+
+    #[test]
+    fn snapshot_recording() {
+        let potential_hits = hits();
+        let mut view = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        view.recording = true;
+        insta::assert_snapshot!(render(&view, 80, 8));
+    }
+
+    #[test]
+    fn test_tabs() {
+        let potential_hits = hits();
+        let search = |query: &str, _: SearchMode| match query {
+            "bad(" => Err("Invalid query".to_string()),
+            _ => Ok(vec![(0, query.to_string(), format!("./src/{}.rs", query))]),
+        };
+        let options = UiOptions {
+            query: "main",
+            search: Some(&search),
+            ..UiOptions::default()
+        };
+        let theme = Theme::dark();
+        let mut tabs = ResultTabs {
+            tabs: vec![ResultTab {
+                query: "main".to_string(),
+                mode: SearchMode::Fuzzy,
+                root: None,
+                view: ResultsView::new(&potential_hits, &options, theme),
+            }],
+            mode: SearchMode::Fuzzy,
+            active: 0,
+            new_tab: Some((String::new(), None)),
+            command: None,
+            palette: None,
+        };
+        for key in "libs".chars().map(KeyCode::Char) {
+            tabs.type_query(key, &options, theme);
+        }
+        tabs.type_query(KeyCode::Backspace, &options, theme);
+        tabs.type_query(KeyCode::Enter, &options, theme);
+        assert!(tabs.new_tab.is_none());
+        assert_eq!(tabs.active, 1);
+        assert_eq!(tabs.tabs[1].query, "lib");
+        assert_eq!(tabs.view().potential_hits[0].2, "./src/lib.rs");
+        // The first tab keeps its own state.
+        assert_eq!(tabs.tabs[0].view.potential_hits.len(), 3);
+
+        tabs.new_tab = Some((String::new(), None));
+        for key in "bad(".chars().map(KeyCode::Char) {
+            tabs.type_query(key, &options, theme);
+        }
+        tabs.type_query(KeyCode::Enter, &options, theme);
+        assert_eq!(
+            tabs.new_tab,
+            Some(("bad(".to_string(), Some("Invalid query".to_string())))
+        );
+        tabs.type_query(KeyCode::Esc, &options, theme);
+        assert!(tabs.new_tab.is_none());
+        assert_eq!(tabs.tabs.len(), 2);
+    }
+
+    #[test]
+    fn test_change_directory() {
+        let base = std::env::temp_dir().join(format!("ffs-cd-{}", std::process::id()));
+        for directory in ["fuzzy-ls", "fuzzer", ".cache", "notes"] {
+            std::fs::create_dir_all(base.join("projects").join(directory)).unwrap();
+        }
+        let projects = base.join("projects");
+        let completions = [
+            complete_directory(&base, "projects/fzls"),
+            complete_directory(&projects, "fuzze"),
+            complete_directory(&projects, "cache"),
+            complete_directory(&projects, ".c"),
+            complete_directory(&projects, "xyz"),
+        ];
+        let potential_hits = hits();
+        let search_directory = |root: &Path, query: &str, _: SearchMode| {
+            let full_path = format!("{}/{}.rs", root.display(), query);
+            Ok(vec![(0, query.to_string(), full_path)])
+        };
+        let options = UiOptions {
+            query: "main",
+            search_directory: Some(&search_directory),
+            ..UiOptions::default()
+        };
+        let theme = Theme::dark();
+        let mut tabs = ResultTabs {
+            tabs: vec![ResultTab {
+                query: "main".to_string(),
+                mode: SearchMode::Fuzzy,
+                root: Some(base.clone()),
+                view: ResultsView::new(&potential_hits, &options, theme),
+            }],
+            mode: SearchMode::Fuzzy,
+            active: 0,
+            new_tab: None,
+            command: Some((String::new(), None)),
+            palette: None,
+        };
+        for key in "cd projects/nts".chars().map(KeyCode::Char) {
+            tabs.type_command(key, &options, theme);
+        }
+        tabs.type_command(KeyCode::Tab, &options, theme);
+        assert_eq!(tabs.command, Some(("cd projects/notes/".to_string(), None)));
+        tabs.type_command(KeyCode::Enter, &options, theme);
+        let notes = base.join("projects/notes/");
+        assert!(tabs.command.is_none());
+        assert_eq!(tabs.tabs[0].root, Some(notes.clone()));
+        assert_eq!(
+            tabs.view().potential_hits[0].2,
+            format!("{}/main.rs", notes.display())
+        );
+
+        tabs.command = Some(("cd missing".to_string(), None));
+        tabs.type_command(KeyCode::Enter, &options, theme);
+        let missing_error = tabs.command.take().and_then(|(_, error)| error);
+        tabs.command = Some(("pwd".to_string(), None));
+        tabs.type_command(KeyCode::Enter, &options, theme);
+        let unknown_error = tabs.command.take().and_then(|(_, error)| error);
+        std::fs::remove_dir_all(&base).unwrap();
+        assert_eq!(
+            completions,
+            [
+                Some("projects/fuzzy-ls/".to_string()),
+                Some("fuzzer/".to_string()),
+                None,
+                Some(".cache/".to_string()),
+                None,
+            ]
+        );
+        assert!(missing_error.is_some_and(|error| error.ends_with("missing is not a directory.")));
+        assert_eq!(
+            unknown_error,
+            Some("Unknown command pwd. Try cd <directory>.".to_string())
+        );
+    }
+
+    #[test]
+    fn snapshot_tabs() {
+        let potential_hits = hits();
+        let options = UiOptions::default();
+        let tab = |query: &str| ResultTab {
+            query: query.to_string(),
+            mode: SearchMode::Fuzzy,
+            root: None,
+            view: ResultsView::new(&potential_hits, &options, Theme::dark()),
+        };
+        let tabs = ResultTabs {
+            tabs: vec![tab("main"), tab("gain")],
+            mode: SearchMode::Fuzzy,
+            active: 1,
+            new_tab: None,
+            command: None,
+            palette: None,
+        };
+        insta::assert_snapshot!(render_frame(60, 9, |f| draw_tabs(f, &tabs)));
+    }
+
+    #[test]
+    fn test_palette() {
+        let mut palette = Palette::new(&UiOptions::default());
+        // Without a search, the UI can't open new tabs.
+        assert!(palette
+            .actions
+            .iter()
+            .all(|(name, _)| !name.starts_with("New tab")));
+        assert_eq!(palette.matches().len(), palette.actions.len());
+        for key in "grp".chars().map(KeyCode::Char) {
+            palette.type_key(key);
+        }
+        assert_eq!(palette.matches()[0].0, "Group by extension");
+        assert_eq!(
+            palette.type_key(KeyCode::Enter),
+            Some(&[KeyCode::Char('g')][..])
+        );
+        palette.type_key(KeyCode::Char('z'));
+        assert!(palette.matches().is_empty());
+        assert_eq!(palette.type_key(KeyCode::Enter), None);
+    }
+
+    #[test]
+    fn snapshot_palette() {
+        let potential_hits = hits();
+        let options = UiOptions::default();
+        let mut palette = Palette::new(&options);
+        palette.type_key(KeyCode::Char('x'));
+        palette.type_key(KeyCode::Down);
+        let tabs = ResultTabs {
+            tabs: vec![ResultTab {
+                query: "main".to_string(),
+                mode: SearchMode::Fuzzy,
+                root: None,
+                view: ResultsView::new(&potential_hits, &options, Theme::dark()),
+            }],
+            mode: SearchMode::Fuzzy,
+            active: 0,
+            new_tab: None,
+            command: None,
+            palette: Some(palette),
+        };
+        insta::assert_snapshot!(render_frame(60, 12, |f| draw_tabs(f, &tabs)));
+    }
+
+    /// Builds the tabs of an exact search for `main` that found nothing.
+    fn exact_tabs<'a>(options: &UiOptions<'a>) -> ResultTabs<'a> {
+        ResultTabs {
+            tabs: vec![ResultTab {
+                query: "main".to_string(),
+                mode: SearchMode::Exact,
+                root: None,
+                view: ResultsView::new(Vec::new(), options, Theme::dark()),
+            }],
+            mode: SearchMode::Exact,
+            active: 0,
+            new_tab: None,
+            command: None,
+            palette: None,
+        }
+    }
+
+    #[test]
+    fn test_switch_mode() {
+        // Only fuzzy searches find anything, and the query is an invalid regex.
+        let search = |query: &str, mode: SearchMode| match mode {
+            SearchMode::Fuzzy => Ok(vec![(1, query.to_string(), format!("./{}s.rs", query))]),
+            SearchMode::Regex => Err("Invalid regex".to_string()),
+            _ => Ok(Vec::new()),
+        };
+        let options = UiOptions {
+            mode: SearchMode::Exact,
+            search: Some(&search),
+            ..UiOptions::default()
+        };
+        let theme = Theme::dark();
+        let mut tabs = exact_tabs(&options);
+        assert_eq!(tabs.view().next_mode, Some(SearchMode::Contains));
+        tabs.switch_mode(&options, theme);
+        assert_eq!(tabs.tabs[0].mode, SearchMode::Contains);
+        assert!(tabs.view().potential_hits.is_empty());
+        tabs.switch_mode(&options, theme);
+        assert_eq!(tabs.tabs[0].mode, SearchMode::Fuzzy);
+        assert_eq!(tabs.view().potential_hits[0].2, "./mains.rs");
+        tabs.switch_mode(&options, theme);
+        assert_eq!(tabs.tabs[0].mode, SearchMode::Phonetic);
+        tabs.switch_mode(&options, theme);
+        assert_eq!(tabs.tabs[0].mode, SearchMode::Exact);
+    }
+
+    #[test]
+    fn test_open_counterpart() {
+        let search = |query: &str, mode: SearchMode| {
+            assert_eq!(mode, SearchMode::Exact);
+            Ok(match query {
+                "main_test" => vec![(0, query.to_string(), "./src/main_test.rs".to_string())],
+                _ => Vec::new(),
+            })
+        };
+        let patterns = CounterpartPatterns::default();
+        let options = UiOptions {
+            mode: SearchMode::Exact,
+            search: Some(&search),
+            counterparts: Some(&patterns),
+            ..UiOptions::default()
+        };
+        let theme = Theme::dark();
+        let mut tabs = exact_tabs(&options);
+        tabs.open_counterpart(&options, theme);
+        assert_eq!(tabs.tabs.len(), 1);
+        tabs.tabs[0].view = ResultsView::new(hits(), &options, theme);
+        tabs.open_counterpart(&options, theme);
+        assert_eq!(tabs.active, 1);
+        assert_eq!(tabs.tabs[1].query, "main_test");
+        assert_eq!(tabs.view().potential_hits[0].2, "./src/main_test.rs");
+    }
+
+    #[test]
+    fn snapshot_switched_mode() {
+        let search = |_: &str, _: SearchMode| Ok(Vec::new());
+        let options = UiOptions {
+            mode: SearchMode::Exact,
+            search: Some(&search),
+            ..UiOptions::default()
+        };
+        let mut tabs = exact_tabs(&options);
+        tabs.switch_mode(&options, Theme::dark());
+        insta::assert_snapshot!(render_frame(60, 5, |f| draw_tabs(f, &tabs)));
+    }
+
+    #[test]
+    fn test_rank_deltas() {
+        let potential_hits = hits();
+        let other = vec![potential_hits[1].clone(), potential_hits[0].clone()];
+        assert_eq!(
+            rank_deltas(&potential_hits, &other),
+            vec![Some(1), Some(-1), None]
+        );
+    }
+
+    #[test]
+    fn snapshot_comparison() {
+        let potential_hits = hits();
+        let other = vec![potential_hits[2].clone(), potential_hits[0].clone()];
+        let view = ComparisonView::new(
+            ("fuzzy", &potential_hits),
+            ("bitap", &other),
+            Theme::dark(),
+            false,
+        );
+        insta::assert_snapshot!(render_frame(100, 8, |f| draw_comparison(f, &view)));
+    }
+
+    #[test]
+    fn snapshot_extension_panel() {
+        let mut potential_hits = hits();
+        potential_hits.push((3, "main".to_string(), "./src/main.py".to_string()));
+        let mut view = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        view.extension_cursor = Some(1);
+        view.toggle_extension();
+        insta::assert_snapshot!(render(&view, 80, 8));
+    }
+
+    #[test]
+    fn snapshot_disk_usage() {
+        let potential_hits = hits();
+        let mut view = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        view.disk_usage = Some(DiskUsage::from_sizes(
+            potential_hits
+                .iter()
+                .zip([2048, 512, 100])
+                .map(|((_, _, full_path), bytes)| (full_path.as_str(), bytes)),
+        ));
+        insta::assert_snapshot!(render(&view, 90, 14));
+    }
+
+    #[test]
+    fn test_show_checksum() {
+        let path = std::env::temp_dir().join(format!("ffs-checksum-{}.txt", std::process::id()));
+        std::fs::write(&path, "abc").unwrap();
+        let full_path = path.to_string_lossy().into_owned();
+        let potential_hits = vec![(0, "checksum".to_string(), full_path)];
+        let mut view = ResultsView::new(&potential_hits, &UiOptions::default(), Theme::dark());
+        view.show_checksum();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            view.checksum.as_deref(),
+            Some("sha256 ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use fuzzy_ls::search::SearchMode;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// The events of a search that run the hooks of the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// Results were picked and opened in the editor.
+    Open,
+    /// The search found results.
+    Results,
+    /// The search found nothing.
+    NoResults,
+}
+
+impl Event {
+    /// The name of the event's option in the config file, e.g. `on_open`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Open => "on_open",
+            Event::Results => "on_results",
+            Event::NoResults => "on_no_results",
+        }
+    }
+}
+
+/// What a hook is told about the search it runs for.
+pub struct Context<'a> {
+    /// The query as given.
+    pub query: &'a str,
+    /// The mode the results were found in.
+    pub mode: SearchMode,
+    /// The first directory searched.
+    pub directory: &'a Path,
+    /// The number of results.
+    pub results: usize,
+    /// The time from the start of fuzzy-ls until the event.
+    pub elapsed: Duration,
+    /// The files opened, or the best result when there is one.
+    pub files: &'a [&'a str],
+}
+
+impl Context<'_> {
+    /// The environment variables a hook runs with, e.g. `FUZZY_LS_QUERY`.
+    fn variables(&self, event: Event) -> Vec<(&'static str, String)> {
+        vec![
+            ("FUZZY_LS_EVENT", event.name().to_string()),
+            ("FUZZY_LS_QUERY", self.query.to_string()),
+            ("FUZZY_LS_MODE", self.mode.name().to_string()),
+            ("FUZZY_LS_DIRECTORY", self.directory.display().to_string()),
+            ("FUZZY_LS_RESULTS", self.results.to_string()),
+            ("FUZZY_LS_ELAPSED_MS", self.elapsed.as_millis().to_string()),
+            (
+                "FUZZY_LS_FILE",
+                self.files.first().copied().unwrap_or_default().to_string(),
+            ),
+            ("FUZZY_LS_FILES", self.files.join("\n")),
+        ]
+    }
+}
+
+/// Builds the command running a hook in the shell, with the context in its environment.
+fn command(hook: &str, event: Event, context: &Context) -> Command {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(hook);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(hook);
+        command
+    };
+    command.envs(context.variables(event));
+    command
+}
+
+/// Runs the hook configured for an event, if there is one, without waiting for it to finish.
+///
+/// Hooks run detached from the terminal, so they can't draw over the results UI: their input and
+/// output are discarded.
+///
+/// # Arguments
+///
+/// * `hook` - The shell command configured for the event.
+/// * `event` - The event that happened.
+/// * `context` - What the hook is told about the search.
+///
+/// # Returns
+///
+/// An error message if the shell could not be started.
+pub fn run(hook: Option<&str>, event: Event, context: &Context) -> Result<(), String> {
+    let Some(hook) = hook else {
+        return Ok(());
+    };
+    command(hook, event, context)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|error| format!("Failed to run the {} hook: {}", event.name(), error))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn test_hook_environment() {
+        let files = ["./src/main.rs", "./src/gui.rs"];
+        let context = Context {
+            query: "main",
+            mode: SearchMode::Fuzzy,
+            directory: Path::new("."),
+            results: 7,
+            elapsed: Duration::from_millis(1500),
+            files: &files,
+        };
+        let command = command("notify-send done", Event::Open, &context);
+        let environment: Vec<(&OsStr, Option<&OsStr>)> = command.get_envs().collect();
+        let variable = |name: &str| {
+            environment
+                .iter()
+                .find(|(key, _)| *key == name)
+                .and_then(|(_, value)| value.and_then(OsStr::to_str))
+        };
+        assert_eq!(variable("FUZZY_LS_EVENT"), Some("on_open"));
+        assert_eq!(variable("FUZZY_LS_MODE"), Some("fuzzy"));
+        assert_eq!(variable("FUZZY_LS_RESULTS"), Some("7"));
+        assert_eq!(variable("FUZZY_LS_ELAPSED_MS"), Some("1500"));
+        assert_eq!(variable("FUZZY_LS_FILE"), Some("./src/main.rs"));
+        assert_eq!(
+            variable("FUZZY_LS_FILES"),
+            Some("./src/main.rs\n./src/gui.rs")
+        );
+        assert_eq!(
+            command.get_args().last(),
+            Some(OsStr::new("notify-send done"))
+        );
+    }
+}
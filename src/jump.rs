@@ -0,0 +1,106 @@
+use clap::ValueEnum;
+use fuzzy_ls::source::CandidateSource;
+use std::path::Path;
+use std::process::Command;
+
+/// Directory jumper databases that can be used as the source of search candidates.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum JumpDatabase {
+    Zoxide,
+    Autojump,
+}
+
+/// A parser turning a jumper's listing into `(score, path)` pairs.
+type ListingParser = fn(&str) -> Vec<(f64, String)>;
+
+/// Parses `zoxide query --list --score` output, where each line is `<score> <path>`.
+fn parse_zoxide(output: &str) -> Vec<(f64, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (score, path) = line.trim().split_once(' ')?;
+            Some((score.parse().ok()?, path.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Parses `autojump --stat` output, where each entry is `<weight>:\t<path>` followed by summary
+/// lines such as `<weight>:\t total weight`, which are skipped as they carry no absolute path.
+fn parse_autojump(output: &str) -> Vec<(f64, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (weight, path) = line.split_once(":\t")?;
+            let path = path.trim();
+            if !Path::new(path).has_root() {
+                return None;
+            }
+            Some((weight.trim().parse().ok()?, path.to_string()))
+        })
+        .collect()
+}
+
+/// Loads the directories recorded by a directory jumper, most frequently used first.
+///
+/// # Arguments
+///
+/// * `database` - The directory jumper to query.
+///
+/// # Returns
+///
+/// A vector of tuples containing the directory name and its full path, or an error message if the
+/// jumper could not be queried.
+pub fn load_directories(database: JumpDatabase) -> Result<Vec<(String, String)>, String> {
+    let (program, args, parse): (&str, &[&str], ListingParser) = match database {
+        JumpDatabase::Zoxide => ("zoxide", &["query", "--list", "--score"], parse_zoxide),
+        JumpDatabase::Autojump => ("autojump", &["--stat"], parse_autojump),
+    };
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|error| format!("Failed to run {}: {}", program, error))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let mut entries = parse(&String::from_utf8_lossy(&output.stdout));
+    entries.sort_by(|a, b| b.0.total_cmp(&a.0));
+    Ok(entries
+        .into_iter()
+        .map(|(_, full_path)| {
+            let dir_name = Path::new(&full_path)
+                .file_name()
+                .map_or(full_path.clone(), |name| name.to_string_lossy().to_string());
+            (dir_name, full_path)
+        })
+        .collect())
+}
+
+impl CandidateSource for JumpDatabase {
+    fn candidates(&mut self) -> Result<Vec<(String, String)>, String> {
+        load_directories(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_jump_databases() {
+        assert_eq!(
+            parse_zoxide("  12.5 /home/user/src/fuzzy-ls\n   4.0 /tmp\n"),
+            vec![
+                (12.5, "/home/user/src/fuzzy-ls".to_string()),
+                (4.0, "/tmp".to_string())
+            ]
+        );
+        assert_eq!(
+            parse_autojump("10.0:\t/home/user/notes\n________\n\n30:\t total weight\n"),
+            vec![(10.0, "/home/user/notes".to_string())]
+        );
+    }
+}
@@ -0,0 +1,19 @@
+//! The search core of fuzzy-ls: candidate sources such as directory walking, compact storage of
+//! candidate paths, candidate filters, path patterns, fuzzy scoring and ranking.
+//!
+//! The core compiles to `wasm32-unknown-unknown`, where the [`wasm`] module exposes a
+//! candidate-list-in/results-out API so web tools and editor extensions can reuse the same ranking.
+//! On native targets the [`ffi`] module exposes the ranking engine through a C ABI, and the optional
+//! `python` feature builds the `python` module into a Python extension.
+pub mod arena;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ffi;
+pub mod filter;
+pub mod glob;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod search;
+pub mod source;
+pub mod throttle;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
@@ -1,24 +1,80 @@
+// The options of a run report outgrew the default limit of the json! macro.
+#![recursion_limit = "256"]
 extern crate clap;
+mod adjust;
+mod build_target;
+mod checksum;
+mod codeowners;
+mod config;
+#[cfg(feature = "tui")]
+mod counterpart;
+mod doctor;
 mod editor;
-mod search;
+mod export;
+mod git;
+mod guard;
+#[cfg(feature = "tui")]
 mod gui;
-use clap::{ArgAction, Parser};
-use regex::Regex;
-use std::collections::BTreeSet;
+mod hook;
+mod jump;
+mod man;
+mod plugin;
+mod priority;
+mod rank;
+mod refs;
+mod report;
+mod scope;
+mod storage;
+#[cfg(feature = "tui")]
+mod theme;
+mod update;
+mod usage;
+use clap::parser::ValueSource;
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use fuzzy_ls::arena::{PathArena, PathId};
+use fuzzy_ls::filter::{
+    parse_size, parse_time, DepthFilter, ExcludeDirFilter, ExcludeGlobFilter, ExtensionFilter,
+    FilterChain, GeneratedFilter, GitignoreFilter, HiddenFilter, ModifiedFilter, PathGlobFilter,
+    SizeFilter,
+};
+use fuzzy_ls::search;
+use fuzzy_ls::source::{
+    self, CandidateSource, ChainedSource, LineSource, ProgressSource, WalkSource,
+};
+use fuzzy_ls::throttle::IoThrottle;
+use regex::RegexBuilder;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 #[derive(Parser)]
 #[clap(
     name = "ffs",
-    version = "0.1.0",
+    version,
     about = "Fuzzy file search command line tool.",
-    author = "Ashwin Pugalia"
+    author = "Ashwin Pugalia",
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
 )]
 struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     /// Query string used for the search.
     #[clap(
+        required = true,
         help = "Query used for the search. Default search mode is fuzzy search within recursive directories."
     )]
-    query: String,
+    query: Option<String>,
+
+    /// The directories to search.
+    #[clap(
+        value_name = "PATH",
+        help = "The directories to search instead of the current one. Directories inside another one given are only searched once.",
+        conflicts_with_all = ["changed_since", "target", "cargo_crate", "owned_by", "show_owners", "dirs_from", "from_report"]
+    )]
+    paths: Vec<PathBuf>,
 
     /// Use query as a regex pattern.
     #[clap(short, long, action = ArgAction::SetTrue, help = "Query is a regex pattern and the search is performed using the regex.")]
@@ -28,11 +84,35 @@ struct Cli {
     #[clap(short = 'p', long, action = ArgAction::SetTrue, help = "Exact pattern matching is done for the query.")]
     exact: bool,
 
+    /// Match names containing the query.
+    #[clap(long, action = ArgAction::SetTrue, help = "Names containing the query match, ranked by where it starts and how much longer they are.")]
+    contains: bool,
+
+    /// Match names by how they sound.
+    #[clap(long, action = ArgAction::SetTrue, help = "Names whose words sound like the words of the query match, e.g. kolor finds color_utils.")]
+    phonetic: bool,
+
+    /// Match the query against the paths of the files.
+    #[clap(long, action = ArgAction::SetTrue, help = "Match the query against the path of each file relative to the searched directory instead of its name, segment by segment, e.g. handlers/user finds api/handlers/user.rs. Only applies to fuzzy searches.")]
+    full_path: bool,
+
+    /// Fall back to a fuzzy search when a stricter one finds nothing.
+    #[clap(long, action = ArgAction::SetTrue, help = "When an exact, contains or regex search finds nothing, rank the same files with a fuzzy search instead and label them as fallback results.")]
+    fallback: bool,
+
+    /// Match upper and lower case letters as different.
+    #[clap(long, action = ArgAction::SetTrue, help = "Upper and lower case letters don't match each other. By default names are compared in lower case, so foo matches Foo.rs exactly.")]
+    case_sensitive: bool,
+
+    /// Match case only when the query has upper case letters.
+    #[clap(short = 'S', long, action = ArgAction::SetTrue, help = "Queries in lower case ignore case, while queries with upper case letters match case-sensitively, like in fzf and ripgrep.")]
+    smart_case: bool,
+
     /// Exclude files of specific extensions.
     #[clap(
         short = 'e',
         long,
-        help = "Exclude files of specific extensions.",
+        help = "Exclude files of specific extensions, e.g. log or tar.gz.",
         value_name = ".ext",
         num_args = 0..,
     )]
@@ -42,12 +122,117 @@ struct Cli {
     #[clap(
         short = 'f',
         long,
-        help = "Focus search on specific set of extensions. In case both exclude and focus are provided, focus takes precedence.",
+        help = "Focus search on specific set of extensions, e.g. rs or tar.gz. In case both exclude and focus are provided, focus takes precedence.",
         value_name = ".ext",
         num_args = 0..,
     )]
     focus: Vec<String>,
 
+    /// Only search files whose path matches a glob.
+    #[clap(
+        long,
+        help = "Only search files whose path relative to the searched directory matches one of the globs, e.g. 'src/**/*.rs'.",
+        value_name = "glob",
+        num_args = 1..,
+    )]
+    glob: Vec<String>,
+
+    /// Leave out the files and directories whose path matches a glob.
+    #[clap(
+        long,
+        help = "Leave out the files and directories whose path relative to the searched directory matches one of the globs. Directories matched by a glob ending in /**, e.g. '**/generated/**', aren't walked.",
+        value_name = "glob",
+        num_args = 1..,
+    )]
+    exclude_glob: Vec<String>,
+
+    /// Directories that are never walked.
+    #[clap(
+        long,
+        help = "Don't walk the directories with these names, e.g. node_modules target .venv, wherever they are.",
+        value_name = "name",
+        num_args = 1..,
+    )]
+    exclude_dir: Vec<String>,
+
+    /// Search files ignored by git too.
+    #[clap(long, action = ArgAction::SetTrue, help = "Also search the files ignored by .gitignore files and .git/info/exclude, like target/ and node_modules/.")]
+    no_ignore: bool,
+
+    /// Also search hidden files and directories.
+    #[clap(long, action = ArgAction::SetTrue, help = "Also search hidden files and directories, whose names start with a dot. .git directories are never searched.")]
+    hidden: bool,
+
+    /// Walk the directories symbolic links point to.
+    #[clap(long, action = ArgAction::SetTrue, help = "Also search the directories symbolic links point to. Links leading back to a directory being searched are skipped.")]
+    follow: bool,
+
+    /// The kinds of entries searched.
+    #[clap(
+        long = "type",
+        help = "Search files (f), directories (d) or both (all) when walking directories. Directories are listed with a trailing separator.",
+        value_name = "type",
+        default_value = "f"
+    )]
+    entry_type: EntryType,
+
+    /// Leave out generated files.
+    #[clap(
+        long,
+        action = ArgAction::SetTrue,
+        help = "Leave out generated files: minified bundles, source maps, generated protobuf code, bytecode caches, dist directories and lockfiles, plus the patterns of the generated file in the config directory."
+    )]
+    no_generated: bool,
+
+    /// Search the root of the file system or a large home directory without asking.
+    #[clap(long, action = ArgAction::SetTrue, help = "Search the root of the file system or a large home directory without asking first, as searching them may take very long.")]
+    force: bool,
+
+    /// Only search this many directory levels down.
+    #[clap(
+        long,
+        help = "Only search this many levels below the searched directory; 1 searches only the files directly in it.",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    max_depth: Option<u32>,
+
+    /// Only search files at least this large.
+    #[clap(
+        long,
+        help = "Only search files at least this large, in bytes or with a unit, e.g. 10k or 5M.",
+        value_name = "size",
+        value_parser = parse_size
+    )]
+    min_size: Option<u64>,
+
+    /// Only search files at most this large.
+    #[clap(
+        long,
+        help = "Only search files at most this large, in bytes or with a unit, e.g. 10k or 5M, to leave out huge binaries.",
+        value_name = "size",
+        value_parser = parse_size
+    )]
+    max_size: Option<u64>,
+
+    /// Only search files modified since a point in time.
+    #[clap(
+        long,
+        help = "Only search files modified after a point in time: a duration before now, e.g. 3h, 2d or 1w, or a UTC date, e.g. 2024-05-01 or 2024-05-01T14:30.",
+        value_name = "time",
+        value_parser = parse_time
+    )]
+    newer_than: Option<SystemTime>,
+
+    /// Only search files not modified since a point in time.
+    #[clap(
+        long,
+        help = "Only search files last modified before a point in time: a duration before now, e.g. 3h, 2d or 1w, or a UTC date, e.g. 2024-05-01 or 2024-05-01T14:30.",
+        value_name = "time",
+        value_parser = parse_time
+    )]
+    older_than: Option<SystemTime>,
+
     /// Default code editor to open the files.
     #[clap(
         short = 'd',
@@ -57,99 +242,1586 @@ struct Cli {
         default_value = "nvim"
     )]
     default_editor_command: String,
+
+    /// Terminal to open the editor in.
+    #[clap(
+        long,
+        help = "Open the editor in this terminal instead of the platform's, given with the arguments preceding the editor command, e.g. 'alacritty -e' or 'wezterm start --'.",
+        value_name = "terminal"
+    )]
+    terminal: Option<String>,
+
+    /// Wait for the editor to exit instead of opening it in a new terminal window.
+    #[clap(
+        long,
+        action = ArgAction::SetTrue,
+        help = "Run the editor in this terminal and wait for it to exit, then return to the results. With --prompt, exit with the editor's exit status."
+    )]
+    wait: bool,
+
+    /// Print the results in another format instead of showing them.
+    #[clap(
+        long,
+        visible_aliases = ["emit", "output"],
+        help = "Print the results instead of showing them: in vim's quickfix format (path:line:col: text), as a VS Code search editor, as a Markdown table of names, paths, scores and sizes, or as a standalone HTML page with a filter box and sortable columns.",
+        value_name = "format"
+    )]
+    format: Option<export::ExportFormat>,
+
+    /// Don't rank results by the picks learned in the project, nor learn from this run.
+    #[clap(
+        long,
+        action = ArgAction::SetTrue,
+        help = "Don't rank the results by the picks learned in the project, nor learn from the results picked in this run."
+    )]
+    no_learn: bool,
+
+    /// Show at most this many results.
+    #[clap(
+        long,
+        help = "Show at most this many of the best results, noting how many were left out; 0 shows them all. --hash and --du cover every result unless a limit is given.",
+        value_name = "N",
+        default_value = "100"
+    )]
+    limit: usize,
+
+    /// Print how the score of each result came about instead of showing them.
+    #[clap(
+        long,
+        action = ArgAction::SetTrue,
+        conflicts_with_all = ["format", "hash"],
+        help = "Print how the score of each result came about instead of showing them: the score it matched with, the adjustments from the config directory and the weight learned from picks."
+    )]
+    explain: bool,
+
+    /// Create a file named after the query when nothing matches it.
+    #[clap(
+        long,
+        action = ArgAction::SetTrue,
+        conflicts_with_all = ["regex", "format", "hash", "dirs_from"],
+        help = "When nothing matches, create a file named after the query in the searched directory, or at the path of a query like src/ui/widget.rs, from the template of its extension in the config directory, and open it in the editor."
+    )]
+    create: bool,
+
+    /// Print the checksums of the results instead of showing them.
+    #[clap(
+        long,
+        conflicts_with = "format",
+        help = "Print the checksums of the results in the format of sha256sum instead of showing them, as a manifest `verify` can check.",
+        value_name = "algorithm"
+    )]
+    hash: Option<checksum::HashAlgorithm>,
+
+    /// Limit the search to files changed since a git ref or duration.
+    #[clap(
+        long,
+        help = "Only search files changed since a git ref (e.g. origin/main) or a duration (e.g. 2d, 3h).",
+        value_name = "ref|duration"
+    )]
+    changed_since: Option<String>,
+
+    /// Limit the search to the source files of a build target.
+    #[clap(
+        long,
+        help = "Only search the source files of a Bazel target (e.g. //src/app:server) or a Cargo package, optionally with one of its targets (e.g. fuzzy-ls or fuzzy-ls:scoring).",
+        value_name = "target"
+    )]
+    target: Option<String>,
+
+    /// Limit the search to the sources of a member of the Cargo workspace.
+    #[clap(
+        long,
+        help = "Only search the source directory of a member of the Cargo workspace, as found by cargo metadata.",
+        value_name = "name"
+    )]
+    cargo_crate: Option<String>,
+
+    /// Restrict results to files owned by specific CODEOWNERS owners.
+    #[clap(
+        long,
+        help = "Only search files owned by the given CODEOWNERS owners (e.g. @org/team).",
+        value_name = "@owner",
+        num_args = 1..,
+    )]
+    owned_by: Vec<String>,
+
+    /// Show the CODEOWNERS owners of each result.
+    #[clap(long, action = ArgAction::SetTrue, help = "Show the CODEOWNERS owners of each result in the results table.")]
+    show_owners: bool,
+
+    /// Sum the sizes of the results.
+    #[clap(long, action = ArgAction::SetTrue, help = "Sum the sizes of the results and show the totals by extension and directory in a panel, or print them with --format and --prompt.")]
+    du: bool,
+
+    /// Where the search candidates come from.
+    #[clap(
+        long,
+        help = "Where the candidates come from: the file system, paths piped through stdin, the files tracked by git, or the files changed in the git history. Repeat to search several sources at once.",
+        value_name = "source",
+        default_value = "fs"
+    )]
+    source: Vec<SourceKind>,
+
+    /// Pace file system operations for network file systems.
+    #[clap(
+        long,
+        help = "Limit file system operations to this many per second, backing off while they fail. Useful on NFS and SMB mounts.",
+        value_name = "ops",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    io_throttle: Option<u32>,
+
+    /// Bound the memory taken by the collected candidates.
+    #[clap(
+        long,
+        help = "Bound the memory taken by the collected candidates. Beyond it, candidates are matched while they are collected and only the best results are kept.",
+        value_name = "MB",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    max_memory: Option<u64>,
+
+    /// Run with a lower CPU and I/O priority.
+    #[clap(
+        long,
+        action = ArgAction::SetTrue,
+        help = "Lower the CPU and I/O priority of the search, so large scans don't slow down builds and other work."
+    )]
+    nice: bool,
+
+    /// Score candidates on this many threads.
+    #[clap(
+        long,
+        help = "Score candidates on this many threads. Defaults to the number of CPUs; 1 scores them on the main thread.",
+        value_name = "n",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    threads: Option<u32>,
+
+    /// Write a machine-readable report of the run.
+    #[clap(
+        long,
+        help = "Write a JSON report of the run (options, timings, counts, warnings and results) to a file.",
+        value_name = "file"
+    )]
+    report: Option<String>,
+
+    /// Print how long each phase of the run took.
+    #[clap(long, action = ArgAction::SetTrue, help = "Print how long each phase of the run took (parsing, collecting, filtering, matching, ranking and setting up the UI) to stderr after the run.")]
+    timings: bool,
+
+    /// Search directories recorded by a directory jumper instead of walking the file system.
+    #[clap(
+        long,
+        help = "Search directories from a directory jumper database instead of files, ranked by fuzzy match and then by frecency.",
+        value_name = "database"
+    )]
+    dirs_from: Option<jump::JumpDatabase>,
+
+    /// Search the results of an earlier report instead of walking the file system.
+    #[clap(
+        long,
+        conflicts_with_all = ["dirs_from", "source"],
+        help = "Search the results of a report written by --report instead of walking the file system, to narrow down the results of a large scan with other queries.",
+        value_name = "file"
+    )]
+    from_report: Option<String>,
+
+    /// List the results and prompt for numbers to open instead of showing the interactive UI.
+    #[clap(
+        long,
+        visible_alias = "no-tui",
+        action = ArgAction::SetTrue,
+        help = "List the results and prompt for the numbers of the files to open instead of showing the interactive UI."
+    )]
+    prompt: bool,
+
+    /// Show a column visualizing the relative match quality of each result.
+    #[cfg(feature = "tui")]
+    #[clap(
+        long,
+        help = "Show a score column colored from green (best) to red (worst), either as the raw score, as a mini bar or as the similarity to the query from 0 to 100.",
+        value_name = "style"
+    )]
+    score_column: Option<gui::ScoreColumn>,
+
+    /// Show the author and age of the last commit of each result.
+    #[cfg(feature = "tui")]
+    #[clap(long, action = ArgAction::SetTrue, help = "Show the author and age of the last commit of each result in the results table, read from git for the rows on screen.")]
+    show_last_commit: bool,
+
+    /// Keys to press in the UI before reading the keyboard.
+    #[cfg(feature = "tui")]
+    #[clap(
+        long,
+        help = "Press keys in the UI before reading the keyboard, e.g. \"jjj<enter>\" to open the fourth result. Named keys are <enter>, <esc>, <up>, <down>, <left>, <right>, <backspace>, <space>, <tab>, <lt> for < and <c-k> for Ctrl-K.",
+        value_name = "keys"
+    )]
+    keys: Option<gui::KeySequence>,
+
+    /// A second algorithm whose results are shown next to those of `--algorithm`.
+    #[cfg(feature = "tui")]
+    #[clap(
+        long,
+        help = "Show the results of -a side by side with those of another algorithm, marking how far each result moved, e.g. to tune a new scorer.",
+        value_name = "algorithm"
+    )]
+    compare: Option<search::AlgorithmChoice>,
+
+    /// Draw the UI with ASCII characters only.
+    #[cfg(feature = "tui")]
+    #[clap(long, action = ArgAction::SetTrue, help = "Replace unicode borders, arrows and glyphs in the UI with ASCII equivalents.")]
+    ascii: bool,
+
+    /// Color theme of the UI.
+    #[cfg(feature = "tui")]
+    #[clap(
+        long,
+        help = "Color theme of the UI. By default it is picked based on the detected terminal background.",
+        value_name = "theme",
+        default_value = "auto"
+    )]
+    theme: theme::ThemeChoice,
+
+    /// Algorithm used to score fuzzy matches.
+    #[clap(
+        short = 'a',
+        long,
+        help = "Algorithm used to score fuzzy matches: damerau-levenshtein, levenshtein, bitap, jaro-winkler, subsequence, tokens, smith-waterman, trigram, or plugin:<name> for a scorer plugin from the plugins directory.",
+        value_name = "algorithm",
+        default_value = "damerau-levenshtein"
+    )]
+    algorithm: search::AlgorithmChoice,
+
+    /// The most errors a Bitap match may have.
+    #[clap(
+        long,
+        help = "The most insertions, deletions and substitutions a match may have with -a bitap. Defaults to a share of the query length.",
+        value_name = "errors"
+    )]
+    max_errors: Option<u32>,
+
+    /// The cost of a mismatch in a Smith-Waterman alignment.
+    #[clap(
+        long,
+        help = "The cost of aligning two different characters with -a smith-waterman, against 2 earned per matching character. [default: 1]",
+        value_name = "penalty"
+    )]
+    mismatch_penalty: Option<u32>,
+
+    /// The cost of a gap in a Smith-Waterman alignment.
+    #[clap(
+        long,
+        help = "The cost of skipping a character of the query or the name with -a smith-waterman, against 2 earned per matching character. [default: 1]",
+        value_name = "penalty"
+    )]
+    gap_penalty: Option<u32>,
+
+    /// The highest score a fuzzy match may have.
+    #[clap(
+        long,
+        help = "The highest score a fuzzy or phonetic match may have, e.g. the most edits with the default algorithm. Defaults to a share of the query length that grows with it.",
+        value_name = "score",
+        conflicts_with = "max_distance_ratio"
+    )]
+    threshold: Option<u32>,
+
+    /// The highest score a fuzzy match may have, as a share of the query length.
+    #[clap(
+        long,
+        help = "The highest score a fuzzy or phonetic match may have as a share of the query length, rounded up, e.g. 0.5 for 3 edits in a query of 6 characters.",
+        value_name = "ratio"
+    )]
+    max_distance_ratio: Option<f64>,
+
+    /// The lowest similarity to the query a result may have.
+    #[clap(
+        long,
+        help = "Leave out results less similar to the query than this, on a scale from 0 to 100 for an exact match that compares across query lengths and algorithms.",
+        value_name = "score",
+        default_value = "0",
+        value_parser = clap::value_parser!(u32).range(0..=100)
+    )]
+    min_score: u32,
+}
+
+/// The number of results kept once the candidates exceed `--max-memory`.
+const MAX_MEMORY_TOP_HITS: usize = 1000;
+
+/// How often the results found so far are shown while the directory is walked.
+const SCAN_REFRESH: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// The number of results found so far that are shown while the directory is walked.
+#[cfg(feature = "tui")]
+const SCAN_TOP_HITS: usize = 1000;
+
+/// How candidate names are matched against the query.
+enum Matcher {
+    /// The name has to equal the query, lower cased unless matching case-sensitively.
+    Exact(String, bool),
+    /// The name has to contain the query, lower cased unless matching case-sensitively.
+    Contains(String, bool),
+    /// The regex has to match the whole name.
+    Regex(regex::Regex),
+    /// Names within the fuzzy threshold of the query, scored by the selected algorithm.
+    Fuzzy(String, Box<dyn search::ScoreFunction>),
+    /// Paths relative to the searched directory within the threshold of the query, scored segment
+    /// by segment by a [`search::FullPath`] scorer.
+    FullPath(String, Box<dyn search::ScoreFunction>),
+    /// Rust files of the modules a Rust path like `search::walk` goes through, the deepest first.
+    Module(Vec<String>),
+}
+
+impl Matcher {
+    /// Matches candidates against the query.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - The candidates.
+    /// * `ids` - The candidates to match.
+    /// * `min_score` - The lowest [`Matcher::similarity`] a match may have.
+    ///
+    /// # Returns
+    ///
+    /// The matching candidates as `(score, id)` tuples, best first, or the scorer's error. Equal
+    /// scores are ordered by similarity, the most similar first.
+    fn match_candidates(
+        &self,
+        paths: &PathArena,
+        ids: Vec<PathId>,
+        min_score: u32,
+    ) -> Result<Vec<(u32, PathId)>, String> {
+        let mut potential_hits: Vec<(u32, PathId)> = Vec::new();
+        match self {
+            Matcher::Exact(query, case_sensitive) => {
+                let mut buffer = String::new();
+                for id in ids {
+                    let name = search::normalize_name(paths.name(id), *case_sensitive, &mut buffer);
+                    if name == query {
+                        potential_hits.push((0, id));
+                    }
+                }
+            }
+            Matcher::Contains(query, case_sensitive) => {
+                let mut buffer = String::new();
+                for id in ids {
+                    let name = search::normalize_name(paths.name(id), *case_sensitive, &mut buffer);
+                    if let Some(score) = search::contains_score(query, name) {
+                        potential_hits.push((score, id));
+                    }
+                }
+                potential_hits.sort_by_key(|hit| hit.0);
+            }
+            Matcher::Regex(pattern) => {
+                for id in ids {
+                    let file_name = &*search::compose(paths.name(id));
+                    match pattern.captures(file_name) {
+                        Some(caps) => {
+                            if caps
+                                .get(0)
+                                .is_some_and(|matched| matched.as_str() == file_name)
+                            {
+                                potential_hits.push((0, id));
+                            }
+                        }
+                        None => continue,
+                    }
+                }
+            }
+            Matcher::Fuzzy(query, scorer) => {
+                potential_hits = search::rank_files(query, paths, ids, scorer.as_ref())?;
+            }
+            Matcher::FullPath(query, scorer) => {
+                potential_hits = search::rank_full_paths(query, paths, ids, scorer.as_ref())?;
+            }
+            Matcher::Module(segments) => {
+                for id in ids {
+                    let Some(modules) = search::module_path_of_file(paths.path(id)) else {
+                        continue;
+                    };
+                    // Crate roots are left out, since every path goes through them.
+                    let goes_through = !modules.is_empty()
+                        && modules.len() <= segments.len()
+                        && modules
+                            .iter()
+                            .zip(segments)
+                            .all(|(module, segment)| module == segment);
+                    if goes_through {
+                        potential_hits.push(((segments.len() - modules.len()) as u32, id));
+                    }
+                }
+                potential_hits.sort_by_key(|hit| hit.0);
+            }
+        }
+        // Exact and regex matches are all alike.
+        if matches!(self, Matcher::Exact(..) | Matcher::Regex(_)) {
+            return Ok(potential_hits);
+        }
+        let mut similar: Vec<(u32, u32, PathId)> = potential_hits
+            .into_iter()
+            .map(|(score, id)| {
+                let similarity = self.similarity(paths.name(id), paths.path(id), score);
+                (score, similarity, id)
+            })
+            .filter(|(_, similarity, _)| *similarity >= min_score)
+            .collect();
+        similar.sort_by_key(|(score, similarity, _)| (*score, std::cmp::Reverse(*similarity)));
+        Ok(similar
+            .into_iter()
+            .map(|(score, _, id)| (score, id))
+            .collect())
+    }
+
+    /// Returns how similar a matching file is to the query given its score, from 0 to 100 for an
+    /// exact match.
+    fn similarity(&self, name: &str, full_path: &str, score: u32) -> u32 {
+        match self {
+            Matcher::Exact(..) | Matcher::Regex(_) => 100,
+            // The score counts the characters of the name around the query.
+            Matcher::Contains(query, _) => search::edit_similarity(query, name, score),
+            Matcher::Fuzzy(query, scorer) => scorer.similarity(query, name, score),
+            // The score was computed against the path rather than the name.
+            Matcher::FullPath(query, scorer) => {
+                scorer.similarity(query, search::relative_path_of(full_path), score)
+            }
+            // The score counts the segments of the query past the module of the file.
+            Matcher::Module(segments) => {
+                let segments = segments.len() as u32;
+                100 * segments.saturating_sub(score) / segments.max(1)
+            }
+        }
+    }
+}
+
+/// The candidate sources that can be selected with `--source`.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum SourceKind {
+    /// Walk the current directory, or the PATH given.
+    Fs,
+    /// Read file paths from stdin, one per line.
+    Stdin,
+    /// The files git tracks, plus untracked files that are not ignored.
+    Git,
+    /// The files changed in the git history, most recently changed first.
+    GitHistory,
+}
+
+impl SourceKind {
+    /// The name of the source shown next to results, as accepted by `--source`.
+    fn label(&self) -> String {
+        self.to_possible_value()
+            .map_or(String::new(), |value| value.get_name().to_string())
+    }
+}
+
+/// The kinds of entries that can be searched with `--type`.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum EntryType {
+    /// Files only.
+    #[value(name = "f")]
+    File,
+    /// Directories only.
+    #[value(name = "d")]
+    Directory,
+    /// Both files and directories.
+    All,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage the ranking weights learned from the results picked in the current project.
+    Rank {
+        #[clap(subcommand)]
+        action: RankAction,
+    },
+    /// Check the editor, terminal and other subsystems fuzzy-ls depends on.
+    Doctor {
+        /// The editor to check, as passed to -d when searching.
+        #[clap(short = 'd', long, value_name = "nvim", default_value = "nvim")]
+        default_editor_command: String,
+    },
+    /// List the files referencing a file, grouped by directory, to preview the files a rename
+    /// touches. Nothing is renamed.
+    Refs {
+        /// The name or path of the file to be renamed, e.g. search.rs or src/search.rs.
+        #[clap(value_name = "name")]
+        name: String,
+        /// Also look through the files ignored by .gitignore files.
+        #[clap(long)]
+        no_ignore: bool,
+        /// Also look through hidden files and directories.
+        #[clap(long)]
+        hidden: bool,
+    },
+    /// Verify the checksums of the files listed in a manifest like the ones sha256sum writes,
+    /// locating the files that moved by their names.
+    Verify {
+        /// The manifest, with a checksum and a path on each line.
+        #[clap(value_name = "sumfile")]
+        manifest: String,
+    },
+    /// List the scopes of the config directory as @name, one per line, e.g. for shell completion.
+    Scopes,
+    /// Ask for the preferred editor, terminal, theme, excluded extensions and whether to learn
+    /// from picks, and write them to the config file as defaults for every search.
+    Setup,
+    /// Print the man page in roff format, e.g. `fuzzy-ls man > fuzzy-ls.1`.
+    Man,
+    /// Update fuzzy-ls to the latest release, verifying the download's SHA-256 checksum.
+    SelfUpdate {
+        /// Only check whether a newer release is available.
+        #[clap(long)]
+        check: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum RankAction {
+    /// Clear the learned ranking weights of the current project.
+    Reset,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Cli::parse();
+    let started = Instant::now();
+    let matches = Cli::command().get_matches();
+    let mut args = Cli::from_arg_matches(&matches)?;
+    let parse_time = started.elapsed();
+    if let Some(command) = args.command {
+        return match command {
+            Command::Rank {
+                action: RankAction::Reset,
+            } => {
+                rank::RankWeights::reset()?;
+                println!("Ranking weights of the current project were reset.");
+                Ok(())
+            }
+            Command::Doctor {
+                default_editor_command,
+            } => {
+                doctor::run(&default_editor_command)?;
+                Ok(())
+            }
+            Command::Refs {
+                name,
+                no_ignore,
+                hidden,
+            } => {
+                refs::run(&name, no_ignore, hidden)?;
+                Ok(())
+            }
+            Command::Verify { manifest } => {
+                checksum::run_verify(&manifest)?;
+                Ok(())
+            }
+            Command::Scopes => {
+                for name in scope::Scopes::load()?.names() {
+                    println!("@{}", name);
+                }
+                Ok(())
+            }
+            Command::Setup => {
+                let config = config::Config::load()?
+                    .setup(&mut std::io::stdin().lock(), &mut std::io::stdout())?;
+                println!("Wrote {}.", config.save()?.display());
+                Ok(())
+            }
+            Command::Man => {
+                man::render(Cli::command(), &mut std::io::stdout())?;
+                Ok(())
+            }
+            Command::SelfUpdate { check } => {
+                println!("{}", update::self_update(check)?);
+                Ok(())
+            }
+        };
+    }
+    // The config file fills in the options not given on the command line.
+    let config = config::Config::load()?;
+    let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    if let Some(editor) = config.editor.filter(|_| !given("default_editor_command")) {
+        args.default_editor_command = editor;
+    }
+    args.terminal = args.terminal.or(config.terminal);
+    #[cfg(feature = "tui")]
+    if let Some(theme) = config.theme.filter(|_| !given("theme")) {
+        args.theme = theme::ThemeChoice::from_str(&theme, true)?;
+    }
+    if !given("exclude") {
+        args.exclude = config.exclude;
+    }
+    args.no_learn |= config.learn == Some(false);
+    let Some(mut query) = args.query.take() else {
+        return Err("A query is required.".into());
+    };
+    // A query like `@notes meeting` searches the directory of the scope for the query after it.
+    // Other queries starting with `@`, like `@types`, are searched for as they are, and `@@` escapes
+    // a name that is also a scope. Regexes never name scopes.
+    if let Some(escaped) = query.strip_prefix("@@").filter(|_| !args.regex) {
+        query = format!("@{}", escaped);
+    } else if let Some(name) = query.strip_prefix('@').filter(|_| !args.regex) {
+        let directory = if args.paths.is_empty() {
+            None
+        } else {
+            // A missing or broken scopes file leaves the query as typed, like an unknown scope.
+            scope::Scopes::load()
+                .ok()
+                .and_then(|scopes| scopes.directory(name).ok())
+        };
+        if let Some(directory) = directory {
+            query = args.paths.remove(0).to_string_lossy().into_owned();
+            args.paths.insert(0, directory);
+        }
+    }
     if args.regex && args.exact {
         return Err("Both regex and exact flags cannot be set together.".into());
     }
+    if args.contains && (args.regex || args.exact) {
+        return Err(
+            "The contains flag cannot be set together with the regex or exact flags.".into(),
+        );
+    }
+    if args.phonetic && (args.regex || args.exact || args.contains) {
+        return Err(
+            "The phonetic flag cannot be set together with the regex, exact or contains flags."
+                .into(),
+        );
+    }
+    if args.smart_case && args.case_sensitive {
+        return Err("Both smart-case and case-sensitive flags cannot be set together.".into());
+    }
+    let threshold = match (args.threshold, args.max_distance_ratio) {
+        (Some(threshold), _) => Some(search::Threshold::Absolute(threshold)),
+        (None, Some(ratio)) if ratio.is_finite() && ratio >= 0.0 => {
+            Some(search::Threshold::Ratio(ratio))
+        }
+        (None, Some(ratio)) => {
+            return Err(format!("--max-distance-ratio must be 0 or more, not {}.", ratio).into())
+        }
+        (None, None) => None,
+    };
+    if threshold.is_some() && (args.exact || args.contains || args.regex) && !args.fallback {
+        return Err(
+            "--threshold and --max-distance-ratio only apply to fuzzy and phonetic searches, and to --fallback."
+                .into(),
+        );
+    }
+    if !(args.exact || args.contains || args.regex) {
+        let penalties = args.mismatch_penalty.is_some() || args.gap_penalty.is_some();
+        if args.phonetic && (args.max_errors.is_some() || penalties) {
+            return Err("--phonetic doesn't take scorer options.".into());
+        }
+        #[cfg(feature = "tui")]
+        let compared = args.compare.as_ref();
+        #[cfg(not(feature = "tui"))]
+        let compared = None;
+        // Scorer options may also tune the algorithm that -a is compared with.
+        let algorithms: Vec<search::FuzzySearchAlgorithm> = std::iter::once(&args.algorithm)
+            .chain(compared)
+            .filter_map(|choice| match choice {
+                search::AlgorithmChoice::Builtin(algorithm) => Some(*algorithm),
+                search::AlgorithmChoice::Plugin(_) => None,
+            })
+            .collect();
+        if args.max_errors.is_some() && !algorithms.contains(&search::FuzzySearchAlgorithm::Bitap) {
+            return Err("--max-errors only applies to -a bitap.".into());
+        }
+        if penalties && !algorithms.contains(&search::FuzzySearchAlgorithm::SmithWaterman) {
+            return Err(
+                "--mismatch-penalty and --gap-penalty only apply to -a smith-waterman.".into(),
+            );
+        }
+    }
+    let mode = if args.exact {
+        search::SearchMode::Exact
+    } else if args.contains {
+        search::SearchMode::Contains
+    } else if args.regex {
+        search::SearchMode::Regex
+    } else if args.phonetic {
+        search::SearchMode::Phonetic
+    } else {
+        search::SearchMode::Fuzzy
+    };
+    if args.full_path && mode != search::SearchMode::Fuzzy {
+        return Err("--full-path only applies to fuzzy searches.".into());
+    }
+    if args.fallback {
+        if matches!(
+            mode,
+            search::SearchMode::Fuzzy | search::SearchMode::Phonetic
+        ) {
+            return Err("--fallback only applies to exact, contains and regex searches.".into());
+        }
+        // Spilled candidates are dropped once they are matched, so none are left to fall back to.
+        if args.max_memory.is_some() {
+            return Err("--fallback cannot be set together with --max-memory.".into());
+        }
+    }
+    #[cfg(feature = "tui")]
+    if args.compare.is_some() {
+        if mode != search::SearchMode::Fuzzy {
+            return Err("--compare only applies to fuzzy searches.".into());
+        }
+        if args.prompt || args.format.is_some() || args.max_memory.is_some() {
+            return Err(
+                "--compare cannot be set together with the prompt, format or max-memory flags."
+                    .into(),
+            );
+        }
+    }
+    let roots = if args.paths.is_empty() {
+        vec![PathBuf::from(".")]
+    } else {
+        search::distinct_roots(&args.paths)
+    };
+    if !args.paths.is_empty() {
+        if let Some(root) = roots.iter().find(|root| !root.is_dir()) {
+            return Err(format!("{} is not a directory.", root.display()).into());
+        }
+        // Other sources list the files of the current directory or of stdin.
+        if args.source.iter().any(|kind| *kind != SourceKind::Fs) {
+            return Err("A PATH to search only applies to --source fs.".into());
+        }
+    }
+    // An accidental search of the whole file system would seem to hang, so it has to be confirmed.
+    let walks = args.dirs_from.is_none()
+        && args.from_report.is_none()
+        && args.source.contains(&SourceKind::Fs);
+    if walks && !args.force && search::existing_path(&query).is_none() {
+        let home = storage::home_dir();
+        for root in &roots {
+            let risky = guard::risky_root(root, home.as_deref(), guard::HOME_ENTRY_LIMIT);
+            if let Some(reason) = risky {
+                if !guard::confirm_risky_root(&reason)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    if args.nice {
+        priority::lower_priority()?;
+    }
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads as usize)
+            .build_global()
+            .map_err(|error| format!("Failed to start {} scoring threads: {}", threads, error))?;
+    }
+    let unix_seconds = |time: SystemTime| {
+        time.duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs())
+    };
+    // `--timings` is printed from the report, so one is kept even when it isn't written.
+    let mut report = (args.report.is_some() || args.timings).then(|| {
+        report::RunReport::new(
+            json!({
+                "query": query,
+                "paths": args.paths,
+                "mode": mode.name(),
+                "case_sensitive": args.case_sensitive,
+                "smart_case": args.smart_case,
+                "fallback": args.fallback,
+                "algorithm": args.algorithm.to_string(),
+                "max_errors": args.max_errors,
+                "mismatch_penalty": args.mismatch_penalty,
+                "gap_penalty": args.gap_penalty,
+                "threshold": args.threshold,
+                "max_distance_ratio": args.max_distance_ratio,
+                "full_path": args.full_path,
+                "min_score": args.min_score,
+                "exclude": args.exclude,
+                "focus": args.focus,
+                "glob": args.glob,
+                "exclude_glob": args.exclude_glob,
+                "exclude_dir": args.exclude_dir,
+                "no_generated": args.no_generated,
+                "min_size": args.min_size,
+                "max_size": args.max_size,
+                "newer_than": args.newer_than.map(unix_seconds),
+                "older_than": args.older_than.map(unix_seconds),
+                "no_ignore": args.no_ignore,
+                "hidden": args.hidden,
+                "max_depth": args.max_depth,
+                "follow": args.follow,
+                "type": args
+                    .entry_type
+                    .to_possible_value()
+                    .map(|value| value.get_name().to_string()),
+                "sources": args.source.iter().map(SourceKind::label).collect::<Vec<String>>(),
+                "dirs_from": args.dirs_from.map(|database| format!("{:?}", database)),
+                "from_report": args.from_report,
+                "changed_since": args.changed_since,
+                "target": args.target,
+                "cargo_crate": args.cargo_crate,
+                "owned_by": args.owned_by,
+                "io_throttle": args.io_throttle,
+                "threads": args.threads,
+                "nice": args.nice,
+                "du": args.du,
+                "hash": args.hash.map(|algorithm| algorithm.name()),
+                "create": args.create,
+                "limit": args.limit,
+                "explain": args.explain,
+                "no_learn": args.no_learn,
+                "max_memory": args.max_memory,
+            }),
+            started,
+        )
+    });
+    if let Some(report) = &mut report {
+        report.record("parse", parse_time);
+    }
     let mut exclude_extension_set: BTreeSet<String> = BTreeSet::new();
     let mut focus_extension_set: BTreeSet<String> = BTreeSet::new();
+    // Extensions may be given with their dot, as in `-f .rs`.
     args.exclude.into_iter().for_each(|ext| {
-        exclude_extension_set.insert(ext.to_string());
+        exclude_extension_set.insert(ext.strip_prefix('.').unwrap_or(&ext).to_string());
     });
     args.focus.into_iter().for_each(|ext| {
-        focus_extension_set.insert(ext.to_string());
+        focus_extension_set.insert(ext.strip_prefix('.').unwrap_or(&ext).to_string());
     });
-    let files = search::walk_directory(exclude_extension_set, focus_extension_set);
-    let mut potential_hits: Vec<(u32, String, String)> = Vec::new();
-    if args.exact {
-        for (file_name, full_path) in files {
-            if file_name == args.query {
-                potential_hits.push((0, file_name, full_path));
+    let generated_patterns = if args.no_generated {
+        let path = storage::config_dir()?.join("generated");
+        std::fs::read_to_string(path).unwrap_or_default()
+    } else {
+        String::new()
+    };
+    let throttle = args.io_throttle.map(|ops| IoThrottle::new(ops as usize));
+    // Ignore files apply relative to the directory walked, so each directory gets its own chain.
+    let filters_for = |root: &Path| {
+        let mut filters = FilterChain::new()
+            .with(ExtensionFilter {
+                exclude: exclude_extension_set.clone(),
+                focus: focus_extension_set.clone(),
+            })
+            .with(HiddenFilter {
+                hidden: args.hidden,
+            })
+            .with(PathGlobFilter {
+                globs: args.glob.clone(),
+            })
+            .with(ExcludeGlobFilter {
+                globs: args.exclude_glob.clone(),
+            })
+            .with(ExcludeDirFilter {
+                names: args.exclude_dir.iter().cloned().collect(),
+            });
+        if args.no_generated {
+            filters = filters.with(GeneratedFilter::new(&generated_patterns));
+        }
+        if args.min_size.is_some() || args.max_size.is_some() {
+            filters = filters.with(SizeFilter {
+                min: args.min_size,
+                max: args.max_size,
+            });
+        }
+        if args.newer_than.is_some() || args.older_than.is_some() {
+            filters = filters.with(ModifiedFilter {
+                newer_than: args.newer_than,
+                older_than: args.older_than,
+            });
+        }
+        if let Some(max_depth) = args.max_depth {
+            filters = filters.with(DepthFilter {
+                max_depth: max_depth as usize,
+            });
+        }
+        if !args.no_ignore {
+            filters = filters.with(GitignoreFilter::new(root, throttle.as_ref()));
+        }
+        filters
+    };
+    let root_filters: Vec<FilterChain> = roots.iter().map(|root| filters_for(root)).collect();
+    // Other sources list the files of the current directory, the only root when they are searched.
+    let filters = &root_filters[0];
+    let walk_options = search::WalkOptions {
+        follow_links: args.follow,
+        files: args.entry_type != EntryType::Directory,
+        directories: args.entry_type != EntryType::File,
+    };
+    let mut source_kinds: Vec<SourceKind> = Vec::new();
+    for kind in args.source {
+        if !source_kinds.contains(&kind) {
+            source_kinds.push(kind);
+        }
+    }
+    let filter_start = Instant::now();
+    let changed_files = match &args.changed_since {
+        Some(since) => Some(git::changed_since(since)?),
+        None => None,
+    };
+    let target_files = match &args.target {
+        Some(target) => Some(build_target::target_files(target)?),
+        None => None,
+    };
+    let crate_directory = match &args.cargo_crate {
+        Some(name) => Some(build_target::crate_directory(name)?),
+        None => None,
+    };
+    let code_owners = if args.owned_by.is_empty() && !args.show_owners {
+        None
+    } else {
+        Some(codeowners::CodeOwners::load()?)
+    };
+    // Queries that look like paths match the name of their last component within the directories
+    // before it. Regexes are left alone, since backslashes escape in them, and so are fuzzy queries
+    // matched against full paths.
+    let split_query = |query: &str, mode: search::SearchMode| match mode {
+        search::SearchMode::Regex => (query.to_string(), None),
+        search::SearchMode::Fuzzy if args.full_path => (query.to_string(), None),
+        _ => search::split_path_query(query),
+    };
+    let (name_query, mut query_directories) = split_query(&query, mode);
+    let mut notice: Option<String> = None;
+    // A path pasted from elsewhere, e.g. a log, is shown on its own when it exists. Otherwise its
+    // directories are likely from another machine, so only its name is searched for.
+    let pasted_path = if args.regex {
+        None
+    } else {
+        search::existing_path(&query)
+    };
+    if pasted_path.is_none() && !args.regex && search::is_absolute_query(&query) {
+        query_directories = None;
+        notice = Some(format!(
+            "{} does not exist, so files with a similar name are shown.",
+            query
+        ));
+    }
+    // Keeps the candidates in the directories of a query, changed since the requested ref, in the
+    // requested build target or crate and owned by the requested owners.
+    let keep = |directories: Option<&PathBuf>, paths: &PathArena, ids: &mut Vec<PathId>| {
+        if let Some(directories) = directories {
+            ids.retain(|id| {
+                Path::new(paths.path(*id))
+                    .parent()
+                    .is_some_and(|parent| parent.ends_with(directories))
+            });
+        }
+        if let Some(changed_files) = &changed_files {
+            ids.retain(|id| changed_files.contains(&git::normalize_path(paths.path(*id))));
+        }
+        if let Some(target_files) = &target_files {
+            ids.retain(|id| target_files.contains(&git::normalize_path(paths.path(*id))));
+        }
+        if let Some(crate_directory) = &crate_directory {
+            ids.retain(|id| {
+                Path::new(&git::normalize_path(paths.path(*id))).starts_with(crate_directory)
+            });
+        }
+        if let Some(code_owners) = &code_owners {
+            if !args.owned_by.is_empty() {
+                ids.retain(|id| code_owners.is_owned_by(paths.path(*id), &args.owned_by));
             }
         }
-    } else if args.regex {
-        let pattern: Regex = match Regex::new(&args.query) {
-            Ok(pattern) => pattern,
-            Err(error) => return Err(error.into()),
+    };
+    let filter_setup = filter_start.elapsed();
+    // Matches a query in a mode, given the name it matches, e.g. the last component of a path.
+    let matcher_for = |query: &str,
+                       name_query: String,
+                       mode: search::SearchMode,
+                       algorithm: &search::AlgorithmChoice|
+     -> Result<Matcher, Box<dyn std::error::Error>> {
+        let is_regex = mode == search::SearchMode::Regex;
+        let case_sensitive = args.case_sensitive
+            || (args.smart_case && search::has_upper_case(&name_query, is_regex));
+        let name_query = search::compose(&name_query).into_owned();
+        let case_query = if case_sensitive {
+            name_query.clone()
+        } else {
+            name_query.to_lowercase()
         };
-        for (file_name, full_path) in files {
-            match pattern.captures(&file_name) {
-                Some(caps) => {
-                    if caps
-                        .get(0)
-                        .map_or(false, |matched| matched.as_str() == file_name)
-                    {
-                        potential_hits.push((0, file_name, full_path));
-                    }
+        Ok(match mode {
+            search::SearchMode::Exact => Matcher::Exact(case_query, case_sensitive),
+            search::SearchMode::Contains => Matcher::Contains(case_query, case_sensitive),
+            search::SearchMode::Regex => Matcher::Regex(
+                RegexBuilder::new(&search::compose(query))
+                    .case_insensitive(!case_sensitive)
+                    .build()?,
+            ),
+            search::SearchMode::Phonetic => {
+                let scorer: Box<dyn search::ScoreFunction> = match threshold {
+                    Some(threshold) => Box::new(search::WithThreshold(
+                        Box::new(search::PhoneticScorer),
+                        threshold,
+                    )),
+                    None => Box::new(search::PhoneticScorer),
+                };
+                Matcher::Fuzzy(name_query, scorer)
+            }
+            search::SearchMode::Fuzzy => {
+                if let Some(segments) = search::split_module_query(&name_query) {
+                    return Ok(Matcher::Module(
+                        segments.into_iter().map(String::from).collect(),
+                    ));
+                }
+                // The scorer options were checked against -a, and apply to any algorithm they fit.
+                // Bitap gives up on names needing more errors than it searches with, so it searches
+                // with as many as the threshold accepts.
+                let max_errors = args
+                    .max_errors
+                    .or(threshold.map(|threshold| threshold.of(&name_query)));
+                let scorer: Box<dyn search::ScoreFunction> = match (algorithm, max_errors) {
+                    (
+                        search::AlgorithmChoice::Builtin(search::FuzzySearchAlgorithm::Bitap),
+                        Some(max_errors),
+                    ) => Box::new(search::BitapScorer { max_errors }),
+                    (
+                        search::AlgorithmChoice::Builtin(
+                            search::FuzzySearchAlgorithm::SmithWaterman,
+                        ),
+                        _,
+                    ) => Box::new(search::SmithWatermanScorer {
+                        mismatch_penalty: args
+                            .mismatch_penalty
+                            .unwrap_or(search::SMITH_WATERMAN_MISMATCH),
+                        gap_penalty: args.gap_penalty.unwrap_or(search::SMITH_WATERMAN_GAP),
+                    }),
+                    (choice, _) => plugin::load_scorer(choice)?,
+                };
+                let scorer = if case_sensitive {
+                    Box::new(search::CaseSensitive(scorer))
+                } else {
+                    scorer
+                };
+                let scorer = match threshold {
+                    Some(threshold) => Box::new(search::WithThreshold(scorer, threshold)),
+                    None => scorer,
+                };
+                if args.full_path {
+                    return Ok(Matcher::FullPath(
+                        name_query,
+                        Box::new(search::FullPath(scorer)),
+                    ));
                 }
-                None => continue,
+                Matcher::Fuzzy(name_query, scorer)
+            }
+        })
+    };
+    let matcher = matcher_for(&query, name_query.clone(), mode, &args.algorithm)?;
+    let mut top_hits: search::TopHits<String> = search::TopHits::new(MAX_MEMORY_TOP_HITS);
+    let mut spill_sink = |paths: PathArena| -> Result<(), String> {
+        let mut ids: Vec<PathId> = paths.ids().collect();
+        keep(query_directories.as_ref(), &paths, &mut ids);
+        let hits = matcher.match_candidates(&paths, ids, args.min_score)?;
+        for hit in paths.to_hits(&hits) {
+            top_hits.push(hit);
+        }
+        Ok(())
+    };
+    // The UI's terminal is set up while the candidates are collected.
+    #[cfg(feature = "tui")]
+    let mut pending_terminal = (!args.prompt && args.format.is_none() && args.hash.is_none())
+        .then(gui::PendingTerminal::start);
+    // The results found so far are shown while the directory is walked for the interactive UI, as
+    // walking a large tree takes a while. Comparisons and spilled candidates are only shown once
+    // they are complete.
+    #[cfg(feature = "tui")]
+    let scans = pending_terminal.is_some()
+        && args.compare.is_none()
+        && args.max_memory.is_none()
+        && pasted_path.is_none();
+    #[cfg(feature = "tui")]
+    let mut scan_screen: Option<gui::ScanScreen> = None;
+    #[cfg(feature = "tui")]
+    let mut scan_hits: Vec<(u32, String, String)> = Vec::new();
+    #[cfg(feature = "tui")]
+    let mut scanned = 0;
+    #[cfg(feature = "tui")]
+    let mut show_scan = |batch: &[(String, String)]| -> Result<(), String> {
+        scanned += batch.len();
+        let mut paths = PathArena::new();
+        for (name, full_path) in batch {
+            paths.push(name, full_path);
+        }
+        let mut ids: Vec<PathId> = paths.ids().collect();
+        keep(query_directories.as_ref(), &paths, &mut ids);
+        scan_hits.extend(paths.to_hits(&matcher.match_candidates(&paths, ids, args.min_score)?));
+        scan_hits.sort_by_key(|hit| hit.0);
+        scan_hits.truncate(SCAN_TOP_HITS);
+        let screen = match (&mut scan_screen, pending_terminal.take()) {
+            (Some(screen), _) => screen,
+            (None, Some(pending_terminal)) => {
+                let results_terminal =
+                    pending_terminal.wait().map_err(|error| error.to_string())?;
+                scan_screen.insert(gui::ScanScreen::new(
+                    results_terminal,
+                    gui::UiOptions {
+                        code_owners: code_owners.as_ref().filter(|_| args.show_owners),
+                        score_column: args.score_column,
+                        ascii: args.ascii,
+                        theme: args.theme,
+                        query: &query,
+                        mode,
+                        ..gui::UiOptions::default()
+                    },
+                ))
             }
+            (None, None) => return Ok(()),
+        };
+        if screen
+            .draw(&scan_hits, scanned)
+            .map_err(|error| error.to_string())?
+        {
+            return Err("Quit while scanning.".to_string());
         }
+        Ok(())
+    };
+    #[cfg(feature = "tui")]
+    let mut scan_progress = scans.then_some(&mut show_scan);
+    #[cfg(not(feature = "tui"))]
+    let mut scan_progress: Option<&mut source::Progress> = None;
+    let collect_start = Instant::now();
+    let merged = {
+        let from_report = &args.from_report;
+        let sources: Vec<(String, Box<dyn CandidateSource>)> = match (args.dirs_from, from_report) {
+            // The pasted path is the only result, so nothing needs to be collected.
+            _ if pasted_path.is_some() => Vec::new(),
+            (Some(database), _) => vec![("dirs".to_string(), Box::new(database))],
+            (None, Some(report)) => vec![(
+                "report".to_string(),
+                Box::new(report::ReportSource {
+                    path: report.clone(),
+                    filters,
+                }),
+            )],
+            (None, None) => source_kinds
+                .iter()
+                .map(|kind| {
+                    let source: Box<dyn CandidateSource> = match kind {
+                        SourceKind::Fs => {
+                            let walks = roots
+                                .iter()
+                                .zip(&root_filters)
+                                .map(|(root, filters)| -> Box<dyn CandidateSource> {
+                                    Box::new(
+                                        WalkSource::new(root.clone(), filters, throttle.as_ref())
+                                            .with_options(walk_options),
+                                    )
+                                })
+                                .collect();
+                            let walk = Box::new(ChainedSource { sources: walks });
+                            match scan_progress.take() {
+                                Some(progress) => Box::new(ProgressSource {
+                                    inner: walk,
+                                    interval: SCAN_REFRESH,
+                                    progress,
+                                }),
+                                None => walk,
+                            }
+                        }
+                        SourceKind::Stdin => Box::new(LineSource {
+                            reader: std::io::stdin().lock(),
+                            filters,
+                        }),
+                        SourceKind::Git => Box::new(git::TrackedFiles { filters }),
+                        SourceKind::GitHistory => Box::new(git::HistoryFiles { filters }),
+                    };
+                    (kind.label(), source)
+                })
+                .collect(),
+        };
+        let spill = args.max_memory.map(|megabytes| source::Spill {
+            limit: usize::try_from(megabytes.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX),
+            sink: &mut spill_sink,
+        });
+        source::merge_sources_spilling(sources, spill)
+    };
+    // Quitting while the results found so far are shown stops the walk.
+    #[cfg(feature = "tui")]
+    if scan_screen.as_ref().is_some_and(gui::ScanScreen::quit) {
+        return Ok(());
+    }
+    let mut merged = merged?;
+    let paths = &merged.paths;
+    let mut ids: Vec<PathId> = paths.ids().collect();
+    if let Some(report) = &mut report {
+        report.time("collect", collect_start);
+        report.count("candidates", merged.spilled + paths.len());
+        report.warn(merged.warnings.clone());
+    }
+    let filter_start = Instant::now();
+    keep(query_directories.as_ref(), paths, &mut ids);
+    if let Some(report) = &mut report {
+        report.record("filter", filter_setup + filter_start.elapsed());
+        report.count("filtered", ids.len());
+    }
+    let match_start = Instant::now();
+    let mut matched: Vec<(u32, PathId)> = Vec::new();
+    let mut potential_hits = if let Some(path) = pasted_path {
+        vec![(0, search::split_path_query(&path).0, path)]
+    } else if merged.spilled == 0 {
+        matched = matcher.match_candidates(paths, ids, args.min_score)?;
+        paths.to_hits(&matched)
     } else {
-        let mut ranked_files: Vec<(u32, String, String)> = Vec::new();
-        for (file_name, full_path) in files {
-            match search::score_fuzzy_search(
-                args.query.clone(),
-                file_name.clone(),
-                search::FuzzySearchAlgorithm::DamerauLevenshtein,
-            ) {
-                Ok(score) => ranked_files.push((score, file_name.clone(), full_path)),
-                Err(error) => return Err(error.into()),
-            };
-        }
-        ranked_files.sort_by(|a, b| a.0.cmp(&b.0));
-        let threshold: u32 = match args.query.len() {
-            0..=4 => (args.query.len() as f32 * 0.20).ceil() as u32,
-            5..=10 => (args.query.len() as f32 * 0.30).ceil() as u32,
-            _ => (args.query.len() as f32 * 0.40).ceil() as u32,
+        spill_sink(std::mem::take(&mut merged.paths))?;
+        notice = Some(if top_hits.pushed() > MAX_MEMORY_TOP_HITS {
+            format!(
+                "The candidates exceeded --max-memory, so only the best {} of {} results are shown.",
+                MAX_MEMORY_TOP_HITS,
+                top_hits.pushed()
+            )
+        } else {
+            "The candidates exceeded --max-memory and were matched while they were collected."
+                .to_string()
+        });
+        top_hits.into_sorted_vec()
+    };
+    let fell_back = args.fallback && potential_hits.is_empty();
+    if fell_back {
+        // The query is split into a name and directories like any fuzzy query, even in regex mode.
+        let (name_query, directories) = search::split_path_query(&query);
+        let matcher = matcher_for(
+            &query,
+            name_query,
+            search::SearchMode::Fuzzy,
+            &args.algorithm,
+        )?;
+        let mut ids: Vec<PathId> = merged.paths.ids().collect();
+        keep(directories.as_ref(), &merged.paths, &mut ids);
+        matched = matcher.match_candidates(&merged.paths, ids, args.min_score)?;
+        potential_hits = merged.paths.to_hits(&matched);
+        notice = Some(format!(
+            "No {} matches were found, so these are fuzzy fallback results.",
+            mode.name()
+        ));
+    }
+    if let Some(report) = &mut report {
+        report.count("spilled", merged.spilled);
+        report.warn(notice.clone());
+        report.time("match", match_start);
+    }
+    // Labels are only worth showing when results can come from more than one source, and are
+    // incomplete once candidates were spilled.
+    let source_labels: Option<BTreeMap<String, String>> =
+        (source_kinds.len() > 1 && args.dirs_from.is_none() && merged.spilled == 0).then(|| {
+            matched
+                .iter()
+                .map(|(_, id)| {
+                    (
+                        merged.paths.path(*id).to_string(),
+                        merged.labels(*id).join(" "),
+                    )
+                })
+                .collect()
+        });
+    let rank_start = Instant::now();
+    let match_scores: BTreeMap<String, u32> = if args.explain {
+        potential_hits
+            .iter()
+            .map(|(score, _, full_path)| (full_path.clone(), *score))
+            .collect()
+    } else {
+        BTreeMap::new()
+    };
+    let adjustments = adjust::ScoreAdjustments::load()?;
+    adjustments.apply(&mut potential_hits);
+    let mut rank_weights = if args.no_learn {
+        rank::RankWeights::none()
+    } else {
+        rank::RankWeights::load()
+    };
+    rank_weights.apply(&mut potential_hits);
+    if let Some(report) = &mut report {
+        report.time("rank", rank_start);
+        report.count("results", potential_hits.len());
+    }
+    // Checksums and disk usage cover every result unless a limit is asked for.
+    let limit = if (args.hash.is_some() || args.du) && !given("limit") {
+        0
+    } else {
+        args.limit
+    };
+    if limit > 0 && potential_hits.len() > limit {
+        let limited = format!(
+            "Showing the best {} of {} results. Pass --limit 0 to see them all.",
+            limit,
+            potential_hits.len()
+        );
+        notice = Some(match notice {
+            Some(notice) => format!("{} {}", notice, limited),
+            None => limited,
+        });
+        potential_hits.truncate(limit);
+    }
+    if let (Some(report), Some(report_path)) = (&report, &args.report) {
+        report.write(report_path, &potential_hits, source_labels.as_ref())?;
+    }
+    // The hooks of the config file are told about the search, whatever shows its results.
+    let results = potential_hits.len();
+    let run_hook = |event: hook::Event, files: &[&str]| {
+        let command = match event {
+            hook::Event::Open => config.on_open.as_deref(),
+            hook::Event::Results => config.on_results.as_deref(),
+            hook::Event::NoResults => config.on_no_results.as_deref(),
         };
-        for (score, file_name, full_path) in ranked_files {
-            if score <= threshold {
-                potential_hits.push((score, file_name, full_path));
+        let context = hook::Context {
+            query: &query,
+            mode: if fell_back {
+                search::SearchMode::Fuzzy
             } else {
-                break;
+                mode
+            },
+            directory: &roots[0],
+            results,
+            elapsed: started.elapsed(),
+            files,
+        };
+        hook::run(command, event, &context)
+    };
+    match potential_hits.first() {
+        Some((_, _, best)) => run_hook(hook::Event::Results, &[best])?,
+        None => run_hook(hook::Event::NoResults, &[])?,
+    }
+    if args.create && potential_hits.is_empty() {
+        // The UI isn't shown, so its terminal is restored before the editor takes over.
+        #[cfg(feature = "tui")]
+        {
+            drop(scan_screen);
+            drop(pending_terminal);
+        }
+        // Files are created in the first directory searched, from the templates in the config
+        // directory.
+        let templates = storage::config_dir().ok().map(|dir| dir.join("templates"));
+        let full_path = editor::create_file(&roots[0], &query, templates.as_deref())?;
+        println!("Created {}.", full_path);
+        run_hook(hook::Event::Open, &[&full_path])?;
+        let created = [(0, search::candidate_name_of_path(&full_path), full_path)];
+        let launcher = editor::SystemLauncher;
+        if args.wait {
+            let status =
+                editor::edit_selection(&launcher, &args.default_editor_command, &created, &[0])?;
+            if status != 0 {
+                std::process::exit(status);
             }
+        } else {
+            editor::open_selection(
+                &launcher,
+                args.terminal.as_deref(),
+                &args.default_editor_command,
+                &created,
+                &[0],
+            )?;
         }
+        return Ok(());
     }
-    if cfg!(feature = "open_in_editor") {
-        if potential_hits.is_empty() {
-            println!("No files found.");
-        } else {
-            println!("{} files found:", potential_hits.len());
-            let mut file_number: usize = 1;
-            for (score, file_name, full_path) in potential_hits.clone() {
-                if score == 0 {
-                    println!(
-                        "{}. \x1b[32m{}\x1b[0m - {}",
-                        file_number, file_name, full_path
-                    ); // Green color for score 0
-                } else {
-                    println!(
-                        "{}. \x1b[34m{}\x1b[0m - {}",
-                        file_number, file_name, full_path
-                    ); // Blue color for other scores
-                }
-                file_number += 1;
-            }
-            return editor::experimental_open_files(
-                args.default_editor_command,
-                file_number,
-                potential_hits,
+    if args.explain {
+        if let Some(notice) = &notice {
+            eprintln!("{}", notice);
+        }
+        for (score, _, full_path) in &potential_hits {
+            let weight = rank_weights.weight(full_path);
+            println!(
+                "{}",
+                adjustments.explain(full_path, *score, match_scores[full_path], weight)
             );
         }
+        if let Some(report) = report.as_ref().filter(|_| args.timings) {
+            report.print_timings();
+        }
+        return Ok(());
+    }
+    if let Some(algorithm) = args.hash {
+        if let Some(notice) = &notice {
+            eprintln!("{}", notice);
+        }
+        print!("{}", checksum::manifest(algorithm, &potential_hits)?);
+        if let Some(report) = report.as_ref().filter(|_| args.timings) {
+            report.print_timings();
+        }
+        return Ok(());
+    }
+    if let Some(format) = args.format {
+        // Keep the exported results clean for the tools that read them.
+        if let Some(notice) = &notice {
+            eprintln!("{}", notice);
+        }
+        if args.du {
+            for line in usage::DiskUsage::of(&potential_hits).summary(usage::SUMMARY_TOP) {
+                eprintln!("{}", line);
+            }
+        }
+        print!("{}", export::render(format, &query, &potential_hits));
+        if let Some(report) = report.as_ref().filter(|_| args.timings) {
+            report.print_timings();
+        }
+        return Ok(());
+    }
+    let ranked_paths: Vec<String> = potential_hits
+        .iter()
+        .map(|(_, _, full_path)| full_path.clone())
+        .collect();
+    // The interactive UI shows the notice in its footer instead.
+    if args.prompt || cfg!(not(feature = "tui")) {
+        if let Some(notice) = &notice {
+            println!("{}", notice);
+        }
+        if args.du {
+            for line in usage::DiskUsage::of(&potential_hits).summary(usage::SUMMARY_TOP) {
+                println!("{}", line);
+            }
+        }
+    }
+    #[cfg(feature = "tui")]
+    let results_terminal = match scan_screen {
+        Some(scan_screen) => Some(Ok(scan_screen.finish())),
+        None => pending_terminal.map(gui::PendingTerminal::wait),
+    };
+    // The prompt opens only results of the first search, while the UI reports every file opened.
+    let picked_paths = |picked: &[usize]| -> Vec<String> {
+        picked
+            .iter()
+            .map(|index| ranked_paths[*index].clone())
+            .collect()
+    };
+    #[cfg(feature = "tui")]
+    let (picked, opened, editor_status) = if let Some(results_terminal) = results_terminal {
+        let results_terminal = results_terminal?;
+        if let Some(report) = &mut report {
+            report.record("tui_init", results_terminal.setup_time);
+        }
+        if let Some(compare) = &args.compare {
+            // Both sides are compared before the ranking weights reorder them.
+            let left = merged.paths.to_hits(&matched);
+            let matcher = matcher_for(&query, name_query, mode, compare)?;
+            let mut ids: Vec<PathId> = merged.paths.ids().collect();
+            keep(query_directories.as_ref(), &merged.paths, &mut ids);
+            let right = merged.paths.to_hits(&matcher.match_candidates(
+                &merged.paths,
+                ids,
+                args.min_score,
+            )?);
+            gui::display_comparison_ui(
+                results_terminal,
+                (&args.algorithm.to_string(), &left),
+                (&compare.to_string(), &right),
+                gui::UiOptions {
+                    ascii: args.ascii,
+                    theme: args.theme,
+                    keys: args.keys.map(|keys| keys.0).unwrap_or_default(),
+                    ..gui::UiOptions::default()
+                },
+            )?;
+            return Ok(());
+        }
+        // Matches a query typed in the UI among candidates, ranked like the first search. New tabs
+        // and tabs switched to another mode search the candidates of the first walk again, without
+        // walking the disk, while `:cd` searches those of another directory.
+        let search_paths = |paths: &PathArena,
+                            tab_query: &str,
+                            mode: search::SearchMode|
+         -> Result<Vec<(u32, String, String)>, String> {
+            let (name_query, directories) = split_query(tab_query, mode);
+            let matcher = matcher_for(tab_query, name_query, mode, &args.algorithm)
+                .map_err(|error| error.to_string())?;
+            let mut ids: Vec<PathId> = paths.ids().collect();
+            keep(directories.as_ref(), paths, &mut ids);
+            let matched = matcher.match_candidates(paths, ids, args.min_score)?;
+            let mut hits = paths.to_hits(&matched);
+            adjustments.apply(&mut hits);
+            rank_weights.apply(&mut hits);
+            Ok(hits)
+        };
+        // The similarity column rescores the results of each tab with the matcher of its query.
+        let similarity = |tab_query: &str,
+                          mode: search::SearchMode,
+                          hits: &[(u32, String, String)]|
+         -> Option<Vec<u32>> {
+            let name_query = split_query(tab_query, mode).0;
+            let matcher = matcher_for(tab_query, name_query, mode, &args.algorithm).ok()?;
+            Some(
+                hits.iter()
+                    .map(|(score, name, full_path)| matcher.similarity(name, full_path, *score))
+                    .collect(),
+            )
+        };
+        let search_tab = |tab_query: &str, mode: search::SearchMode| {
+            search_paths(&merged.paths, tab_query, mode)
+        };
+        // `:cd` walks another directory with the options of the first walk.
+        let search_directory = |root: &Path, tab_query: &str, mode: search::SearchMode| {
+            let filters = filters_for(root);
+            let mut paths = PathArena::new();
+            WalkSource::new(root.to_path_buf(), &filters, throttle.as_ref())
+                .with_options(walk_options)
+                .visit_candidates(&mut |name, full_path| {
+                    paths.push(name, full_path);
+                    Ok(())
+                })?;
+            search_paths(&paths, tab_query, mode)
+        };
+        let counterpart_patterns = counterpart::CounterpartPatterns::load()?;
+        let last_commit = |full_path: &str| {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+            git::LastCommit::of(full_path).map(|commit| commit.label(now))
+        };
+        let (picked, opened) = gui::display_results_ui(
+            results_terminal,
+            potential_hits,
+            &args.default_editor_command,
+            gui::UiOptions {
+                code_owners: code_owners.as_ref().filter(|_| args.show_owners),
+                source_labels: source_labels.as_ref(),
+                last_commit: args
+                    .show_last_commit
+                    .then_some(&last_commit as &gui::LastCommitLabel),
+                score_column: args.score_column,
+                ascii: args.ascii,
+                theme: args.theme,
+                wait: args.wait,
+                terminal: args.terminal.as_deref(),
+                notice: notice.as_deref(),
+                keys: args.keys.map(|keys| keys.0).unwrap_or_default(),
+                query: &query,
+                mode: if fell_back {
+                    search::SearchMode::Fuzzy
+                } else {
+                    mode
+                },
+                // Candidates that were spilled or never collected can't be searched again.
+                search: (!merged.paths.is_empty()).then_some(&search_tab),
+                search_directory: Some(&search_directory),
+                counterparts: Some(&counterpart_patterns),
+                similarity: Some(&similarity),
+                disk_usage: args.du,
+            },
+        )?;
+        (picked, opened, None)
+    } else {
+        let (picked, editor_status) = editor::prompt_results(
+            args.default_editor_command,
+            args.terminal.as_deref(),
+            &potential_hits,
+            source_labels.as_ref(),
+            args.wait,
+        )?;
+        let opened = picked_paths(&picked);
+        (picked, opened, editor_status)
+    };
+    #[cfg(not(feature = "tui"))]
+    let (picked, editor_status) = editor::prompt_results(
+        args.default_editor_command,
+        args.terminal.as_deref(),
+        &potential_hits,
+        source_labels.as_ref(),
+        args.wait,
+    )?;
+    #[cfg(not(feature = "tui"))]
+    let opened = picked_paths(&picked);
+    if !opened.is_empty() {
+        let opened: Vec<&str> = opened.iter().map(String::as_str).collect();
+        run_hook(hook::Event::Open, &opened)?;
+    }
+    if !picked.is_empty() && !args.no_learn {
+        rank_weights.record_picks(&ranked_paths, &picked);
+        // Failing to learn from the picks doesn't fail the search.
+        if let Err(error) = rank_weights.save() {
+            eprintln!("Failed to save the learned ranking: {}", error);
+        }
+    }
+    if let Some(report) = report.as_ref().filter(|_| args.timings) {
+        report.print_timings();
+    }
+    match editor_status {
+        Some(code) if code != 0 => std::process::exit(code),
+        _ => Ok(()),
     }
-    return gui::display_results_ui(potential_hits, &args.default_editor_command);
 }
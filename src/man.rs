@@ -0,0 +1,162 @@
+use clap_mangen::roff::{bold, italic, roman, Roff};
+use clap_mangen::Man;
+use std::io::Write;
+
+/// The files of the config directory, with what they hold.
+const CONFIG_FILES: &[(&str, &str)] = &[
+    (
+        "config",
+        "Defaults for the options, one name = value per line, as written by fuzzy-ls setup, and \
+         the on_open, on_results and on_no_results hooks.",
+    ),
+    (
+        "scopes",
+        "Named directories searched with @name queries, one name = directory per line.",
+    ),
+    (
+        "adjustments",
+        "Numbers added to the scores of results matching an extension or pattern, one \
+         pattern and number per line.",
+    ),
+    (
+        "counterparts",
+        "The test patterns of each extension, one extension per line followed by its patterns, \
+         e.g. rs {}_test tests/{}, for the counterparts tab of the results UI.",
+    ),
+    (
+        "generated",
+        "More gitignore-style patterns of generated files left out with --no-generated, one per \
+         line.",
+    ),
+    (
+        "templates",
+        "A directory of templates named after extensions, e.g. rs, that files created with \
+         --create start out with. {{name}} is replaced by the name of the new file.",
+    ),
+    (
+        "plugins",
+        "Scorer plugins selected with --algorithm plugin:<name>.",
+    ),
+];
+
+/// Renders the fuzzy-ls man page from the command line definition.
+///
+/// Next to the sections generated from the clap definitions, the page documents the query syntax,
+/// the selection syntax of the numbered prompt, the keybindings of the results UI and the files
+/// fuzzy-ls reads and writes.
+///
+/// # Arguments
+///
+/// * `command` - The clap definition of the command line.
+/// * `out` - Where the roff source is written.
+pub fn render(command: clap::Command, out: &mut dyn Write) -> Result<(), std::io::Error> {
+    let man = Man::new(command);
+    man.render_title(out)?;
+    man.render_name_section(out)?;
+    man.render_synopsis_section(out)?;
+    man.render_description_section(out)?;
+    man.render_options_section(out)?;
+    man.render_subcommands_section(out)?;
+
+    let mut roff = Roff::new();
+    roff.control("SH", ["QUERY SYNTAX"]);
+    roff.text([
+        roman("Files are matched by their name without the final extension, so the query "),
+        italic("main"),
+        roman(" matches "),
+        italic("main.rs"),
+        roman(". Dotfiles like "),
+        italic(".gitignore"),
+        roman(" and names without a dot like "),
+        italic("Makefile"),
+        roman(" have no extension and are matched by their whole name."),
+    ]);
+    roff.control("TP", []);
+    roff.text([bold("fuzzy (default)")]);
+    roff.text([roman(
+        "Names scored within a threshold that grows with the query length are listed, closest \
+         first. A score of 0 is an exact match. The scorer is an edit distance by default and is \
+         chosen with -a, e.g. -a jaro-winkler or -a plugin:<name>.",
+    )]);
+    roff.control("TP", []);
+    roff.text([bold("--contains")]);
+    roff.text([roman("The name has to contain the query.")]);
+    roff.control("TP", []);
+    roff.text([bold("-r, --regex")]);
+    roff.text([roman(
+        "The query is a regular expression that has to match the whole name.",
+    )]);
+    roff.control("TP", []);
+    roff.text([bold("-p, --exact")]);
+    roff.text([roman("The name has to equal the query.")]);
+    roff.control("TP", []);
+    roff.text([bold("--phonetic")]);
+    roff.text([roman(
+        "Every word of the query has to sound like a word of the name, e.g. kolor finds color_utils.",
+    )]);
+    roff.control("TP", []);
+    roff.text([bold("--full-path")]);
+    roff.text([roman(
+        "A fuzzy query is matched against the path relative to the searched directory, segment by \
+         segment, e.g. handlers/user finds api/handlers/user.rs.",
+    )]);
+    roff.control("PP", []);
+    roff.text([roman(
+        "Every mode ignores case by default. --case-sensitive makes case matter, and -S, \
+         --smart-case makes it matter only for queries with upper case letters.",
+    )]);
+    roff.control("PP", []);
+    roff.text([
+        roman("A query with directories, e.g. "),
+        italic("src/ui/main.rs"),
+        roman(", matches its last component within those directories. A query like "),
+        italic("@notes meeting"),
+        roman(
+            " searches the directory of the scope notes, from the scopes file, for the query after \
+             it; other queries starting with @ are searched for as they are, and @@ escapes a name \
+             that is also a scope.",
+        ),
+    ]);
+
+    roff.control("SH", ["PROMPT SELECTION"]);
+    roff.text([
+        roman("With "),
+        bold("--prompt"),
+        roman(", results are listed with numbers and the files to open are picked by entering numbers and ranges such as "),
+        italic("1,3-5"),
+        roman(", "),
+        italic("a"),
+        roman(" for all results, or "),
+        italic("q"),
+        roman(" or an empty line to quit."),
+    ]);
+
+    #[cfg(feature = "tui")]
+    {
+        roff.control("SH", ["KEYBINDINGS"]);
+        for (keys, action) in crate::gui::KEYBINDINGS {
+            roff.control("TP", []);
+            roff.text([bold(*keys)]);
+            roff.text([roman(*action)]);
+        }
+    }
+
+    roff.control("SH", ["FILES"]);
+    roff.text([roman(
+        "Files in ~/.config/fuzzy-ls honor $XDG_CONFIG_HOME, and %APPDATA%\\fuzzy-ls is used on Windows.",
+    )]);
+    for (file, description) in CONFIG_FILES {
+        roff.control("TP", []);
+        roff.text([italic(format!("~/.config/fuzzy-ls/{}", file))]);
+        roff.text([roman(*description)]);
+    }
+    roff.control("TP", []);
+    roff.text([italic("~/.local/share/fuzzy-ls/rank")]);
+    roff.text([roman(
+        "Ranking weights learned per project from the results picked. $XDG_DATA_HOME is honored.",
+    )]);
+    roff.to_writer(out)?;
+
+    man.render_version_section(out)?;
+    man.render_authors_section(out)
+}
@@ -0,0 +1,137 @@
+use crate::storage;
+use fuzzy_ls::search::{AlgorithmChoice, ScoreFunction};
+use libloading::Library;
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::fs;
+use std::path::PathBuf;
+
+/// Version of the scorer plugin ABI. Plugins must export `ffs_plugin_abi_version` returning it.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// `ffs_score(query, query_len, file_name, file_name_len) -> score` over UTF-8 byte buffers.
+/// Lower scores are better and `0` denotes an exact match, like the built-in scorers.
+type ScoreFn = unsafe extern "C" fn(*const u8, usize, *const u8, usize) -> u32;
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+
+/// A custom scorer loaded from a dynamic library in the plugins directory.
+pub struct ScorerPlugin {
+    score: ScoreFn,
+    _library: Library,
+}
+
+/// Returns the directory scorer plugins are discovered from.
+pub fn plugins_dir() -> Result<PathBuf, String> {
+    Ok(storage::config_dir()?.join("plugins"))
+}
+
+/// Lists the names of the scorer plugins available in the plugins directory.
+pub fn available_plugins() -> Vec<String> {
+    let Ok(entries) = plugins_dir().and_then(|dir| fs::read_dir(dir).map_err(|e| e.to_string()))
+    else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            file_name
+                .strip_prefix(DLL_PREFIX)?
+                .strip_suffix(DLL_SUFFIX)
+                .map(String::from)
+        })
+        .collect()
+}
+
+impl ScorerPlugin {
+    /// Loads the scorer plugin `name` from the plugins directory, e.g. `libname.so` on Linux.
+    ///
+    /// # Returns
+    ///
+    /// The loaded plugin, or an error message if it is missing, fails to load or targets another ABI version.
+    pub fn load(name: &str) -> Result<ScorerPlugin, String> {
+        // Plugins are only loaded from the plugins directory, never from a path.
+        if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+            return Err(format!(
+                "{} is not a plugin name: names can't contain path separators or ..",
+                name
+            ));
+        }
+        let path = plugins_dir()?.join(format!("{}{}{}", DLL_PREFIX, name, DLL_SUFFIX));
+        if !path.exists() {
+            return Err(format!(
+                "Scorer plugin {} not found at {}. Available plugins: [{}]",
+                name,
+                path.display(),
+                available_plugins().join(", ")
+            ));
+        }
+        // SAFETY: loading a plugin runs its initializers; plugins are trusted user-installed code.
+        let library = unsafe { Library::new(&path) }
+            .map_err(|error| format!("Failed to load scorer plugin {}: {}", name, error))?;
+        // SAFETY: the symbol types match the documented plugin ABI.
+        let (abi_version, score) = unsafe {
+            let abi_version = library
+                .get::<AbiVersionFn>(b"ffs_plugin_abi_version")
+                .map_err(|error| format!("Invalid scorer plugin {}: {}", name, error))?;
+            let score = library
+                .get::<ScoreFn>(b"ffs_score")
+                .map_err(|error| format!("Invalid scorer plugin {}: {}", name, error))?;
+            (abi_version(), *score)
+        };
+        if abi_version != PLUGIN_ABI_VERSION {
+            return Err(format!(
+                "Scorer plugin {} targets ABI version {} but version {} is required",
+                name, abi_version, PLUGIN_ABI_VERSION
+            ));
+        }
+        Ok(ScorerPlugin {
+            score,
+            _library: library,
+        })
+    }
+
+    /// Scores a file name against the query with the plugin.
+    pub fn score(&self, query: &str, file_name: &str) -> u32 {
+        // SAFETY: the buffers outlive the call and the library is kept loaded by `self`.
+        unsafe {
+            (self.score)(
+                query.as_ptr(),
+                query.len(),
+                file_name.as_ptr(),
+                file_name.len(),
+            )
+        }
+    }
+}
+
+impl ScoreFunction for ScorerPlugin {
+    fn score(&self, query: &str, file_name: &str) -> Result<u32, String> {
+        Ok(ScorerPlugin::score(self, query, file_name))
+    }
+
+    /// The plugin ABI doesn't require `ffs_score` to be thread-safe.
+    fn parallel(&self) -> bool {
+        false
+    }
+}
+
+/// Prepares the scorer for the selected algorithm, loading the plugin if one was requested.
+pub fn load_scorer(choice: &AlgorithmChoice) -> Result<Box<dyn ScoreFunction>, String> {
+    match choice {
+        AlgorithmChoice::Builtin(algorithm) => Ok(Box::new(*algorithm)),
+        AlgorithmChoice::Plugin(name) => Ok(Box::new(ScorerPlugin::load(name)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_names() {
+        for name in ["../../tmp/x", "x/y", "x\\y", "..", ""] {
+            let error = ScorerPlugin::load(name).err().unwrap();
+            assert!(error.ends_with("names can't contain path separators or .."));
+        }
+    }
+}
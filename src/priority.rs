@@ -0,0 +1,76 @@
+/// How much `--nice` raises the nice value, the same as `nice` does by default.
+#[cfg(unix)]
+const NICE_INCREMENT: libc::c_int = 10;
+
+/// Lowers the CPU and I/O priority of the process, so a large scan yields to builds and other
+/// foreground work. Threads started afterwards inherit the lower priority.
+///
+/// # Returns
+///
+/// An error message if the priority could not be lowered.
+#[cfg(unix)]
+pub fn lower_priority() -> Result<(), String> {
+    // SAFETY: nice only reads its integer argument. Lowering the priority never needs privileges.
+    // It returns the new nice value, which can only be -1 on failure for a process that wasn't
+    // already running with a raised priority.
+    let result = unsafe { libc::nice(NICE_INCREMENT) };
+    if result == -1 {
+        return Err(format!(
+            "Failed to lower the CPU priority: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    lower_io_priority()
+}
+
+/// Moves the process to the idle I/O scheduling class, so its reads only use otherwise idle disk time.
+#[cfg(target_os = "linux")]
+fn lower_io_priority() -> Result<(), String> {
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+    const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+    const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+    // SAFETY: ioprio_set only reads its integer arguments.
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            0,
+            IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+        )
+    };
+    if result != 0 {
+        return Err(format!(
+            "Failed to lower the I/O priority: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+/// Other Unix systems lower the I/O priority together with the CPU priority.
+#[cfg(all(unix, not(target_os = "linux")))]
+fn lower_io_priority() -> Result<(), String> {
+    Ok(())
+}
+
+/// Lowers the CPU, I/O and memory priority of the process, so a large scan yields to builds and
+/// other foreground work.
+///
+/// # Returns
+///
+/// An error message if the priority could not be lowered.
+#[cfg(windows)]
+pub fn lower_priority() -> Result<(), String> {
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentProcess, SetPriorityClass, PROCESS_MODE_BACKGROUND_BEGIN,
+    };
+    // SAFETY: GetCurrentProcess returns a pseudo handle that is always valid for the process.
+    let result = unsafe { SetPriorityClass(GetCurrentProcess(), PROCESS_MODE_BACKGROUND_BEGIN) };
+    if result == 0 {
+        return Err(format!(
+            "Failed to lower the process priority: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
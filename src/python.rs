@@ -0,0 +1,58 @@
+use crate::filter::{ExtensionFilter, FilterChain};
+use crate::search::{self, AlgorithmChoice};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Ranks candidate paths against a query exactly like the fuzzy-ls command line does.
+///
+/// Returns `(score, name, path)` tuples, best first. Lower scores are better and `0` is an exact match.
+#[pyfunction]
+#[pyo3(signature = (query, candidates, algorithm = "damerau-levenshtein"))]
+fn rank(
+    query: &str,
+    candidates: Vec<String>,
+    algorithm: &str,
+) -> PyResult<Vec<(u32, String, String)>> {
+    let algorithm = match algorithm.parse::<AlgorithmChoice>() {
+        Ok(AlgorithmChoice::Builtin(algorithm)) => algorithm,
+        Ok(AlgorithmChoice::Plugin(_)) => {
+            return Err(PyValueError::new_err(
+                "Scorer plugins are not supported by the Python bindings",
+            ))
+        }
+        Err(error) => return Err(PyValueError::new_err(error)),
+    };
+    let candidates = candidates
+        .into_iter()
+        .map(|path| (search::candidate_name_of_path(&path), path))
+        .collect();
+    search::rank_candidates(query, candidates, &algorithm).map_err(PyValueError::new_err)
+}
+
+/// Walks `root` recursively and returns `(name, path)` tuples for every file, where the name is the
+/// file name without its final extension.
+///
+/// `exclude` skips files with the given extensions and `focus` keeps only files with the given
+/// extensions, taking precedence over `exclude`.
+#[pyfunction]
+#[pyo3(signature = (root = ".", exclude = None, focus = None))]
+fn walk(
+    root: &str,
+    exclude: Option<Vec<String>>,
+    focus: Option<Vec<String>>,
+) -> Vec<(String, String)> {
+    let exclude: BTreeSet<String> = exclude.unwrap_or_default().into_iter().collect();
+    let focus: BTreeSet<String> = focus.unwrap_or_default().into_iter().collect();
+    let filters = FilterChain::new().with(ExtensionFilter { exclude, focus });
+    search::walk_directory(Path::new(root), &filters)
+}
+
+/// Python bindings of the fuzzy-ls matcher.
+#[pymodule]
+fn fuzzy_ls(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(rank, module)?)?;
+    module.add_function(wrap_pyfunction!(walk, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use crate::storage;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Weight added to a result every time it is picked.
+const PICK_REWARD: f64 = 1.0;
+/// Weight removed from a result every time it is ranked above the picked one but skipped.
+const SKIP_PENALTY: f64 = 0.25;
+/// Maximum absolute weight, i.e. the most a learned weight can move a result by in score units.
+const MAX_WEIGHT: f64 = 2.0;
+
+/// Per-project ranking weights learned from which results are picked and which are skipped.
+pub struct RankWeights {
+    store_path: PathBuf,
+    weights: BTreeMap<String, f64>,
+    /// The directory relative paths of results are resolved against, read once on loading.
+    current_dir: PathBuf,
+}
+
+impl RankWeights {
+    fn store_path() -> Result<PathBuf, String> {
+        Ok(storage::data_dir()?
+            .join("rank")
+            .join(format!("{}.tsv", storage::project_id())))
+    }
+
+    /// Loads the learned weights of the current project. Missing or unreadable stores yield no
+    /// weights, and without a data directory nothing is learned, so searches never fail here.
+    pub fn load() -> RankWeights {
+        let Ok(store_path) = RankWeights::store_path() else {
+            return RankWeights::none();
+        };
+        let weights = fs::read_to_string(&store_path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let (weight, path) = line.split_once('\t')?;
+                Some((path.to_string(), weight.parse().ok()?))
+            })
+            .collect();
+        RankWeights {
+            store_path,
+            weights,
+            current_dir: RankWeights::current_dir(),
+        }
+    }
+
+    /// No weights, for runs that neither rank by nor learn from picks.
+    pub fn none() -> RankWeights {
+        RankWeights {
+            store_path: PathBuf::new(),
+            weights: BTreeMap::new(),
+            current_dir: RankWeights::current_dir(),
+        }
+    }
+
+    /// Writes the learned weights back to the project store, creating the data directory if needed.
+    /// Weights that weren't loaded from a store aren't written.
+    pub fn save(&self) -> Result<(), String> {
+        if self.store_path.as_os_str().is_empty() {
+            return Ok(());
+        }
+        if let Some(parent) = self.store_path.parent() {
+            fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+        }
+        let contents: String = self
+            .weights
+            .iter()
+            .map(|(path, weight)| format!("{}\t{}\n", weight, path))
+            .collect();
+        fs::write(&self.store_path, contents).map_err(|error| error.to_string())
+    }
+
+    /// Removes the learned weights of the current project.
+    pub fn reset() -> Result<(), String> {
+        let store_path = RankWeights::store_path()?;
+        if store_path.exists() {
+            fs::remove_file(&store_path).map_err(|error| error.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// The key a result is learned under: its absolute path, with `.` and `..` resolved against
+    /// the current directory without touching the file system, so ranking costs no system call
+    /// per result.
+    fn key(current_dir: &Path, full_path: &str) -> String {
+        let mut key = PathBuf::new();
+        for component in current_dir.join(full_path).components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    key.pop();
+                }
+                component => key.push(component),
+            }
+        }
+        key.to_string_lossy().to_string()
+    }
+
+    fn current_dir() -> PathBuf {
+        env::current_dir().unwrap_or_default()
+    }
+
+    /// Returns the weight learned for a result, 0 when nothing was learned about it.
+    pub fn weight(&self, full_path: &str) -> f64 {
+        if self.weights.is_empty() {
+            return 0.0;
+        }
+        self.weights
+            .get(&RankWeights::key(&self.current_dir, full_path))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Reorders the hits so that learned weights act as a bounded bonus or penalty on the score.
+    /// Scores themselves are left unchanged.
+    pub fn apply(&self, potential_hits: &mut [(u32, String, String)]) {
+        if self.weights.is_empty() {
+            return;
+        }
+        let mut keyed: Vec<(f64, (u32, String, String))> = potential_hits
+            .iter()
+            .map(|hit| {
+                let weight = self
+                    .weights
+                    .get(&RankWeights::key(&self.current_dir, &hit.2))
+                    .copied()
+                    .unwrap_or(0.0);
+                (hit.0 as f64 - weight, hit.clone())
+            })
+            .collect();
+        keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (slot, (_, hit)) in potential_hits.iter_mut().zip(keyed) {
+            *slot = hit;
+        }
+    }
+
+    /// Learns from picks: picked results are rewarded and every other result ranked above the last
+    /// pick is penalized.
+    ///
+    /// # Arguments
+    ///
+    /// * `ranked_paths` - The full paths of the results in the order they were shown.
+    /// * `picked` - The indices of the picked results.
+    pub fn record_picks(&mut self, ranked_paths: &[String], picked: &[usize]) {
+        let Some(last_pick) = picked.iter().max() else {
+            return;
+        };
+        for (index, full_path) in ranked_paths.iter().enumerate().take(last_pick + 1) {
+            let delta = if picked.contains(&index) {
+                PICK_REWARD
+            } else {
+                -SKIP_PENALTY
+            };
+            let weight = self
+                .weights
+                .entry(RankWeights::key(&self.current_dir, full_path))
+                .or_insert(0.0);
+            *weight = (*weight + delta).clamp(-MAX_WEIGHT, MAX_WEIGHT);
+        }
+        self.weights.retain(|_, weight| weight.abs() > f64::EPSILON);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_picks_reorder_hits() {
+        let mut rank_weights = RankWeights {
+            store_path: PathBuf::new(),
+            weights: BTreeMap::new(),
+            current_dir: PathBuf::from("/work/app"),
+        };
+        let ranked_paths = vec!["a".to_string(), "b".to_string()];
+        rank_weights.record_picks(&ranked_paths, &[1]);
+        rank_weights.record_picks(&ranked_paths, &[1]);
+        let mut potential_hits = vec![
+            (1, "a".to_string(), "a".to_string()),
+            (2, "b".to_string(), "b".to_string()),
+            (3, "c".to_string(), "c".to_string()),
+        ];
+        rank_weights.apply(&mut potential_hits);
+        assert_eq!(potential_hits[0].2, "b");
+        assert_eq!(potential_hits[0].0, 2);
+        assert_eq!(potential_hits[1].2, "a");
+        assert_eq!(rank_weights.weight("./b"), 2.0);
+        assert_eq!(
+            RankWeights::key(Path::new("/work/app"), "./src/../lib.rs"),
+            Path::new("/work/app/lib.rs").to_string_lossy()
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use fuzzy_ls::filter::{FilterChain, GitignoreFilter, HiddenFilter};
+use fuzzy_ls::search;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// How much of the start of a file is checked for NUL bytes to tell binary files apart.
+const BINARY_SNIFF_LENGTH: usize = 8192;
+
+/// A file referencing the file being renamed, and on how many of its lines.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Reference {
+    pub full_path: String,
+    pub lines: usize,
+}
+
+/// Returns the pattern of the references to a file: its name without the extension as a whole
+/// word, which also matches its name with the extension, e.g. `search` in `mod search;` or
+/// `"./search.js"`.
+fn reference_pattern(name: &str) -> Result<Regex, String> {
+    let stem = Path::new(name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .filter(|stem| !stem.is_empty())
+        .ok_or(format!("{} is not the name of a file.", name))?;
+    Regex::new(&format!(r"\b{}\b", regex::escape(&stem))).map_err(|error| error.to_string())
+}
+
+/// Counts the lines of a file that match the pattern, skipping binary and unreadable files.
+fn count_references(pattern: &Regex, full_path: &str) -> usize {
+    let Ok(contents) = fs::read(full_path) else {
+        return 0;
+    };
+    if contents[..contents.len().min(BINARY_SNIFF_LENGTH)].contains(&0) {
+        return 0;
+    }
+    String::from_utf8_lossy(&contents)
+        .lines()
+        .filter(|line| pattern.is_match(line))
+        .count()
+}
+
+/// Finds the candidates whose contents reference a file, grouped by their directory.
+///
+/// # Arguments
+///
+/// * `name` - The name or path of the file being renamed, e.g. `search.rs` or `src/search.rs`.
+/// * `candidates` - The file names and full paths of the files to look through.
+///
+/// # Returns
+///
+/// The files referencing the file by directory, with the directories and their files in order.
+/// The file itself is left out.
+pub fn find_references(
+    name: &str,
+    candidates: &[(String, String)],
+) -> Result<BTreeMap<String, Vec<Reference>>, String> {
+    let pattern = reference_pattern(name)?;
+    let mut references: BTreeMap<String, Vec<Reference>> = BTreeMap::new();
+    for (_, full_path) in candidates {
+        let path = Path::new(full_path);
+        if path.ends_with(name) {
+            continue;
+        }
+        let lines = count_references(&pattern, full_path);
+        if lines > 0 {
+            let directory = path.parent().map_or(String::new(), |parent| {
+                parent.to_string_lossy().into_owned()
+            });
+            references.entry(directory).or_default().push(Reference {
+                full_path: full_path.clone(),
+                lines,
+            });
+        }
+    }
+    for files in references.values_mut() {
+        files.sort_by(|file, other| file.full_path.cmp(&other.full_path));
+    }
+    Ok(references)
+}
+
+/// Lists the files under the current directory that reference a file, grouped by directory, to
+/// estimate how many files a rename touches. Nothing is renamed.
+///
+/// # Arguments
+///
+/// * `name` - The name or path of the file being renamed.
+/// * `no_ignore` - Also look through the files ignored by `.gitignore` files.
+/// * `hidden` - Also look through hidden files and directories.
+pub fn run(name: &str, no_ignore: bool, hidden: bool) -> Result<(), String> {
+    let mut filters = FilterChain::new().with(HiddenFilter { hidden });
+    if !no_ignore {
+        filters = filters.with(GitignoreFilter::new(Path::new("."), None));
+    }
+    let candidates = search::walk_directory(Path::new("."), &filters);
+    let references = find_references(name, &candidates)?;
+    let files: usize = references.values().map(Vec::len).sum();
+    for (directory, files) in &references {
+        println!("{}/ ({})", directory, files.len());
+        for reference in files {
+            println!(
+                "  {} ({})",
+                reference.full_path,
+                plural(reference.lines, "line")
+            );
+        }
+    }
+    println!(
+        "{} in {} reference {}.",
+        plural(files, "file"),
+        plural(references.len(), "directory"),
+        name
+    );
+    Ok(())
+}
+
+/// Formats a count with a noun, e.g. `1 line` or `2 lines`.
+fn plural(count: usize, noun: &str) -> String {
+    match (count, noun.strip_suffix('y')) {
+        (1, _) => format!("{} {}", count, noun),
+        (_, Some(stem)) => format!("{} {}ies", count, stem),
+        (_, None) => format!("{} {}s", count, noun),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_references() {
+        let root = std::env::temp_dir().join(format!("ffs-refs-{}", std::process::id()));
+        let files = [
+            ("src/search.rs", "pub fn search() {}\n"),
+            (
+                "src/main.rs",
+                "mod search;\nuse search::search;\nfn main() {}\n",
+            ),
+            (
+                "src/research.rs",
+                "// Not a reference to research_search.\n",
+            ),
+            ("docs/guide.md", "See `src/search.rs`.\n"),
+            ("static/logo.bin", "search\0"),
+        ];
+        for (path, contents) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        let candidates = search::walk_directory(&root, &FilterChain::new());
+        let references = find_references("src/search.rs", &candidates).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        let found: Vec<(String, usize)> = references
+            .values()
+            .flatten()
+            .map(|reference| {
+                let relative = Path::new(&reference.full_path).strip_prefix(&root).unwrap();
+                (
+                    relative.to_string_lossy().replace('\\', "/"),
+                    reference.lines,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("docs/guide.md".to_string(), 1),
+                ("src/main.rs".to_string(), 2)
+            ]
+        );
+        assert!(find_references("", &candidates).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use fuzzy_ls::filter::FilterChain;
+use fuzzy_ls::search::candidate_name_of_path;
+use fuzzy_ls::source::{accepts_file, CandidateSource};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// A machine-readable record of a run, written by `--report` for bug reports and benchmarks.
+pub struct RunReport {
+    options: Value,
+    timings: Vec<(&'static str, Duration)>,
+    counts: Vec<(&'static str, usize)>,
+    warnings: Vec<String>,
+    started: Instant,
+}
+
+impl RunReport {
+    /// Starts a report of a run with the given options.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The options of the run.
+    /// * `started` - When the run started, which the total time is measured from.
+    pub fn new(options: Value, started: Instant) -> RunReport {
+        RunReport {
+            options,
+            timings: Vec::new(),
+            counts: Vec::new(),
+            warnings: Vec::new(),
+            started,
+        }
+    }
+
+    /// Records that a phase of the run started at `start` just finished.
+    pub fn time(&mut self, phase: &'static str, start: Instant) {
+        self.timings.push((phase, start.elapsed()));
+    }
+
+    /// Records how long a phase of the run took, for phases measured in several parts.
+    pub fn record(&mut self, phase: &'static str, duration: Duration) {
+        self.timings.push((phase, duration));
+    }
+
+    /// Records the number of items at a stage of the run.
+    pub fn count(&mut self, stage: &'static str, count: usize) {
+        self.counts.push((stage, count));
+    }
+
+    /// Records warnings, such as entries that could not be read.
+    pub fn warn(&mut self, warnings: impl IntoIterator<Item = String>) {
+        self.warnings.extend(warnings);
+    }
+
+    /// Writes the report as JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file the report is written to.
+    /// * `potential_hits` - The results of the run, in the order they are shown.
+    /// * `source_labels` - When provided, the sources each result was found in, keyed by full path.
+    pub fn write(
+        &self,
+        path: &str,
+        potential_hits: &[(u32, String, String)],
+        source_labels: Option<&BTreeMap<String, String>>,
+    ) -> Result<(), String> {
+        let mut timings: Map<String, Value> = self
+            .timings
+            .iter()
+            .map(|(phase, duration)| (phase.to_string(), milliseconds(*duration)))
+            .collect();
+        timings.insert("total".to_string(), milliseconds(self.started.elapsed()));
+        let counts: Map<String, Value> = self
+            .counts
+            .iter()
+            .map(|(stage, count)| (stage.to_string(), json!(count)))
+            .collect();
+        let results: Vec<Value> = potential_hits
+            .iter()
+            .map(|(score, name, full_path)| {
+                let mut result = json!({ "score": score, "name": name, "path": full_path });
+                if let Some(label) = source_labels.and_then(|labels| labels.get(full_path)) {
+                    result["sources"] = json!(label.split(' ').collect::<Vec<&str>>());
+                }
+                result
+            })
+            .collect();
+        let report = json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "options": self.options,
+            "timings_ms": timings,
+            "counts": counts,
+            "warnings": self.warnings,
+            "results": results,
+        });
+        let contents = serde_json::to_string_pretty(&report).map_err(|error| error.to_string())?;
+        std::fs::write(path, contents + "\n")
+            .map_err(|error| format!("Failed to write the report to {}: {}", path, error))
+    }
+
+    /// Prints how long each phase of the run took so far to stderr, for `--timings`.
+    pub fn print_timings(&self) {
+        let total = self.started.elapsed();
+        eprintln!("Timings:");
+        for (phase, duration) in self.timings.iter().chain([&("total", total)]) {
+            eprintln!(
+                "  {:<10}{:>10.2} ms",
+                phase,
+                duration.as_secs_f64() * 1000.0
+            );
+        }
+    }
+}
+
+fn milliseconds(duration: Duration) -> Value {
+    json!(duration.as_secs_f64() * 1000.0)
+}
+
+/// Candidates from the results of a report written by `--report`, so the results of a large scan
+/// can be searched again with other queries without walking the disk.
+pub struct ReportSource<'a> {
+    /// The report file.
+    pub path: String,
+    pub filters: &'a FilterChain,
+}
+
+impl ReportSource<'_> {
+    /// Reads the paths of the results of a report, in the order they were shown.
+    fn result_paths(contents: &str) -> Result<Vec<String>, String> {
+        let report: Value = serde_json::from_str(contents).map_err(|error| error.to_string())?;
+        let results = report["results"]
+            .as_array()
+            .ok_or("It has no results.".to_string())?;
+        results
+            .iter()
+            .map(|result| {
+                result["path"]
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or("A result has no path.".to_string())
+            })
+            .collect()
+    }
+}
+
+impl CandidateSource for ReportSource<'_> {
+    fn candidates(&mut self) -> Result<Vec<(String, String)>, String> {
+        let contents = std::fs::read_to_string(&self.path)
+            .map_err(|error| format!("Failed to read the report {}: {}", self.path, error))?;
+        let paths = ReportSource::result_paths(&contents)
+            .map_err(|error| format!("{} is not a report: {}", self.path, error))?;
+        Ok(paths
+            .into_iter()
+            .filter(|full_path| accepts_file(self.filters, full_path))
+            .map(|full_path| (candidate_name_of_path(&full_path), full_path))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_source() {
+        let path = std::env::temp_dir().join(format!("ffs-report-{}.json", std::process::id()));
+        let report = RunReport::new(json!({ "query": "main" }), Instant::now());
+        let hits = vec![
+            (0, "main".to_string(), "./src/main.rs".to_string()),
+            (2, "mains".to_string(), "./docs/mains.md".to_string()),
+        ];
+        report.write(&path.to_string_lossy(), &hits, None).unwrap();
+        let filters = FilterChain::new();
+        let mut source = ReportSource {
+            path: path.to_string_lossy().into_owned(),
+            filters: &filters,
+        };
+        let candidates = source.candidates();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            candidates,
+            Ok(vec![
+                ("main".to_string(), "./src/main.rs".to_string()),
+                ("mains".to_string(), "./docs/mains.md".to_string()),
+            ])
+        );
+        assert_eq!(
+            ReportSource::result_paths("{\"results\": [{\"name\": \"main\"}]}"),
+            Err("A result has no path.".to_string())
+        );
+    }
+}
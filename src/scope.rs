@@ -0,0 +1,87 @@
+use crate::storage;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Named directories to search, from the `scopes` file in the config directory, so `@notes meeting`
+/// searches `~/notes` wherever it is run from.
+///
+/// Each line of the file names a scope and its directory, e.g. `notes = ~/notes`, where a leading
+/// `~` stands for the home directory. Lines starting with `#` are comments.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Scopes {
+    directories: BTreeMap<String, PathBuf>,
+}
+
+impl Scopes {
+    /// Reads the scopes file of the config directory. Without one, there are no scopes.
+    pub fn load() -> Result<Scopes, String> {
+        let path = storage::config_dir()?.join("scopes");
+        match fs::read_to_string(&path) {
+            Ok(contents) => Scopes::parse(&contents, storage::home_dir().as_ref())
+                .map_err(|error| format!("{}: {}", path.display(), error)),
+            Err(_) => Ok(Scopes::default()),
+        }
+    }
+
+    fn parse(contents: &str, home: Option<&PathBuf>) -> Result<Scopes, String> {
+        let mut directories = BTreeMap::new();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, directory) = line
+                .split_once('=')
+                .ok_or(format!("The scope {} has no directory.", line))?;
+            let (name, directory) = (name.trim(), directory.trim());
+            if name.is_empty() || directory.is_empty() {
+                return Err(format!("The scope {} has no name or directory.", line));
+            }
+            let directory = match (directory.strip_prefix('~'), home) {
+                (Some(rest), Some(home)) => home.join(rest.trim_start_matches(['/', '\\'])),
+                _ => PathBuf::from(directory),
+            };
+            directories.insert(name.to_string(), directory);
+        }
+        Ok(Scopes { directories })
+    }
+
+    /// Returns the directory of a scope, or an error if no scope has the name.
+    pub fn directory(&self, name: &str) -> Result<PathBuf, String> {
+        self.directories.get(name).cloned().ok_or(format!(
+            "There is no scope named {}. Scopes are defined in the scopes file of the config directory.",
+            name
+        ))
+    }
+
+    /// The names of the scopes, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.directories.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scopes() {
+        let home = PathBuf::from("/home/me");
+        let scopes = Scopes::parse(
+            "# projects\nwork = ~/src/work\n\nnotes=/srv/notes\n",
+            Some(&home),
+        )
+        .unwrap();
+        assert_eq!(scopes.names().collect::<Vec<_>>(), ["notes", "work"]);
+        assert_eq!(
+            scopes.directory("work"),
+            Ok(PathBuf::from("/home/me/src/work"))
+        );
+        assert_eq!(scopes.directory("notes"), Ok(PathBuf::from("/srv/notes")));
+        assert!(scopes.directory("music").is_err());
+        assert_eq!(
+            Scopes::parse("work ~/src/work\n", None),
+            Err("The scope work ~/src/work has no directory.".to_string())
+        );
+    }
+}
@@ -1,86 +1,2115 @@
-use std::collections::BTreeSet;
+use crate::arena::{PathArena, PathId};
+use crate::filter::{CandidateEntry, CandidateFilter, FfsignoreFilter, FilterChain};
+use crate::throttle::IoThrottle;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BinaryHeap, HashMap};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
 use walkdir::WalkDir;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FuzzySearchAlgorithm {
-    LEVENSHTEIN,
+    Levenshtein,
     DamerauLevenshtein,
-    BITAP,
+    Bitap,
     JaroWinkler,
+    Subsequence,
+    Tokens,
+    SmithWaterman,
+    Trigram,
+}
+
+impl FuzzySearchAlgorithm {
+    /// Every built-in algorithm, in the order they are listed in help texts.
+    pub const ALL: [FuzzySearchAlgorithm; 8] = [
+        FuzzySearchAlgorithm::DamerauLevenshtein,
+        FuzzySearchAlgorithm::Levenshtein,
+        FuzzySearchAlgorithm::Bitap,
+        FuzzySearchAlgorithm::JaroWinkler,
+        FuzzySearchAlgorithm::Subsequence,
+        FuzzySearchAlgorithm::Tokens,
+        FuzzySearchAlgorithm::SmithWaterman,
+        FuzzySearchAlgorithm::Trigram,
+    ];
+
+    /// The name the algorithm is selected by, e.g. `damerau-levenshtein`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FuzzySearchAlgorithm::Levenshtein => "levenshtein",
+            FuzzySearchAlgorithm::DamerauLevenshtein => "damerau-levenshtein",
+            FuzzySearchAlgorithm::Bitap => "bitap",
+            FuzzySearchAlgorithm::JaroWinkler => "jaro-winkler",
+            FuzzySearchAlgorithm::Subsequence => "subsequence",
+            FuzzySearchAlgorithm::Tokens => "tokens",
+            FuzzySearchAlgorithm::SmithWaterman => "smith-waterman",
+            FuzzySearchAlgorithm::Trigram => "trigram",
+        }
+    }
+}
+
+/// A scorer selected on the command line: a built-in algorithm or `plugin:<name>` for a scorer plugin.
+#[derive(Debug, Clone)]
+pub enum AlgorithmChoice {
+    Builtin(FuzzySearchAlgorithm),
+    Plugin(String),
+}
+
+impl FromStr for AlgorithmChoice {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Some(name) = value.strip_prefix("plugin:") {
+            if name.is_empty() {
+                return Err("A plugin name is required, e.g. plugin:my_scorer".to_string());
+            }
+            return Ok(AlgorithmChoice::Plugin(name.to_string()));
+        }
+        FuzzySearchAlgorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == value)
+            .map(AlgorithmChoice::Builtin)
+            .ok_or_else(|| {
+                let names: Vec<&str> = FuzzySearchAlgorithm::ALL
+                    .iter()
+                    .map(FuzzySearchAlgorithm::name)
+                    .collect();
+                format!(
+                    "Unknown algorithm {}. Expected one of {} or plugin:<name>",
+                    value,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for AlgorithmChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlgorithmChoice::Builtin(algorithm) => write!(f, "{}", algorithm.name()),
+            AlgorithmChoice::Plugin(name) => write!(f, "plugin:{}", name),
+        }
+    }
+}
+
+/// How a query matches names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Names equal to the query.
+    Exact,
+    /// Names containing the query.
+    Contains,
+    /// Names within the fuzzy threshold of the query.
+    #[default]
+    Fuzzy,
+    /// Names the query matches as a regex.
+    Regex,
+    /// Names whose words sound like those of the query.
+    Phonetic,
+}
+
+impl SearchMode {
+    /// The name of the mode, e.g. `exact`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SearchMode::Exact => "exact",
+            SearchMode::Contains => "contains",
+            SearchMode::Fuzzy => "fuzzy",
+            SearchMode::Regex => "regex",
+            SearchMode::Phonetic => "phonetic",
+        }
+    }
+
+    /// Returns the mode after this one, in the order the results UI switches between them.
+    pub fn next(&self) -> SearchMode {
+        match self {
+            SearchMode::Exact => SearchMode::Contains,
+            SearchMode::Contains => SearchMode::Fuzzy,
+            SearchMode::Fuzzy => SearchMode::Regex,
+            SearchMode::Regex => SearchMode::Phonetic,
+            SearchMode::Phonetic => SearchMode::Exact,
+        }
+    }
+}
+
+/// Scores file names against a query. Lower scores are better and `0` denotes an exact match.
+///
+/// Scorers are shared by the threads of [`rank_files`], so they must be `Sync`.
+pub trait ScoreFunction: Sync {
+    fn score(&self, query: &str, file_name: &str) -> Result<u32, String>;
+
+    /// Returns the maximum score accepted for a query. Defaults to [`fuzzy_threshold`], which suits
+    /// scores that count edits.
+    fn threshold(&self, query: &str) -> u32 {
+        fuzzy_threshold(query)
+    }
+
+    /// Returns how similar a name is to the query given its score, from `0` to `100` for an exact
+    /// match, so scores compare across query lengths and algorithms. Defaults to
+    /// [`edit_similarity`], which suits scores that count edits.
+    fn similarity(&self, query: &str, file_name: &str, score: u32) -> u32 {
+        edit_similarity(query, file_name, score)
+    }
+
+    /// Returns whether ranking lower cases the query and the names before scoring them, so `foo`
+    /// matches `Foo.rs` exactly. Scorers that compare words ignoring case themselves return
+    /// `false`, since lower casing would hide the camelCase words of names.
+    fn normalizes_case(&self) -> bool {
+        true
+    }
+
+    /// Returns whether [`rank_files`] may score names on several threads at once. Scorers calling
+    /// into code that may not be thread-safe, like plugins, return `false`.
+    fn parallel(&self) -> bool {
+        true
+    }
+}
+
+impl ScoreFunction for FuzzySearchAlgorithm {
+    fn score(&self, query: &str, file_name: &str) -> Result<u32, String> {
+        score_fuzzy_search(query, file_name, *self)
+    }
+
+    fn threshold(&self, query: &str) -> u32 {
+        match self {
+            FuzzySearchAlgorithm::JaroWinkler => jaro_winkler_threshold(query),
+            // Every name containing the query as a subsequence is a match.
+            FuzzySearchAlgorithm::Subsequence => NOT_A_SUBSEQUENCE - 1,
+            FuzzySearchAlgorithm::Tokens => tokens_threshold(query),
+            FuzzySearchAlgorithm::SmithWaterman => SmithWatermanScorer::default().threshold(query),
+            FuzzySearchAlgorithm::Trigram => TRIGRAM_THRESHOLD,
+            _ => fuzzy_threshold(query),
+        }
+    }
+
+    fn similarity(&self, query: &str, file_name: &str, score: u32) -> u32 {
+        match self {
+            // Both already score in hundredths of dissimilarity.
+            FuzzySearchAlgorithm::JaroWinkler | FuzzySearchAlgorithm::Trigram => {
+                share_similarity(score, 100)
+            }
+            FuzzySearchAlgorithm::Tokens => {
+                share_similarity(score, query_tokens(query).len() as u32 * TOKEN_MISS)
+            }
+            FuzzySearchAlgorithm::SmithWaterman => {
+                SmithWatermanScorer::default().similarity(query, file_name, score)
+            }
+            // Bitap finds the query anywhere in a name, so only its characters can be edited.
+            FuzzySearchAlgorithm::Bitap => share_similarity(score, query.chars().count() as u32),
+            _ => edit_similarity(query, file_name, score),
+        }
+    }
+
+    fn normalizes_case(&self) -> bool {
+        !matches!(
+            self,
+            FuzzySearchAlgorithm::Subsequence
+                | FuzzySearchAlgorithm::Tokens
+                | FuzzySearchAlgorithm::SmithWaterman
+                | FuzzySearchAlgorithm::Trigram
+        )
+    }
+}
+
+/// Returns the name a file is matched by: its file name without the final extension, like
+/// [`Path::file_stem`]. Dotfiles like `.gitignore` and names without a dot like `Makefile` have no
+/// extension, so they are matched by their whole name.
+///
+/// # Arguments
+///
+/// * `file_name` - The last component of the file's path.
+pub fn candidate_name(file_name: &str) -> String {
+    name_of_file(file_name).to_string()
+}
+
+/// Like [`candidate_name`], borrowing the name from the file name.
+fn name_of_file(file_name: &str) -> &str {
+    Path::new(file_name)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(file_name)
+}
+
+/// Returns the name a path is matched by: the [`candidate_name`] of its last component, or the
+/// whole last component of a directory's path, which ends in a separator.
+pub fn candidate_name_of_path(path: &str) -> String {
+    let file_name = Path::new(path)
+        .file_name()
+        .map_or(path.to_string(), |name| name.to_string_lossy().to_string());
+    if is_directory_path(path) {
+        file_name
+    } else {
+        candidate_name(&file_name)
+    }
+}
+
+/// The Bitap scorer with a configurable number of allowed errors.
+#[derive(Debug, Clone, Copy)]
+pub struct BitapScorer {
+    /// The most insertions, deletions and substitutions a match may need.
+    pub max_errors: u32,
+}
+
+impl ScoreFunction for BitapScorer {
+    fn score(&self, query: &str, file_name: &str) -> Result<u32, String> {
+        bitap_errors(query, file_name, self.max_errors)
+    }
+
+    fn threshold(&self, _query: &str) -> u32 {
+        self.max_errors
+    }
+
+    fn similarity(&self, query: &str, _file_name: &str, score: u32) -> u32 {
+        share_similarity(score, query.chars().count() as u32)
+    }
+}
+
+/// The Smith-Waterman scorer with configurable penalties.
+#[derive(Debug, Clone, Copy)]
+pub struct SmithWatermanScorer {
+    /// What aligning two different characters costs.
+    pub mismatch_penalty: u32,
+    /// What skipping a character of the query or the name costs.
+    pub gap_penalty: u32,
+}
+
+impl Default for SmithWatermanScorer {
+    fn default() -> Self {
+        SmithWatermanScorer {
+            mismatch_penalty: SMITH_WATERMAN_MISMATCH,
+            gap_penalty: SMITH_WATERMAN_GAP,
+        }
+    }
+}
+
+impl ScoreFunction for SmithWatermanScorer {
+    fn score(&self, query: &str, file_name: &str) -> Result<u32, String> {
+        Ok(smith_waterman_score(
+            query,
+            file_name,
+            self.mismatch_penalty,
+            self.gap_penalty,
+        ))
+    }
+
+    /// Accepts alignments falling short by as many matches as [`fuzzy_threshold`] allows edits.
+    fn threshold(&self, query: &str) -> u32 {
+        fuzzy_threshold(query) * SMITH_WATERMAN_MATCH
+    }
+
+    /// Returns the share of the query's characters the best alignment matched.
+    fn similarity(&self, query: &str, _file_name: &str, score: u32) -> u32 {
+        share_similarity(score, query.chars().count() as u32 * SMITH_WATERMAN_MATCH)
+    }
+
+    fn normalizes_case(&self) -> bool {
+        false
+    }
+}
+
+/// Scores with another scorer without lower casing the query and the names first, for
+/// `--case-sensitive`.
+pub struct CaseSensitive(pub Box<dyn ScoreFunction>);
+
+impl ScoreFunction for CaseSensitive {
+    fn score(&self, query: &str, file_name: &str) -> Result<u32, String> {
+        self.0.score(query, file_name)
+    }
+
+    fn threshold(&self, query: &str) -> u32 {
+        self.0.threshold(query)
+    }
+
+    fn similarity(&self, query: &str, file_name: &str, score: u32) -> u32 {
+        self.0.similarity(query, file_name, score)
+    }
+
+    fn normalizes_case(&self) -> bool {
+        false
+    }
+
+    fn parallel(&self) -> bool {
+        self.0.parallel()
+    }
+}
+
+/// The maximum score accepted for every query, in place of the threshold of a scorer, for
+/// `--threshold` and `--max-distance-ratio`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Threshold {
+    /// The same maximum score for every query.
+    Absolute(u32),
+    /// A share of the query length, rounded up, e.g. `0.3` accepts 3 edits for a query of 10
+    /// characters.
+    Ratio(f64),
+}
+
+impl Threshold {
+    /// Returns the maximum score accepted for a query.
+    pub fn of(&self, query: &str) -> u32 {
+        match self {
+            Threshold::Absolute(threshold) => *threshold,
+            Threshold::Ratio(ratio) => (query.chars().count() as f64 * ratio).ceil() as u32,
+        }
+    }
+}
+
+/// Scores with another scorer, accepting the scores within a [`Threshold`] instead of its own.
+pub struct WithThreshold(pub Box<dyn ScoreFunction>, pub Threshold);
+
+impl ScoreFunction for WithThreshold {
+    fn score(&self, query: &str, file_name: &str) -> Result<u32, String> {
+        self.0.score(query, file_name)
+    }
+
+    fn threshold(&self, query: &str) -> u32 {
+        self.1.of(query)
+    }
+
+    fn similarity(&self, query: &str, file_name: &str, score: u32) -> u32 {
+        self.0.similarity(query, file_name, score)
+    }
+
+    fn normalizes_case(&self) -> bool {
+        self.0.normalizes_case()
+    }
+
+    fn parallel(&self) -> bool {
+        self.0.parallel()
+    }
+}
+
+/// What matching the last segment of a [`FullPath`] query in a directory instead of the file name
+/// costs, so files whose names match rank first.
+const DIRECTORY_MATCH_PENALTY: u32 = 1;
+
+/// Scores the relative paths of files with another scorer, for `--full-path`, so `handlers/user`
+/// finds `api/handlers/user.rs`.
+///
+/// Separators are strong word boundaries: the segments of the query, split at `/` or `\`, are
+/// each scored against a different segment of the path, in order, and no match crosses a
+/// separator. Each segment has to be within the threshold of the other scorer, and the score is the
+/// sum of their scores. Path segments no query segment matches cost nothing. Like names, the last
+/// segments of the query and the path are compared without their extensions.
+pub struct FullPath(pub Box<dyn ScoreFunction>);
+
+/// Splits a path or a path-like query into its segments, leaving out empty ones and the final
+/// extension.
+fn path_segments(path: &str) -> Vec<&str> {
+    let mut segments: Vec<&str> = path
+        .split(['/', '\\'])
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect();
+    if let Some(last) = segments.last_mut().filter(|_| !is_directory_path(path)) {
+        *last = name_of_file(last);
+    }
+    segments
+}
+
+impl ScoreFunction for FullPath {
+    fn score(&self, query: &str, file_name: &str) -> Result<u32, String> {
+        let query = path_segments(query);
+        let path = path_segments(file_name);
+        if query.is_empty() {
+            return Ok(0);
+        }
+        // `best[j]` is the best score of the query segments so far, the last of them matched by
+        // `path[j - 1]` or a segment before it.
+        let mut best = vec![Some(0u32); path.len() + 1];
+        for (index, segment) in query.iter().enumerate() {
+            let threshold = self.0.threshold(segment);
+            let last = index + 1 == query.len();
+            let mut current: Vec<Option<u32>> = vec![None; path.len() + 1];
+            for (j, path_segment) in path.iter().enumerate() {
+                let matched = match best[j] {
+                    Some(before) => {
+                        let score = self.0.score(segment, path_segment)?;
+                        let penalty = if last && j + 1 < path.len() {
+                            DIRECTORY_MATCH_PENALTY
+                        } else {
+                            0
+                        };
+                        (score <= threshold).then(|| before.saturating_add(score + penalty))
+                    }
+                    None => None,
+                };
+                current[j + 1] = match (current[j], matched) {
+                    (Some(skipped), Some(matched)) => Some(skipped.min(matched)),
+                    (skipped, matched) => skipped.or(matched),
+                };
+            }
+            best = current;
+        }
+        Ok(best[path.len()].unwrap_or(u32::MAX))
+    }
+
+    fn threshold(&self, query: &str) -> u32 {
+        path_segments(query)
+            .iter()
+            .map(|segment| self.0.threshold(segment))
+            .fold(DIRECTORY_MATCH_PENALTY, u32::saturating_add)
+            .min(u32::MAX - 1)
+    }
+
+    /// Rates the score against the query alone, since path segments it doesn't match cost nothing.
+    fn similarity(&self, query: &str, _file_name: &str, score: u32) -> u32 {
+        self.0.similarity(query, query, score)
+    }
+
+    fn normalizes_case(&self) -> bool {
+        self.0.normalizes_case()
+    }
+
+    fn parallel(&self) -> bool {
+        self.0.parallel()
+    }
+}
+
+/// Returns whether a query has upper case letters, for smart case matching: such queries match
+/// case-sensitively, while lower case ones ignore case.
+///
+/// # Arguments
+///
+/// * `query` - The search query string.
+/// * `is_regex` - Whether the query is a regex, whose escapes like `\W` and `\p{Lu}` aren't
+///   letters it matches.
+pub fn has_upper_case(query: &str, is_regex: bool) -> bool {
+    let mut chars = query.chars();
+    while let Some(character) = chars.next() {
+        if is_regex && character == '\\' {
+            // Unicode classes are named by a letter, like `\pL`, or in braces, like `\p{Lu}`.
+            if matches!(chars.next(), Some('p' | 'P')) && chars.next() == Some('{') {
+                chars.by_ref().find(|character| *character == '}');
+            }
+            continue;
+        }
+        if character.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+/// Returns text composed to Unicode NFC, borrowing it when it already is, so a name with
+/// decomposed accents, as macOS stores them, compares equal to the same name typed as a query.
+pub fn compose(text: &str) -> Cow<'_, str> {
+    if is_nfc_quick(text.chars()) == IsNormalized::Yes {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(text.nfc().collect())
+    }
+}
+
+/// Returns a name the way it is compared with a query: composed to NFC, and lower cased unless
+/// matching case-sensitively. `buffer` holds the name when it had to change.
+pub fn normalize_name<'a>(name: &'a str, case_sensitive: bool, buffer: &'a mut String) -> &'a str {
+    let composed = is_nfc_quick(name.chars()) == IsNormalized::Yes;
+    if composed && case_sensitive {
+        return name;
+    }
+    buffer.clear();
+    match (composed, case_sensitive) {
+        (true, _) => buffer.extend(name.chars().flat_map(char::to_lowercase)),
+        (false, true) => buffer.extend(name.nfc()),
+        (false, false) => buffer.extend(name.nfc().flat_map(char::to_lowercase)),
+    }
+    buffer
+}
+
+/// Rewrites both `\` and `/` in a query to the separator of the current platform, so paths copied
+/// from Windows work as queries on Linux and the other way around.
+pub fn normalize_separators(query: &str) -> String {
+    query.replace(['\\', '/'], std::path::MAIN_SEPARATOR_STR)
+}
+
+/// Returns a query that looks like a path, with its separators normalized, if it names an existing
+/// file or directory.
+pub fn existing_path(query: &str) -> Option<String> {
+    if !query.contains(['\\', '/']) {
+        return None;
+    }
+    let path = normalize_separators(query);
+    Path::new(&path).exists().then_some(path)
+}
+
+/// Returns whether a query is an absolute path on any platform, e.g. `/home/me/main.rs`,
+/// `C:\Users\me\main.rs` or `\\server\share\main.rs`.
+pub fn is_absolute_query(query: &str) -> bool {
+    let mut chars = query.chars();
+    let drive_letter = matches!(
+        (chars.next(), chars.next(), chars.next()),
+        (Some(letter), Some(':'), Some('\\' | '/')) if letter.is_ascii_alphabetic()
+    );
+    drive_letter || query.starts_with(['\\', '/'])
+}
+
+/// Splits a query that looks like a path into the name to match and the directories containing it.
+///
+/// # Arguments
+///
+/// * `query` - The search query string, with either kind of separator.
+///
+/// # Returns
+///
+/// The [`candidate_name`] of the last component, or the whole query if it has no separators, and
+/// the directories before it, if any.
+pub fn split_path_query(query: &str) -> (String, Option<PathBuf>) {
+    let query = normalize_separators(query);
+    let Some((directories, file_name)) = query.rsplit_once(std::path::MAIN_SEPARATOR) else {
+        return (query, None);
+    };
+    let name = name_of_file(file_name).to_string();
+    let directories = (!directories.is_empty()).then(|| PathBuf::from(directories));
+    (name, directories)
+}
+
+/// Splits a query that is a Rust path, e.g. `search::walk` or `crate::gui::draw_tabs`, into the
+/// names of its segments, leaving out a leading `crate`.
+///
+/// # Returns
+///
+/// The segments, or `None` if the query isn't a Rust path.
+pub fn split_module_query(query: &str) -> Option<Vec<&str>> {
+    if !query.contains("::") {
+        return None;
+    }
+    let query = query.strip_prefix("crate::").unwrap_or(query);
+    let segments: Vec<&str> = query.split("::").collect();
+    let identifier = |segment: &&str| {
+        segment.starts_with(|c: char| c.is_alphabetic() || c == '_')
+            && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+    };
+    segments.iter().all(identifier).then_some(segments)
+}
+
+/// Returns the Rust module path of a source file, relative to the last `src` directory in its path,
+/// e.g. `["search", "walk"]` for `./src/search/walk.rs` or `./src/search/walk/mod.rs`. Crate roots
+/// like `src/lib.rs` have an empty module path.
+///
+/// # Returns
+///
+/// The module names, or `None` for files that aren't Rust sources below a `src` directory.
+pub fn module_path_of_file(full_path: &str) -> Option<Vec<&str>> {
+    let components: Vec<&str> = full_path.split(['/', '\\']).collect();
+    let src = components
+        .iter()
+        .rposition(|component| *component == "src")?;
+    let mut modules = components[src + 1..].to_vec();
+    let file = modules.pop()?.strip_suffix(".rs")?;
+    if modules.is_empty() && (file == "lib" || file == "main") {
+        return Some(modules);
+    }
+    if file != "mod" {
+        modules.push(file);
+    }
+    Some(modules)
+}
+
+/// Scores a name containing the query by where the query starts in it and how many more characters
+/// it has, so `0` is an exact match and names starting with the query rank first among equally
+/// long ones.
+///
+/// # Arguments
+///
+/// * `query` - The substring to look for.
+/// * `file_name` - The name to look in.
+///
+/// # Returns
+///
+/// The score of the first occurrence, or `None` if the name doesn't contain the query.
+pub fn contains_score(query: &str, file_name: &str) -> Option<u32> {
+    let start = file_name.find(query)?;
+    let position = file_name[..start].chars().count();
+    let extra = file_name.chars().count() - query.chars().count();
+    Some((position + extra) as u32)
+}
+
+/// Returns the maximum fuzzy score accepted for a query, which grows with the query length.
+pub fn fuzzy_threshold(query: &str) -> u32 {
+    match query.len() {
+        0..=4 => (query.len() as f32 * 0.20).ceil() as u32,
+        5..=10 => (query.len() as f32 * 0.30).ceil() as u32,
+        _ => (query.len() as f32 * 0.40).ceil() as u32,
+    }
+}
+
+/// Returns how similar a name is to the query from its edit distance, from `0` to `100` for an
+/// exact match: the share of the characters of the longer of the two that needn't be edited, e.g.
+/// `75` for `mian` and `main`.
+pub fn edit_similarity(query: &str, file_name: &str, score: u32) -> u32 {
+    let length = query.chars().count().max(file_name.chars().count());
+    share_similarity(score, length as u32)
+}
+
+/// Returns the similarity of a score out of the score of a name sharing nothing with the query,
+/// from `0` to `100` for a score of `0`.
+fn share_similarity(score: u32, dissimilar: u32) -> u32 {
+    if dissimilar == 0 {
+        return if score == 0 { 100 } else { 0 };
+    }
+    100 - (u64::from(score.min(dissimilar)) * 100).div_ceil(u64::from(dissimilar)) as u32
+}
+
+/// Returns the maximum Jaro-Winkler score accepted for a query. Like [`fuzzy_threshold`], longer
+/// queries tolerate a larger share of differences: a similarity of 0.8 for up to 4 characters,
+/// 0.7 for up to 10 and 0.6 beyond.
+pub fn jaro_winkler_threshold(query: &str) -> u32 {
+    match query.chars().count() {
+        0..=4 => 20,
+        5..=10 => 30,
+        _ => 40,
+    }
+}
+
+/// How much worse a match on the initials of a name scores than the same match on the name itself.
+const ACRONYM_PENALTY: u32 = 1;
+
+/// Splits a name into its words, at `_`, `-`, `.` and other separators and at camelCase
+/// boundaries, e.g. `mainWindow_controller` into `main`, `Window` and `controller`.
+pub fn name_tokens(name: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut previous = None;
+    for character in name.chars() {
+        let starts = starts_word_after(previous, character);
+        previous = Some(character);
+        if !character.is_alphanumeric() {
+            continue;
+        }
+        match tokens.last_mut() {
+            Some(token) if !starts => token.push(character),
+            _ => tokens.push(character.to_string()),
+        }
+    }
+    tokens
+}
+
+/// Returns the lower case initials of the words of a name, e.g. `mwc` for
+/// `main_window_controller`, or `None` if the name has fewer than two words.
+pub fn initials(name: &str) -> Option<String> {
+    let mut initials = String::new();
+    initials_into(name, &mut initials).then_some(initials)
+}
+
+/// Writes the [`initials`] of a name into a buffer, returning whether the name has at least two
+/// words.
+fn initials_into(name: &str, initials: &mut String) -> bool {
+    initials.clear();
+    let mut words = 0;
+    let mut previous = None;
+    let mut in_word = false;
+    for character in name.chars() {
+        let starts = starts_word_after(previous, character);
+        previous = Some(character);
+        if !character.is_alphanumeric() {
+            continue;
+        }
+        if !in_word || starts {
+            words += 1;
+            initials.extend(character.to_lowercase());
+        }
+        in_word = true;
+    }
+    words >= 2
+}
+
+/// Ranks candidates against a query.
+///
+/// # Arguments
+///
+/// * `query` - The search query string.
+/// * `candidates` - Tuples of the name to match against and its payload, usually the full path.
+/// * `scorer` - The scoring function.
+///
+/// # Returns
+///
+/// The candidates within the fuzzy threshold as `(score, name, payload)` tuples, best first, or the scorer's error.
+/// Names of several words are also scored by their [`initials`], so `mwc` finds
+/// `main_window_controller`.
+pub fn rank_candidates<T>(
+    query: &str,
+    candidates: Vec<(String, T)>,
+    scorer: &dyn ScoreFunction,
+) -> Result<Vec<(u32, String, T)>, String> {
+    let mut ranked_files: Vec<(u32, String, T)> = Vec::new();
+    let mut candidate_scorer = CandidateScorer::new(query, scorer);
+    for (file_name, full_path) in candidates {
+        if let Some(score) = candidate_scorer.score(&file_name)? {
+            ranked_files.push((score, file_name, full_path));
+        }
+    }
+    ranked_files.sort_by_key(|hit| hit.0);
+    Ok(ranked_files)
+}
+
+/// Ranks the candidates of an arena against a query like [`rank_candidates`], without copying
+/// their names.
+///
+/// # Arguments
+///
+/// * `query` - The search query string.
+/// * `paths` - The arena holding the candidates.
+/// * `ids` - The candidates to rank.
+/// * `scorer` - The scoring function.
+///
+/// # Returns
+///
+/// The candidates within the threshold as `(score, id)` tuples, best first, or the scorer's error.
+pub fn rank_paths(
+    query: &str,
+    paths: &PathArena,
+    ids: Vec<PathId>,
+    scorer: &dyn ScoreFunction,
+) -> Result<Vec<(u32, PathId)>, String> {
+    let mut ranked_paths: Vec<(u32, PathId)> = Vec::new();
+    let mut candidate_scorer = CandidateScorer::new(query, scorer);
+    for id in ids {
+        if let Some(score) = candidate_scorer.score(paths.name(id))? {
+            ranked_paths.push((score, id));
+        }
+    }
+    ranked_paths.sort_by_key(|hit| hit.0);
+    Ok(ranked_paths)
+}
+
+/// The fewest candidates [`rank_files`] scores on each thread, so small searches aren't slowed
+/// down by handing work to other threads.
+pub const PARALLEL_CHUNK: usize = 4096;
+
+/// Ranks the candidates of an arena like [`rank_paths`], scoring chunks of them on the threads of
+/// the global rayon pool. The results are the same as those of [`rank_paths`], including the order
+/// of equal scores.
+///
+/// Searches of fewer than two [`PARALLEL_CHUNK`]s, scorers that aren't [`ScoreFunction::parallel`]
+/// and WebAssembly builds are ranked on the current thread.
+///
+/// # Arguments
+///
+/// * `query` - The search query string.
+/// * `paths` - The arena holding the candidates.
+/// * `ids` - The candidates to rank.
+/// * `scorer` - The scoring function.
+///
+/// # Returns
+///
+/// The candidates within the threshold as `(score, id)` tuples, best first, or the scorer's error.
+pub fn rank_files(
+    query: &str,
+    paths: &PathArena,
+    ids: Vec<PathId>,
+    scorer: &dyn ScoreFunction,
+) -> Result<Vec<(u32, PathId)>, String> {
+    rank_arena(query, paths, ids, scorer, PathArena::name)
+}
+
+/// Ranks the candidates of an arena like [`rank_files`], matching the query against their paths
+/// relative to the searched directory instead of their names, e.g. with a [`FullPath`] scorer.
+pub fn rank_full_paths(
+    query: &str,
+    paths: &PathArena,
+    ids: Vec<PathId>,
+    scorer: &dyn ScoreFunction,
+) -> Result<Vec<(u32, PathId)>, String> {
+    rank_arena(query, paths, ids, scorer, relative_path)
+}
+
+/// Returns the path of a candidate without a leading `./` or `.\`, as [`rank_full_paths`] matches
+/// it.
+pub fn relative_path_of(path: &str) -> &str {
+    path.strip_prefix("./")
+        .or_else(|| path.strip_prefix(".\\"))
+        .unwrap_or(path)
+}
+
+fn relative_path(paths: &PathArena, id: PathId) -> &str {
+    relative_path_of(paths.path(id))
+}
+
+/// Ranks the candidates of an arena by the text `text` returns for each of them, like
+/// [`rank_files`].
+fn rank_arena(
+    query: &str,
+    paths: &PathArena,
+    ids: Vec<PathId>,
+    scorer: &dyn ScoreFunction,
+    text: fn(&PathArena, PathId) -> &str,
+) -> Result<Vec<(u32, PathId)>, String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if ids.len() >= 2 * PARALLEL_CHUNK && scorer.parallel() && rayon::current_num_threads() > 1 {
+        use rayon::prelude::*;
+        let chunk_size = ids
+            .len()
+            .div_ceil(rayon::current_num_threads())
+            .max(PARALLEL_CHUNK);
+        // Chunks are collected in order, so the stable sort keeps equal scores in candidate order.
+        let chunks: Vec<Vec<(u32, PathId)>> = ids
+            .par_chunks(chunk_size)
+            .map(|chunk| {
+                let mut candidate_scorer = CandidateScorer::new(query, scorer);
+                let mut ranked_paths: Vec<(u32, PathId)> = Vec::new();
+                for id in chunk {
+                    if let Some(score) = candidate_scorer.score(text(paths, *id))? {
+                        ranked_paths.push((score, *id));
+                    }
+                }
+                Ok(ranked_paths)
+            })
+            .collect::<Result<_, String>>()?;
+        let mut ranked_paths: Vec<(u32, PathId)> = chunks.into_iter().flatten().collect();
+        ranked_paths.sort_by_key(|hit| hit.0);
+        return Ok(ranked_paths);
+    }
+    let mut ranked_paths: Vec<(u32, PathId)> = Vec::new();
+    let mut candidate_scorer = CandidateScorer::new(query, scorer);
+    for id in ids {
+        if let Some(score) = candidate_scorer.score(text(paths, id))? {
+            ranked_paths.push((score, id));
+        }
+    }
+    ranked_paths.sort_by_key(|hit| hit.0);
+    Ok(ranked_paths)
+}
+
+/// Scores candidate names against a query on behalf of [`rank_candidates`] and [`rank_paths`].
+struct CandidateScorer<'a> {
+    /// The query as it is scored, lower cased unless the scorer doesn't normalize case.
+    query: String,
+    lowercase_query: String,
+    case_sensitive: bool,
+    threshold: u32,
+    scorer: &'a dyn ScoreFunction,
+    initials: String,
+    name: String,
+}
+
+impl<'a> CandidateScorer<'a> {
+    fn new(query: &str, scorer: &'a dyn ScoreFunction) -> CandidateScorer<'a> {
+        let query = compose(query);
+        let lowercase_query = query.to_lowercase();
+        let case_sensitive = !scorer.normalizes_case();
+        let query = if case_sensitive {
+            query.into_owned()
+        } else {
+            lowercase_query.clone()
+        };
+        CandidateScorer {
+            threshold: scorer.threshold(&query),
+            query,
+            lowercase_query,
+            case_sensitive,
+            scorer,
+            initials: String::new(),
+            name: String::new(),
+        }
+    }
+
+    /// Returns the score of a name, or `None` if it's above the threshold.
+    fn score(&mut self, file_name: &str) -> Result<Option<u32>, String> {
+        let name = normalize_name(file_name, self.case_sensitive, &mut self.name);
+        let mut score = self.scorer.score(&self.query, name)?;
+        if initials_into(file_name, &mut self.initials) {
+            let acronym_score = self.scorer.score(&self.lowercase_query, &self.initials)?;
+            score = score.min(acronym_score.saturating_add(ACRONYM_PENALTY));
+        }
+        Ok((score <= self.threshold).then_some(score))
+    }
+}
+
+/// Keeps the best hits out of more candidates than can be held in memory at once.
+///
+/// Hits with equal scores are kept in the order they were pushed, like the stable sort of
+/// [`rank_candidates`].
+pub struct TopHits<T> {
+    capacity: usize,
+    pushed: usize,
+    /// The kept hits with the order they were pushed in; the worst one is on top.
+    heap: BinaryHeap<(u32, usize, HitPayload<T>)>,
+}
+
+/// The name and payload of a hit, which don't take part in the ordering of [`TopHits`].
+struct HitPayload<T>(String, T);
+
+impl<T> PartialEq for HitPayload<T> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for HitPayload<T> {}
+
+impl<T> PartialOrd for HitPayload<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for HitPayload<T> {
+    fn cmp(&self, _: &Self) -> std::cmp::Ordering {
+        std::cmp::Ordering::Equal
+    }
+}
+
+impl<T> TopHits<T> {
+    /// Creates an empty collection keeping at most `capacity` hits.
+    pub fn new(capacity: usize) -> TopHits<T> {
+        TopHits {
+            capacity,
+            pushed: 0,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    /// Offers a hit, which is kept if it is among the best `capacity` hits pushed so far.
+    pub fn push(&mut self, (score, name, payload): (u32, String, T)) {
+        self.heap
+            .push((score, self.pushed, HitPayload(name, payload)));
+        self.pushed += 1;
+        if self.heap.len() > self.capacity {
+            self.heap.pop();
+        }
+    }
+
+    /// The number of hits pushed so far, including the ones that weren't kept.
+    pub fn pushed(&self) -> usize {
+        self.pushed
+    }
+
+    /// Returns the kept hits as `(score, name, payload)` tuples, best first.
+    pub fn into_sorted_vec(self) -> Vec<(u32, String, T)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(score, _, HitPayload(name, payload))| (score, name, payload))
+            .collect()
+    }
+}
+
+/// Drops the directories to search that are repeated or inside another one of them, so walking
+/// each of the rest finds every file once. Directories are compared by their canonical path, so
+/// `.` and the current directory's absolute path are the same one.
+///
+/// # Arguments
+///
+/// * `roots` - The directories to search, in order of precedence.
+///
+/// # Returns
+///
+/// The directories to walk, as given and in the same order.
+pub fn distinct_roots(roots: &[PathBuf]) -> Vec<PathBuf> {
+    let canonical: Vec<PathBuf> = roots
+        .iter()
+        .map(|root| root.canonicalize().unwrap_or(root.clone()))
+        .collect();
+    roots
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| {
+            !canonical.iter().enumerate().any(|(other, path)| {
+                other != *index
+                    && canonical[*index].starts_with(path)
+                    && (canonical[*index] != *path || other < *index)
+            })
+        })
+        .map(|(_, root)| root.clone())
+        .collect()
+}
+
+/// Walks over the directory and returns a vector of tuples containing the file name and the full path.
+/// Entries rejected by the filters or matching a `.ffsignore` file are skipped, and rejected
+/// directories are not descended into.
+///
+/// # Arguments
+///
+/// * `root` - The directory to walk.
+/// * `filters` - The filters deciding which entries become candidates, e.g. an [`ExtensionFilter`](crate::filter::ExtensionFilter).
+///
+/// # Returns
+///
+/// A vector of tuples where each tuple contains the file name (without extension) and the full path.
+pub fn walk_directory(root: &Path, filters: &FilterChain) -> Vec<(String, String)> {
+    walk_directory_throttled(root, filters, None, &mut Vec::new())
+}
+
+/// Walks over the directory like [`walk_directory`], optionally pacing the file system operations
+/// with a throttle for network file systems.
+///
+/// # Arguments
+///
+/// * `root` - The directory to walk.
+/// * `filters` - The filters deciding which entries become candidates.
+/// * `throttle` - When provided, every step of the walk waits for its turn and failed reads back off.
+/// * `unreadable` - Receives a description of every entry that could not be read.
+///
+/// # Returns
+///
+/// A vector of tuples where each tuple contains the file name (without extension) and the full path.
+pub fn walk_directory_throttled(
+    root: &Path,
+    filters: &FilterChain,
+    throttle: Option<&IoThrottle>,
+    unreadable: &mut Vec<String>,
+) -> Vec<(String, String)> {
+    let mut files = Vec::new();
+    visit_directory(
+        root,
+        filters,
+        throttle,
+        unreadable,
+        &mut |file_name, full_path| files.push((file_name.to_string(), full_path.to_string())),
+    );
+    files
+}
+
+/// Walks over the directory like [`walk_directory_throttled`], passing every file to `visit` as it
+/// is found instead of collecting them.
+///
+/// # Arguments
+///
+/// * `root` - The directory to walk.
+/// * `filters` - The filters deciding which entries become candidates.
+/// * `throttle` - When provided, every step of the walk waits for its turn and failed reads back off.
+/// * `unreadable` - Receives a description of every entry that could not be read.
+/// * `visit` - Called with the file name (without extension) and the full path of every file.
+pub fn visit_directory(
+    root: &Path,
+    filters: &FilterChain,
+    throttle: Option<&IoThrottle>,
+    unreadable: &mut Vec<String>,
+    visit: &mut dyn FnMut(&str, &str),
+) {
+    let mut walk = DirectoryWalk::new(root, filters, throttle, WalkOptions::default());
+    for (file_name, full_path) in &mut walk {
+        visit(&file_name, &full_path);
+    }
+    unreadable.append(&mut walk.unreadable);
+}
+
+/// How a [`DirectoryWalk`] walks and which entries it yields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalkOptions {
+    /// Whether to walk the directories symbolic links point to.
+    pub follow_links: bool,
+    /// Whether files are yielded.
+    pub files: bool,
+    /// Whether the directories below the root are yielded, their full paths ending in a separator.
+    pub directories: bool,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        WalkOptions {
+            follow_links: false,
+            files: true,
+            directories: false,
+        }
+    }
+}
+
+/// Returns whether a full path is the one of a directory, which ends in a separator.
+pub fn is_directory_path(full_path: &str) -> bool {
+    full_path.ends_with(['/', '\\'])
+}
+
+/// A walk over a directory that yields its files one at a time as they are found, so callers can
+/// use the files found so far, e.g. to show results, before the walk is over.
+///
+/// Entries rejected by the filters or matching a [`FfsignoreFilter`] are skipped, and rejected
+/// directories are not descended into. Symbolic links to directories are only descended into when
+/// following links, and links leading back to a directory being walked are reported as unreadable
+/// instead of being walked again.
+pub struct DirectoryWalk<'a> {
+    entries: Box<dyn Iterator<Item = walkdir::Result<walkdir::DirEntry>> + 'a>,
+    throttle: Option<&'a IoThrottle>,
+    options: WalkOptions,
+    /// A description of every entry that could not be read so far.
+    pub unreadable: Vec<String>,
+}
+
+impl<'a> DirectoryWalk<'a> {
+    /// Starts walking a directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The directory to walk.
+    /// * `filters` - The filters deciding which entries become candidates.
+    /// * `throttle` - When provided, every step of the walk waits for its turn and failed reads back off.
+    /// * `options` - Whether to follow symbolic links, and which entries to yield.
+    pub fn new(
+        root: &'a Path,
+        filters: &'a FilterChain,
+        throttle: Option<&'a IoThrottle>,
+        options: WalkOptions,
+    ) -> DirectoryWalk<'a> {
+        let ffsignore = FfsignoreFilter::new(root, throttle);
+        let walk = WalkDir::new(root).follow_links(options.follow_links);
+        let entries = walk.into_iter().filter_entry(move |entry| {
+            let candidate = CandidateEntry {
+                path: entry.path(),
+                relative_path: entry.path().strip_prefix(root).unwrap_or(entry.path()),
+                is_dir: entry.file_type().is_dir(),
+                throttle,
+            };
+            entry.depth() == 0 || (ffsignore.accepts(&candidate) && filters.accepts(&candidate))
+        });
+        DirectoryWalk {
+            entries: Box::new(entries),
+            throttle,
+            options,
+            unreadable: Vec::new(),
+        }
+    }
+}
+
+impl Iterator for DirectoryWalk<'_> {
+    /// The file name (without extension) and the full path of a file, or the name and the full
+    /// path ending in a separator of a directory.
+    type Item = (String, String);
+
+    fn next(&mut self) -> Option<(String, String)> {
+        loop {
+            if let Some(throttle) = self.throttle {
+                throttle.wait();
+            }
+            let entry = self.entries.next()?;
+            if let Some(throttle) = self.throttle {
+                throttle.record(entry.is_ok());
+            }
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    self.unreadable.push(error.to_string());
+                    continue;
+                }
+            };
+            let file_name = entry.file_name().to_string_lossy();
+            let full_path = entry.path().to_string_lossy().into_owned();
+            if !entry.file_type().is_dir() {
+                if self.options.files {
+                    return Some((name_of_file(&file_name).to_string(), full_path));
+                }
+            } else if self.options.directories && entry.depth() > 0 {
+                let full_path = format!("{}{}", full_path, std::path::MAIN_SEPARATOR);
+                return Some((file_name.into_owned(), full_path));
+            }
+        }
+    }
+}
+
+/// A query prepared once for scoring many candidates: its characters, case folded characters, words
+/// and Bitap masks.
+struct PreparedQuery {
+    text: String,
+    chars: Vec<char>,
+    folded: Vec<char>,
+    /// The lower case words of the query with their [`fuzzy_threshold`].
+    words: Vec<(Vec<char>, u32)>,
+    /// The distinct [`trigrams`] of the query, sorted.
+    trigrams: Vec<Trigram>,
+    /// The [`metaphone`] keys of the words of the query.
+    phonetic_keys: Vec<String>,
+    /// Which query positions hold each character, for queries Bitap can match.
+    masks: HashMap<char, u64>,
+}
+
+impl PreparedQuery {
+    fn new(query: &str) -> PreparedQuery {
+        let chars: Vec<char> = query.chars().collect();
+        let mut masks: HashMap<char, u64> = HashMap::new();
+        if chars.len() <= BITAP_MAX_QUERY {
+            for (i, query_char) in chars.iter().enumerate() {
+                *masks.entry(*query_char).or_insert(0) |= 1 << i;
+            }
+        }
+        let folded: Vec<char> = chars.iter().copied().map(fold_case).collect();
+        let mut query_trigrams = Vec::new();
+        trigrams(&folded, &mut query_trigrams);
+        PreparedQuery {
+            text: query.to_string(),
+            trigrams: query_trigrams,
+            phonetic_keys: query_tokens(query)
+                .iter()
+                .map(|word| metaphone(word))
+                .collect(),
+            folded,
+            words: query_tokens(query)
+                .iter()
+                .map(|word| {
+                    let word = word.to_lowercase();
+                    (word.chars().collect(), fuzzy_threshold(&word))
+                })
+                .collect(),
+            chars,
+            masks,
+        }
+    }
+}
+
+/// Buffers reused from one candidate to the next, so scoring a candidate doesn't allocate.
+#[derive(Default)]
+struct Buffers {
+    name_chars: Vec<char>,
+    name_folded: Vec<char>,
+    previous: Vec<u32>,
+    current: Vec<u32>,
+    previous_penalties: Vec<i64>,
+    current_penalties: Vec<i64>,
+    states: Vec<u64>,
+    matched: Vec<bool>,
+    matches: Vec<char>,
+    /// The start and end of each word of the name that hasn't been matched yet.
+    words: Vec<Option<(usize, usize)>>,
+    trigrams: Vec<Trigram>,
+    letters: Vec<u8>,
+    /// The [`metaphone`] keys of the words of the name.
+    keys: Vec<String>,
+}
+
+impl Buffers {
+    fn load_name(&mut self, file_name: &str) {
+        self.name_chars.clear();
+        self.name_chars.extend(file_name.chars());
+        self.name_folded.clear();
+        self.name_folded
+            .extend(self.name_chars.iter().copied().map(fold_case));
+    }
+}
+
+/// The most prepared queries a thread keeps. Ranking alternates between the query and its lower
+/// case form for [`initials`], and [`FullPath`] between each segment of the query, so the last few
+/// queries are kept.
+const PREPARED_QUERIES: usize = 16;
+
+/// The prepared queries and buffers of a thread, up to [`PREPARED_QUERIES`] of the latest queries.
+#[derive(Default)]
+struct Scratch {
+    queries: Vec<PreparedQuery>,
+    buffers: Buffers,
+}
+
+impl Scratch {
+    fn prepare(&mut self, query: &str) -> usize {
+        let cached = self
+            .queries
+            .iter()
+            .position(|prepared| prepared.text == query);
+        if let Some(slot) = cached {
+            return slot;
+        }
+        if self.queries.len() == PREPARED_QUERIES {
+            self.queries.remove(0);
+        }
+        self.queries.push(PreparedQuery::new(query));
+        self.queries.len() - 1
+    }
+}
+
+thread_local! {
+    static SCRATCH: RefCell<Scratch> = RefCell::default();
+}
+
+/// Runs a scorer with the prepared query and the buffers of the current thread, loaded with the
+/// file name.
+fn with_scratch<R>(
+    query: &str,
+    file_name: &str,
+    score: impl FnOnce(&PreparedQuery, &mut Buffers) -> R,
+) -> R {
+    SCRATCH.with(|scratch| {
+        let scratch = &mut *scratch.borrow_mut();
+        let slot = scratch.prepare(query);
+        scratch.buffers.load_name(file_name);
+        score(&scratch.queries[slot], &mut scratch.buffers)
+    })
+}
+
+/// Folds a character for comparisons that ignore case.
+fn fold_case(character: char) -> char {
+    character.to_lowercase().next().unwrap_or(character)
+}
+
+/// Scores the similarity between a query and a file name using the specified fuzzy search algorithm.
+///
+/// Both are composed to Unicode NFC first, so accents match whether or not they are decomposed.
+///
+/// # Arguments
+///
+/// * `query` - The search query string.
+/// * `file_name` - The file name to compare against the query.
+/// * `scorer` - The fuzzy search algorithm to use for scoring.
+///
+/// # Returns
+///
+/// A result containing the similarity score as `u32`, or an error message if the algorithm can't
+/// score the query.
+pub fn score_fuzzy_search(
+    query: &str,
+    file_name: &str,
+    scorer: FuzzySearchAlgorithm,
+) -> Result<u32, String> {
+    let (query, file_name) = (&*compose(query), &*compose(file_name));
+    match scorer {
+        FuzzySearchAlgorithm::DamerauLevenshtein => {
+            Ok(damerau_levenshtein_distance(query, file_name))
+        }
+        FuzzySearchAlgorithm::Levenshtein => Ok(levenshtein_distance(query, file_name)),
+        FuzzySearchAlgorithm::JaroWinkler => Ok(jaro_winkler_score(query, file_name)),
+        FuzzySearchAlgorithm::Subsequence => Ok(subsequence_penalty(query, file_name)),
+        FuzzySearchAlgorithm::Tokens => Ok(tokens_score(query, file_name)),
+        FuzzySearchAlgorithm::Bitap => bitap_errors(query, file_name, fuzzy_threshold(query)),
+        FuzzySearchAlgorithm::SmithWaterman => {
+            SmithWatermanScorer::default().score(query, file_name)
+        }
+        FuzzySearchAlgorithm::Trigram => Ok(trigram_score(query, file_name)),
+    }
+}
+
+/// Computes the Levenshtein distance between two strings, counting insertions, deletions and
+/// substitutions of characters.
+///
+/// Only the previous and the current row of the DP table are kept, so memory grows with the
+/// length of the file name alone.
+///
+/// # Arguments
+///
+/// * `query` - The first string.
+/// * `file_name` - The second string.
+///
+/// # Returns
+///
+/// The Levenshtein distance as `u32`.
+fn levenshtein_distance(query: &str, file_name: &str) -> u32 {
+    with_scratch(query, file_name, |query, buffers| {
+        levenshtein_rows(
+            &query.chars,
+            &buffers.name_chars,
+            &mut buffers.previous,
+            &mut buffers.current,
+        )
+    })
+}
+
+/// Computes the Levenshtein distance between two character slices in the given row buffers.
+fn levenshtein_rows(
+    query: &[char],
+    file_name: &[char],
+    previous: &mut Vec<u32>,
+    current: &mut Vec<u32>,
+) -> u32 {
+    previous.clear();
+    previous.extend(0..=file_name.len() as u32);
+    current.clear();
+    current.resize(file_name.len() + 1, 0);
+    for (i, query_char) in query.iter().enumerate() {
+        current[0] = i as u32 + 1;
+        for (j, file_char) in file_name.iter().enumerate() {
+            let substitution = previous[j] + u32::from(query_char != file_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(previous, current);
+    }
+    previous[file_name.len()]
+}
+
+/// The longest query Bitap can match, one bit of the state words per character.
+const BITAP_MAX_QUERY: usize = u64::BITS as usize;
+
+/// Finds the fewest errors with which the query occurs anywhere in a file name, using the
+/// bit-parallel Bitap algorithm with Wu and Manber's extension to insertions, deletions and
+/// substitutions.
+///
+/// Each error count keeps a word whose bit `i` is set when the first `i + 1` query characters
+/// match the text read so far, so every text character costs a few shifts per allowed error.
+///
+/// # Arguments
+///
+/// * `query` - The pattern to search for, at most 64 characters long.
+/// * `file_name` - The text to search in.
+/// * `max_errors` - The most errors to search with.
+///
+/// # Returns
+///
+/// The fewest errors of a match, or the query length if it needs more than `max_errors`, or an
+/// error message if the query is too long.
+pub fn bitap_errors(query: &str, file_name: &str, max_errors: u32) -> Result<u32, String> {
+    with_scratch(query, file_name, |query, buffers| {
+        if query.chars.len() > BITAP_MAX_QUERY {
+            return Err(format!(
+                "Bitap supports queries of up to {} characters",
+                BITAP_MAX_QUERY
+            ));
+        }
+        let length = query.chars.len() as u32;
+        let max_errors = max_errors.min(length);
+        let found = 1u64 << (length.max(1) - 1);
+        // Up to `errors` leading query characters match the empty text by deleting them, so the
+        // whole query always matches with as many errors as it has characters.
+        let states = &mut buffers.states;
+        states.clear();
+        // The low `errors` bits, shifted in from the right so 64 errors don't overflow the word.
+        let low_bits = |errors: u32| u64::MAX.checked_shr(64 - errors).unwrap_or(0);
+        states.extend((0..=max_errors).map(low_bits));
+        let mut best = length;
+        for file_char in &buffers.name_chars {
+            let mask = query.masks.get(file_char).copied().unwrap_or(0);
+            let mut previous = states[0];
+            states[0] = ((states[0] << 1) | 1) & mask;
+            for errors in 1..states.len() {
+                let state = states[errors];
+                states[errors] = (((state << 1) | 1) & mask)
+                    // The file name has an extra character.
+                    | previous
+                    // The file name has a different character.
+                    | (previous << 1)
+                    // The file name lacks a query character.
+                    | (states[errors - 1] << 1)
+                    | 1;
+                previous = state;
+            }
+            if let Some(errors) = states.iter().position(|state| state & found != 0) {
+                best = best.min(errors as u32);
+                if best == 0 {
+                    break;
+                }
+                // Only matches with fewer errors can improve on this one.
+                states.truncate(best as usize);
+            }
+        }
+        Ok(best)
+    })
+}
+
+/// The score of each query word missing from a name. The costs of the words that were found stay
+/// below it, so names are ranked by how many words they miss first.
+const TOKEN_MISS: u32 = 100;
+
+/// Splits a query into its words, at whitespace and wherever [`name_tokens`] splits names.
+fn query_tokens(query: &str) -> Vec<String> {
+    query.split_whitespace().flat_map(name_tokens).collect()
+}
+
+/// Returns the maximum token score accepted for a query: most of its words have to be found.
+fn tokens_threshold(query: &str) -> u32 {
+    let words = query_tokens(query).len() as u32;
+    words.saturating_sub(1) / 2 * TOKEN_MISS + TOKEN_MISS - 1
+}
+
+/// Scores a name by how many of the query's words it contains, so multi-word queries like
+/// `user service test` find `UserServiceTest` and `user_service_test`.
+///
+/// Words are compared ignoring case. Each query word takes the unused word of the name it matches
+/// best: equal words cost nothing, words it is a prefix of `1`, and words within its
+/// [`fuzzy_threshold`] one more than their distance. Words of the name left unused cost `1` each.
+///
+/// # Arguments
+///
+/// * `query` - The words to look for.
+/// * `file_name` - The name to look in.
+///
+/// # Returns
+///
+/// [`TOKEN_MISS`] per missing word plus the costs of the others, capped below [`TOKEN_MISS`].
+fn tokens_score(query: &str, file_name: &str) -> u32 {
+    with_scratch(query, file_name, |query, buffers| {
+        let Buffers {
+            name_chars,
+            name_folded,
+            previous,
+            current,
+            words,
+            ..
+        } = buffers;
+        word_ranges(name_chars, words);
+        let mut misses = 0;
+        let mut cost = 0;
+        for (query_word, threshold) in &query.words {
+            let mut best: Option<(u32, usize)> = None;
+            for (index, word) in words.iter().enumerate() {
+                let Some((start, end)) = *word else {
+                    continue;
+                };
+                let name_word = &name_folded[start..end];
+                let word_cost = if name_word == query_word.as_slice() {
+                    0
+                } else if name_word.starts_with(query_word) {
+                    1
+                } else {
+                    let distance = levenshtein_rows(query_word, name_word, previous, current);
+                    if distance > *threshold {
+                        continue;
+                    }
+                    distance + 1
+                };
+                if best.is_none_or(|(best_cost, _)| word_cost < best_cost) {
+                    best = Some((word_cost, index));
+                }
+            }
+            match best {
+                Some((word_cost, index)) => {
+                    cost += word_cost;
+                    words[index] = None;
+                }
+                None => misses += 1,
+            }
+        }
+        cost += words.iter().flatten().count() as u32;
+        misses * TOKEN_MISS + cost.min(TOKEN_MISS - 1)
+    })
 }
 
-/// Walks over the directory and returns a vector of tuples containing the file name and the full path.
-/// Skip the files with the extensions provided in the exclude_extensions flag.
-/// Focuses the search to extensions provided in the focus_extensions flag.
+/// What aligning two equal characters earns in [`smith_waterman_score`].
+const SMITH_WATERMAN_MATCH: u32 = 2;
+
+/// The default cost of aligning two different characters.
+pub const SMITH_WATERMAN_MISMATCH: u32 = 1;
+
+/// The default cost of skipping a character of the query or the name.
+pub const SMITH_WATERMAN_GAP: u32 = 1;
+
+/// Scores a file name by its best [Smith-Waterman](https://en.wikipedia.org/wiki/Smith%E2%80%93Waterman_algorithm)
+/// local alignment with the query, so a query matching only part of a long name isn't charged for
+/// the rest of it, e.g. `handler` in `legacy_request_handler_v2`.
+///
+/// Characters are compared ignoring case. Equal characters earn [`SMITH_WATERMAN_MATCH`], while
+/// different characters and skipped ones cost the given penalties.
 ///
 /// # Arguments
 ///
-/// * `exclude_extension_set` - A set of file extensions to exclude from the results.
-/// * `focus_extension_set` - A set of file extensions to include in the results. If empty, all extensions except those in `exclude_extension_set` are included.
+/// * `query` - The query to align.
+/// * `file_name` - The name to align it in.
+/// * `mismatch_penalty` - What aligning two different characters costs.
+/// * `gap_penalty` - What skipping a character costs.
 ///
 /// # Returns
 ///
-/// A vector of tuples where each tuple contains the file name (without extension) and the full path.
-pub fn walk_directory(
-    exclude_extension_set: BTreeSet<String>,
-    focus_extension_set: BTreeSet<String>,
-) -> Vec<(String, String)> {
-    let mut files = Vec::new();
-    for entry in WalkDir::new(".")
-        .into_iter()
-        .filter_map(Result::ok)
-        .filter(|e| !e.file_type().is_dir())
-    {
-        let file_name: String = String::from(entry.file_name().to_string_lossy());
-        let full_path: String = String::from(entry.path().to_string_lossy());
-        let chunks: Vec<&str> = file_name.split('.').collect();
-        let raw_file_name: String = chunks[..chunks.len() - 1].join(".");
-        if focus_extension_set.is_empty() {
-            if chunks
-                .last()
-                .map_or(true, |ext| !exclude_extension_set.contains(*ext))
-            {
-                files.push((raw_file_name, full_path));
+/// How far the best alignment falls short of matching every query character, `0` when the name
+/// contains the query.
+fn smith_waterman_score(
+    query: &str,
+    file_name: &str,
+    mismatch_penalty: u32,
+    gap_penalty: u32,
+) -> u32 {
+    with_scratch(query, file_name, |query, buffers| {
+        let query = &query.folded;
+        let Buffers {
+            name_folded: name,
+            previous_penalties: previous,
+            current_penalties: current,
+            ..
+        } = buffers;
+        let (matched, mismatch, gap) = (
+            i64::from(SMITH_WATERMAN_MATCH),
+            i64::from(mismatch_penalty),
+            i64::from(gap_penalty),
+        );
+        // `previous[j]` is the best score of an alignment ending at the previous query character
+        // and `name[j - 1]`.
+        previous.clear();
+        previous.resize(name.len() + 1, 0);
+        current.clear();
+        current.resize(name.len() + 1, 0);
+        let mut best = 0;
+        for query_char in query {
+            for (j, name_char) in name.iter().enumerate() {
+                let substitution = if query_char == name_char {
+                    matched
+                } else {
+                    -mismatch
+                };
+                let diagonal = previous[j] + substitution;
+                current[j + 1] = diagonal
+                    .max(previous[j + 1] - gap)
+                    .max(current[j] - gap)
+                    .max(0);
+                best = best.max(current[j + 1]);
             }
-        } else {
-            if chunks
-                .last()
-                .map_or(false, |ext| focus_extension_set.contains(*ext))
-            {
-                files.push((raw_file_name, full_path));
+            std::mem::swap(previous, current);
+        }
+        (matched * query.len() as i64 - best) as u32
+    })
+}
+
+/// Three consecutive characters of a word, padded with spaces at the word's edges.
+type Trigram = [char; 3];
+
+/// The highest trigram score accepted: half of the query's trigrams have to be found.
+const TRIGRAM_THRESHOLD: u32 = 50;
+
+/// Collects the distinct trigrams of a text, sorted, the way PostgreSQL's `pg_trgm` does: the text
+/// is split into words at every character that isn't alphanumeric, and each word is padded with two
+/// spaces in front and one behind, so `main` has the trigrams `  m`, ` ma`, `mai`, `ain` and `in `.
+fn trigrams(text: &[char], trigrams: &mut Vec<Trigram>) {
+    trigrams.clear();
+    for word in text
+        .split(|character| !character.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        let padded = |index: usize| match index {
+            0 | 1 => ' ',
+            index => word.get(index - 2).copied().unwrap_or(' '),
+        };
+        trigrams.extend(
+            (0..word.len() + 1).map(|start| [padded(start), padded(start + 1), padded(start + 2)]),
+        );
+    }
+    trigrams.sort_unstable();
+    trigrams.dedup();
+}
+
+/// Scores a file name by the share of the query's [`trigrams`] it lacks, which copes with long
+/// names and partial matches better than edit distances: a typo only loses the few trigrams
+/// around it, and the rest of a long name costs nothing.
+///
+/// Characters are compared ignoring case.
+///
+/// # Arguments
+///
+/// * `query` - The query whose trigrams are looked for.
+/// * `file_name` - The name to look in.
+///
+/// # Returns
+///
+/// The share of the query's trigrams missing from the name in hundredths, `0` for names with the
+/// same trigrams as the query and `1` for names with further trigrams but none missing.
+fn trigram_score(query: &str, file_name: &str) -> u32 {
+    with_scratch(query, file_name, |query, buffers| {
+        let query = &query.trigrams;
+        let name = &mut buffers.trigrams;
+        trigrams(&buffers.name_folded, name);
+        if query.is_empty() {
+            return u32::from(!name.is_empty());
+        }
+        let (mut shared, mut i, mut j) = (0, 0, 0);
+        while i < query.len() && j < name.len() {
+            match query[i].cmp(&name[j]) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    shared += 1;
+                    i += 1;
+                    j += 1;
+                }
             }
         }
+        let missing = (100 * (query.len() - shared) as u32).div_ceil(query.len() as u32);
+        missing.max(u32::from(name.len() > shared))
+    })
+}
+
+/// Collects where the words of a name, as split by [`name_tokens`], start and end.
+fn word_ranges(name: &[char], words: &mut Vec<Option<(usize, usize)>>) {
+    words.clear();
+    for (index, character) in name.iter().enumerate() {
+        if !character.is_alphanumeric() {
+            continue;
+        }
+        match words.last_mut() {
+            Some(Some((_, end))) if !starts_word(name, index) => *end = index + 1,
+            _ => words.push(Some((index, index + 1))),
+        }
+    }
+}
+
+/// Scores names by how their words sound, for `--phonetic`.
+#[derive(Debug, Clone, Copy)]
+pub struct PhoneticScorer;
+
+impl ScoreFunction for PhoneticScorer {
+    fn score(&self, query: &str, file_name: &str) -> Result<u32, String> {
+        Ok(phonetic_score(query, file_name))
+    }
+
+    fn threshold(&self, query: &str) -> u32 {
+        tokens_threshold(query)
+    }
+
+    fn similarity(&self, query: &str, _file_name: &str, score: u32) -> u32 {
+        share_similarity(score, query_tokens(query).len() as u32 * TOKEN_MISS)
     }
 
-    return files;
+    fn normalizes_case(&self) -> bool {
+        false
+    }
 }
 
-/// Scores the similarity between a query and a file name using the specified fuzzy search algorithm.
+/// Computes the [Metaphone](https://en.wikipedia.org/wiki/Metaphone) key of a word: a code of how
+/// it sounds in English, so words spelled differently but pronounced alike share it, e.g. `KLR` for
+/// both `kolor` and `color`. Only the ASCII letters of the word are considered.
+pub fn metaphone(word: &str) -> String {
+    let word: Vec<char> = word.chars().collect();
+    let mut key = String::new();
+    metaphone_into(&word, &mut Vec::new(), &mut key);
+    key
+}
+
+/// Writes the [`metaphone`] key of a word into `key`, using `letters` as a buffer.
+fn metaphone_into(word: &[char], letters: &mut Vec<u8>, key: &mut String) {
+    letters.clear();
+    letters.extend(
+        word.iter()
+            .filter(|character| character.is_ascii_alphabetic())
+            .map(|character| character.to_ascii_uppercase() as u8),
+    );
+    key.clear();
+    let letters = letters.as_slice();
+    let at = |index: usize| letters.get(index).copied().unwrap_or(0);
+    let is_vowel = |letter: u8| matches!(letter, b'A' | b'E' | b'I' | b'O' | b'U');
+    let mut start = 0;
+    match (at(0), at(1)) {
+        (b'A', b'E') | (b'G', b'N') | (b'K', b'N') | (b'P', b'N') | (b'W', b'R') => start = 1,
+        (b'X', _) => {
+            key.push('S');
+            start = 1;
+        }
+        (b'W', b'H') => {
+            key.push('W');
+            start = 2;
+        }
+        _ => {}
+    }
+    for index in start..letters.len() {
+        let letter = letters[index];
+        let (previous, next, after_next) = (
+            index.checked_sub(1).map_or(0, |previous| letters[previous]),
+            at(index + 1),
+            at(index + 2),
+        );
+        // Doubled letters sound like one, except for `CC` as in `accent`.
+        if letter == previous && letter != b'C' {
+            continue;
+        }
+        let same = std::slice::from_ref(&letters[index]);
+        let code: &[u8] = match letter {
+            // Vowels only count at the start of a word.
+            _ if is_vowel(letter) => {
+                if index == start && key.is_empty() {
+                    same
+                } else {
+                    b""
+                }
+            }
+            b'B' if previous == b'M' && next == 0 => b"",
+            b'C' if next == b'I' && after_next == b'A' => b"X",
+            b'C' if next == b'H' => {
+                if previous == b'S' {
+                    b"K"
+                } else {
+                    b"X"
+                }
+            }
+            b'C' if matches!(next, b'I' | b'E' | b'Y') => {
+                if previous == b'S' {
+                    b""
+                } else {
+                    b"S"
+                }
+            }
+            b'C' => b"K",
+            b'D' if next == b'G' && matches!(after_next, b'E' | b'I' | b'Y') => b"J",
+            b'D' => b"T",
+            b'G' if next == b'H' && after_next != 0 && !is_vowel(after_next) => b"",
+            b'G' if next == b'N' && (after_next == 0 || &letters[index + 1..] == b"NED") => b"",
+            b'G' if previous == b'D' && matches!(next, b'E' | b'I' | b'Y') => b"",
+            b'G' if matches!(next, b'I' | b'E' | b'Y') && previous != b'G' => b"J",
+            b'G' => b"K",
+            b'H' if matches!(previous, b'C' | b'S' | b'P' | b'T' | b'G') => b"",
+            b'H' if is_vowel(previous) && !is_vowel(next) => b"",
+            b'K' if previous == b'C' => b"",
+            b'P' if next == b'H' => b"F",
+            b'Q' => b"K",
+            b'S' if next == b'H' || (next == b'I' && matches!(after_next, b'O' | b'A')) => b"X",
+            b'T' if next == b'I' && matches!(after_next, b'O' | b'A') => b"X",
+            b'T' if next == b'H' => b"0",
+            b'T' if next == b'C' && after_next == b'H' => b"",
+            b'V' => b"F",
+            b'W' | b'Y' if !is_vowel(next) => b"",
+            b'X' => b"KS",
+            b'Z' => b"S",
+            _ => same,
+        };
+        key.extend(code.iter().map(|&byte| byte as char));
+    }
+}
+
+/// Scores a name by how many of the query's words sound like one of its words, comparing their
+/// [`metaphone`] keys the way [`tokens_score`] compares words: equal keys cost nothing, keys that
+/// are a prefix of the other's `1`, and words of the name left unused `1` each.
 ///
 /// # Arguments
 ///
-/// * `query` - The search query string.
-/// * `file_name` - The file name to compare against the query.
-/// * `scorer` - The fuzzy search algorithm to use for scoring.
+/// * `query` - The words to look for.
+/// * `file_name` - The name to look in.
 ///
 /// # Returns
 ///
-/// A result containing the similarity score as `u32` if the algorithm is implemented, otherwise an error message.
-pub fn score_fuzzy_search(
-    query: String,
-    file_name: String,
-    scorer: FuzzySearchAlgorithm,
-) -> Result<u32, String> {
-    match scorer {
-        FuzzySearchAlgorithm::DamerauLevenshtein => {
-            Ok(damerau_levenshtein_distance(query, file_name))
+/// [`TOKEN_MISS`] per word that sounds like none of the name's, plus the costs of the others,
+/// capped below [`TOKEN_MISS`].
+fn phonetic_score(query: &str, file_name: &str) -> u32 {
+    with_scratch(query, file_name, |query, buffers| {
+        let Buffers {
+            name_chars,
+            words,
+            letters,
+            keys,
+            ..
+        } = buffers;
+        word_ranges(name_chars, words);
+        if keys.len() < words.len() {
+            keys.resize(words.len(), String::new());
+        }
+        for (word, key) in words.iter().flatten().zip(keys.iter_mut()) {
+            metaphone_into(&name_chars[word.0..word.1], letters, key);
+        }
+        let mut misses = 0;
+        let mut cost = 0;
+        for query_key in &query.phonetic_keys {
+            let mut best: Option<(u32, usize)> = None;
+            for (index, key) in keys[..words.len()].iter().enumerate() {
+                if words[index].is_none() {
+                    continue;
+                }
+                let key_cost = if key == query_key {
+                    0
+                } else if !key.is_empty()
+                    && !query_key.is_empty()
+                    && (key.starts_with(query_key.as_str()) || query_key.starts_with(key.as_str()))
+                {
+                    1
+                } else {
+                    continue;
+                };
+                if best.is_none_or(|(best_cost, _)| key_cost < best_cost) {
+                    best = Some((key_cost, index));
+                }
+            }
+            match best {
+                Some((key_cost, index)) => {
+                    cost += key_cost;
+                    words[index] = None;
+                }
+                None => misses += 1,
+            }
+        }
+        cost += words.iter().flatten().count() as u32;
+        misses * TOKEN_MISS + cost.min(TOKEN_MISS - 1)
+    })
+}
+
+/// The score of names that don't contain the query as a subsequence.
+const NOT_A_SUBSEQUENCE: u32 = u32::MAX;
+
+/// The penalty for a match that doesn't follow the previous one or start a word.
+const SUBSEQUENCE_SCATTERED: i64 = 2;
+
+/// The penalty for opening a gap of skipped characters between two matches.
+const SUBSEQUENCE_GAP_START: i64 = 2;
+
+/// Returns whether a character starts a word: it follows a separator such as `_`, `-` or `.`, or is
+/// an upper case letter or digit following a lower case letter, e.g. the `B` in `fooBar`.
+fn starts_word(name: &[char], index: usize) -> bool {
+    starts_word_after(
+        index.checked_sub(1).map(|previous| name[previous]),
+        name[index],
+    )
+}
+
+/// Like [`starts_word`], for a character and the one before it, if any.
+fn starts_word_after(previous: Option<char>, current: char) -> bool {
+    let Some(previous) = previous else {
+        return true;
+    };
+    !previous.is_alphanumeric()
+        || (previous.is_lowercase() && (current.is_uppercase() || current.is_ascii_digit()))
+}
+
+/// Scores a file name by how well the query matches it as an ordered subsequence, the way fzf does,
+/// so abbreviations like `srch` for `search` match.
+///
+/// Characters are compared ignoring case. The best alignment is found with a DP over the query and
+/// the name: matches that run on from the previous one or start a word are free, others cost
+/// [`SUBSEQUENCE_SCATTERED`], and each gap between matches costs [`SUBSEQUENCE_GAP_START`] plus
+/// its length. Starting after the first character or ending before the last costs one more each.
+///
+/// # Arguments
+///
+/// * `query` - The abbreviation to look for.
+/// * `file_name` - The name to match it in.
+///
+/// # Returns
+///
+/// The penalty of the best alignment, `0` for an exact match, or [`NOT_A_SUBSEQUENCE`].
+fn subsequence_penalty(query: &str, file_name: &str) -> u32 {
+    with_scratch(query, file_name, |query, buffers| {
+        let query = &query.folded;
+        let Buffers {
+            name_chars: name,
+            name_folded: lower,
+            previous_penalties: previous,
+            current_penalties: current,
+            ..
+        } = buffers;
+        if query.is_empty() {
+            return 0;
+        }
+        const UNREACHABLE: i64 = i64::MAX / 4;
+        let match_cost = |index: usize| {
+            if starts_word(name, index) {
+                0
+            } else {
+                SUBSEQUENCE_SCATTERED
+            }
+        };
+        // `previous[j]` is the penalty of the best alignment of the query so far ending at
+        // `name[j]`.
+        previous.clear();
+        previous.extend((0..name.len()).map(|j| {
+            if lower[j] != query[0] {
+                UNREACHABLE
+            } else {
+                i64::from(j > 0) + match_cost(j)
+            }
+        }));
+        current.clear();
+        current.resize(name.len(), UNREACHABLE);
+        for query_char in &query[1..] {
+            // The best `previous[k] - k` over the matches that leave a gap before `j`.
+            let mut best_gapped = UNREACHABLE;
+            for j in 0..name.len() {
+                if j >= 2 && previous[j - 2] < UNREACHABLE {
+                    best_gapped = best_gapped.min(previous[j - 2] - (j as i64 - 2));
+                }
+                current[j] = UNREACHABLE;
+                if lower[j] != *query_char || j == 0 {
+                    continue;
+                }
+                let consecutive = previous[j - 1];
+                // A gap from `k` to `j` skips `j - k - 1` characters.
+                let gapped = best_gapped.saturating_add(j as i64 - 1 + SUBSEQUENCE_GAP_START);
+                current[j] = consecutive.min(gapped.saturating_add(match_cost(j)));
+            }
+            std::mem::swap(previous, current);
+        }
+        previous
+            .iter()
+            .enumerate()
+            .filter(|(_, penalty)| **penalty < UNREACHABLE)
+            .map(|(j, penalty)| penalty + i64::from(j + 1 < name.len()))
+            .min()
+            .map_or(NOT_A_SUBSEQUENCE, |penalty| penalty as u32)
+    })
+}
+
+/// How far the similarity of a common prefix pulls the Jaro similarity towards 1.
+const JARO_WINKLER_PREFIX_SCALE: f64 = 0.1;
+
+/// The longest common prefix Jaro-Winkler rewards.
+const JARO_WINKLER_MAX_PREFIX: usize = 4;
+
+/// Computes the Jaro similarity between two strings: `1.0` for equal strings and `0.0` for strings
+/// without common characters.
+///
+/// Characters match when they are equal and no further apart than half the longer string. Matching
+/// characters that appear in a different order count as half a transposition each.
+///
+/// # Arguments
+///
+/// * `query` - The first string.
+/// * `file_name` - The second string.
+/// * `file_matched` - A buffer for which characters of the file name matched.
+/// * `query_matches` - A buffer for the matching characters of the query.
+///
+/// # Returns
+///
+/// The Jaro similarity as `f64`.
+fn jaro_similarity(
+    query: &[char],
+    file_name: &[char],
+    file_matched: &mut Vec<bool>,
+    query_matches: &mut Vec<char>,
+) -> f64 {
+    if query.is_empty() && file_name.is_empty() {
+        return 1.0;
+    }
+    if query.is_empty() || file_name.is_empty() {
+        return 0.0;
+    }
+    let window = (query.len().max(file_name.len()) / 2).saturating_sub(1);
+    file_matched.clear();
+    file_matched.resize(file_name.len(), false);
+    query_matches.clear();
+    for (i, query_char) in query.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(file_name.len());
+        for j in start..end {
+            if !file_matched[j] && file_name[j] == *query_char {
+                file_matched[j] = true;
+                query_matches.push(*query_char);
+                break;
+            }
         }
-        _ => Err(format!("{:?} Algorithm not implemented", scorer)),
     }
+    if query_matches.is_empty() {
+        return 0.0;
+    }
+    let file_matches = file_name
+        .iter()
+        .zip(file_matched.iter())
+        .filter_map(|(file_char, matched)| matched.then_some(file_char));
+    let transpositions = query_matches
+        .iter()
+        .zip(file_matches)
+        .filter(|(query_char, file_char)| query_char != file_char)
+        .count() as f64
+        / 2.0;
+    let matches = query_matches.len() as f64;
+    (matches / query.len() as f64
+        + matches / file_name.len() as f64
+        + (matches - transpositions) / matches)
+        / 3.0
+}
+
+/// Computes the Jaro-Winkler similarity between two strings, which boosts the [`jaro_similarity`]
+/// of strings sharing a prefix of up to four characters.
+fn jaro_winkler_similarity(query: &str, file_name: &str) -> f64 {
+    with_scratch(query, file_name, |query, buffers| {
+        let query = &query.chars;
+        let file_name = &buffers.name_chars;
+        let jaro = jaro_similarity(query, file_name, &mut buffers.matched, &mut buffers.matches);
+        let prefix = query
+            .iter()
+            .zip(file_name)
+            .take(JARO_WINKLER_MAX_PREFIX)
+            .take_while(|(query_char, file_char)| query_char == file_char)
+            .count() as f64;
+        jaro + prefix * JARO_WINKLER_PREFIX_SCALE * (1.0 - jaro)
+    })
+}
+
+/// Scores a file name by its Jaro-Winkler similarity to the query, turned into the score contract
+/// of [`ScoreFunction`]: the distance from a perfect match in hundredths, so `0` is an exact match
+/// and `100` shares no characters with the query.
+///
+/// # Arguments
+///
+/// * `query` - The first string.
+/// * `file_name` - The second string.
+///
+/// # Returns
+///
+/// The Jaro-Winkler score as `u32`.
+fn jaro_winkler_score(query: &str, file_name: &str) -> u32 {
+    ((1.0 - jaro_winkler_similarity(query, file_name)) * 100.0).round() as u32
 }
 
 /// Computes the Damerau-Levenshtein distance between two strings.
 ///
+/// The strings are compared by grapheme clusters rather than bytes or characters, so replacing an
+/// emoji, a flag or a letter with a combining accent costs a single edit.
+///
+/// Both strings are split into graphemes once, and only the last three rows of the DP table are
+/// kept, since transpositions look two rows back.
+///
 /// # Arguments
 ///
 /// * `query` - The first string.
@@ -89,50 +2118,700 @@ pub fn score_fuzzy_search(
 /// # Returns
 ///
 /// The Damerau-Levenshtein distance as `u32`.
-fn damerau_levenshtein_distance(query: String, file_name: String) -> u32 {
-    let n: usize = query.len();
+fn damerau_levenshtein_distance(query: &str, file_name: &str) -> u32 {
+    let query: Vec<&str> = query.graphemes(true).collect();
+    let file_name: Vec<&str> = file_name.graphemes(true).collect();
     let m: usize = file_name.len();
 
-    let mut dp: Vec<Vec<u32>> = vec![vec![0; m + 1]; n + 1];
-    for i in 0..=n {
-        dp[i][0] = i as u32;
-    }
-    for j in 0..=m {
-        dp[0][j] = j as u32;
-    }
-    for i in 1..=n {
+    let mut before_previous: Vec<u32> = vec![0; m + 1];
+    let mut previous: Vec<u32> = (0..=m as u32).collect();
+    let mut current: Vec<u32> = vec![0; m + 1];
+    for i in 1..=query.len() {
+        current[0] = i as u32;
         for j in 1..=m {
-            if query.chars().nth(i - 1) == file_name.chars().nth(j - 1) {
-                dp[i][j] = dp[i - 1][j - 1];
+            if query[i - 1] == file_name[j - 1] {
+                current[j] = previous[j - 1];
             } else {
-                dp[i][j] =
-                    1 + std::cmp::min(dp[i - 1][j], std::cmp::min(dp[i][j - 1], dp[i - 1][j - 1]));
+                current[j] = 1 + previous[j].min(current[j - 1]).min(previous[j - 1]);
             }
             if i > 1
                 && j > 1
-                && query.chars().nth(i - 1) == file_name.chars().nth(j - 2)
-                && query.chars().nth(i - 2) == file_name.chars().nth(j - 1)
+                && query[i - 1] == file_name[j - 2]
+                && query[i - 2] == file_name[j - 1]
             {
-                dp[i][j] = std::cmp::min(dp[i][j], dp[i - 2][j - 2] + 1);
+                current[j] = current[j].min(before_previous[j - 2] + 1);
             }
         }
+        // The oldest row is reused as the next one.
+        std::mem::swap(&mut before_previous, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
     }
-    return dp[n][m];
+    previous[m]
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_algorithm_choice() {
+        for algorithm in FuzzySearchAlgorithm::ALL {
+            let choice: AlgorithmChoice = algorithm.name().parse().unwrap();
+            assert_eq!(choice.to_string(), algorithm.name());
+        }
+        assert_eq!(
+            "plugin:my_scorer"
+                .parse::<AlgorithmChoice>()
+                .unwrap()
+                .to_string(),
+            "plugin:my_scorer"
+        );
+        assert!("plugin:".parse::<AlgorithmChoice>().is_err());
+        assert!("soundex".parse::<AlgorithmChoice>().is_err());
+    }
+
+    #[test]
+    fn test_split_path_query() {
+        let separator = std::path::MAIN_SEPARATOR_STR;
+        assert_eq!(
+            normalize_separators("src\\ui/main.rs"),
+            ["src", "ui", "main.rs"].join(separator)
+        );
+        assert_eq!(split_path_query("main"), ("main".to_string(), None));
+        assert!(is_absolute_query("/home/me/main.rs"));
+        assert!(is_absolute_query("C:\\Users\\me\\main.rs"));
+        assert!(is_absolute_query("\\\\server\\share\\main.rs"));
+        assert!(!is_absolute_query("src\\main.rs"));
+        assert!(!is_absolute_query("c:main.rs"));
+        assert_eq!(existing_path("main"), None);
+        assert_eq!(
+            existing_path("src\\search.rs"),
+            Some(["src", "search.rs"].join(std::path::MAIN_SEPARATOR_STR))
+        );
+        assert_eq!(
+            split_path_query("src\\ui\\main.rs"),
+            ("main".to_string(), Some(Path::new("src").join("ui")))
+        );
+        assert_eq!(
+            split_path_query("src/Makefile"),
+            ("Makefile".to_string(), Some(PathBuf::from("src")))
+        );
+    }
+
+    #[test]
+    fn test_module_paths() {
+        assert_eq!(
+            split_module_query("search::walk"),
+            Some(vec!["search", "walk"])
+        );
+        assert_eq!(
+            split_module_query("crate::gui::draw"),
+            Some(vec!["gui", "draw"])
+        );
+        assert_eq!(split_module_query("walk"), None);
+        assert_eq!(split_module_query("src::main.rs"), None);
+        assert_eq!(split_module_query("a::1b"), None);
+        assert_eq!(module_path_of_file("./src/search.rs"), Some(vec!["search"]));
+        assert_eq!(module_path_of_file("./src/main.rs"), Some(vec![]));
+        assert_eq!(
+            module_path_of_file("crates/core/src/ui/mod.rs"),
+            Some(vec!["ui"])
+        );
+        assert_eq!(
+            module_path_of_file("src\\ui\\lib.rs"),
+            Some(vec!["ui", "lib"])
+        );
+        assert_eq!(module_path_of_file("./src/search.py"), None);
+        assert_eq!(module_path_of_file("./lib/search.rs"), None);
+    }
+
+    #[test]
+    fn test_contains_score() {
+        assert_eq!(contains_score("main", "main"), Some(0));
+        assert_eq!(contains_score("main", "mains"), Some(1));
+        assert_eq!(contains_score("main", "domain"), Some(4));
+        assert_eq!(contains_score("main", "main_window_controller"), Some(18));
+        assert_eq!(contains_score("main", "mian"), None);
+    }
+
+    #[test]
+    fn test_initials() {
+        assert_eq!(
+            name_tokens("mainWindow_controller-v2.test"),
+            vec!["main", "Window", "controller", "v", "2", "test"]
+        );
+        assert_eq!(initials("main_window_controller"), Some("mwc".to_string()));
+        assert_eq!(initials("MainWindowController"), Some("mwc".to_string()));
+        assert_eq!(initials("main"), None);
+        let algorithm = FuzzySearchAlgorithm::DamerauLevenshtein;
+        let ranked = rank_candidates(
+            "mwc",
+            vec![
+                ("main_window_controller".to_string(), ()),
+                ("mwc".to_string(), ()),
+                ("main_widget".to_string(), ()),
+            ],
+            &algorithm,
+        )
+        .unwrap();
+        let hits: Vec<(u32, &str)> = ranked
+            .iter()
+            .map(|(score, name, _)| (*score, name.as_str()))
+            .collect();
+        assert_eq!(hits, vec![(0, "mwc"), (1, "main_window_controller")]);
+    }
+
+    #[test]
+    fn test_candidate_name() {
+        assert_eq!(candidate_name("search.rs"), "search");
+        assert_eq!(candidate_name("archive.tar.gz"), "archive.tar");
+        assert_eq!(candidate_name("Makefile"), "Makefile");
+        assert_eq!(candidate_name(".gitignore"), ".gitignore");
+        assert_eq!(candidate_name(".env.local"), ".env");
+        assert_eq!(candidate_name_of_path("./src/ui/main.rs"), "main");
+        assert_eq!(candidate_name_of_path("./src/ui.d/"), "ui.d");
+    }
+
+    #[test]
+    fn test_walk_entry_types() {
+        let project = std::env::temp_dir().join(format!("ffs-types-{}", std::process::id()));
+        std::fs::create_dir_all(project.join("src/ui")).unwrap();
+        std::fs::write(project.join("src/ui/main.rs"), "").unwrap();
+        let filters = FilterChain::new();
+        let walk = |files: bool, directories: bool| {
+            let options = WalkOptions {
+                files,
+                directories,
+                ..WalkOptions::default()
+            };
+            let mut found: Vec<(String, String)> =
+                DirectoryWalk::new(&project, &filters, None, options)
+                    .map(|(name, full_path)| {
+                        let relative = Path::new(&full_path).strip_prefix(&project).unwrap();
+                        let separator = if is_directory_path(&full_path) {
+                            "/"
+                        } else {
+                            ""
+                        };
+                        let relative = relative.to_string_lossy().replace('\\', "/");
+                        (name, relative + separator)
+                    })
+                    .collect();
+            found.sort();
+            found
+        };
+        let (files, directories, all) = (walk(true, false), walk(false, true), walk(true, true));
+        std::fs::remove_dir_all(&project).unwrap();
+        let entry = |name: &str, path: &str| (name.to_string(), path.to_string());
+        assert_eq!(files, [entry("main", "src/ui/main.rs")]);
+        assert_eq!(directories, [entry("src", "src/"), entry("ui", "src/ui/")]);
+        assert_eq!(
+            all,
+            [
+                entry("main", "src/ui/main.rs"),
+                entry("src", "src/"),
+                entry("ui", "src/ui/")
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_links() {
+        let project = std::env::temp_dir().join(format!("ffs-follow-{}", std::process::id()));
+        let shared = std::env::temp_dir().join(format!("ffs-follow-shared-{}", std::process::id()));
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::create_dir_all(&shared).unwrap();
+        std::fs::write(project.join("src/main.rs"), "").unwrap();
+        std::fs::write(shared.join("util.rs"), "").unwrap();
+        std::os::unix::fs::symlink(&shared, project.join("shared")).unwrap();
+        std::os::unix::fs::symlink(&project, project.join("src/loop")).unwrap();
+        let filters = FilterChain::new();
+        let walk = |follow_links: bool| {
+            let options = WalkOptions {
+                follow_links,
+                ..WalkOptions::default()
+            };
+            let mut walk = DirectoryWalk::new(&project, &filters, None, options);
+            let mut names: Vec<String> = (&mut walk).map(|(name, _)| name).collect();
+            names.sort();
+            (names, walk.unreadable.len())
+        };
+        let (not_following, following) = (walk(false), walk(true));
+        std::fs::remove_dir_all(&project).unwrap();
+        std::fs::remove_dir_all(&shared).unwrap();
+        // Without following, links are files named after the link.
+        let names = vec!["loop".to_string(), "main".into(), "shared".into()];
+        assert_eq!(not_following, (names, 0));
+        assert_eq!(following, (vec!["main".to_string(), "util".into()], 1));
+    }
+
+    #[test]
+    fn test_distinct_roots() {
+        let project = std::env::temp_dir().join(format!("ffs-roots-{}", std::process::id()));
+        std::fs::create_dir_all(project.join("src/ui")).unwrap();
+        std::fs::create_dir_all(project.join("tests")).unwrap();
+        let roots = [
+            project.join("src/ui"),
+            project.join("tests"),
+            project.join("src"),
+            project.join("tests/../tests"),
+            project.join("missing"),
+        ];
+        let distinct = distinct_roots(&roots);
+        std::fs::remove_dir_all(&project).unwrap();
+        assert_eq!(
+            distinct,
+            [
+                project.join("tests"),
+                project.join("src"),
+                project.join("missing")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scratch_reuse() {
+        // Scoring alternates between queries and names of different lengths, which must not see
+        // the buffers left behind by the previous candidate.
+        let pairs = [
+            ("windowcontroller", "wc"),
+            ("x", "a_much_longer_name"),
+            ("main", "mian"),
+        ];
+        for algorithm in FuzzySearchAlgorithm::ALL {
+            let fresh: Vec<_> = pairs
+                .iter()
+                .map(|(query, name)| score_fuzzy_search(query, name, algorithm))
+                .collect();
+            for _ in 0..2 {
+                for ((query, name), expected) in pairs.iter().zip(&fresh) {
+                    assert_eq!(&score_fuzzy_search(query, name, algorithm), expected);
+                }
+            }
+        }
+        assert_eq!(levenshtein_distance("x", "a_much_longer_name"), 18);
+    }
+
+    #[test]
+    fn test_top_hits() {
+        let mut top = TopHits::new(3);
+        for (score, name) in [(2, "c"), (0, "a"), (3, "d"), (1, "b"), (1, "b2"), (0, "a2")] {
+            top.push((score, name.to_string(), ()));
+        }
+        assert_eq!(top.pushed(), 6);
+        let names: Vec<String> = top
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(_, name, _)| name)
+            .collect();
+        assert_eq!(names, vec!["a", "a2", "b"]);
+    }
+
+    #[test]
+    fn test_levenshtein_distance() {
+        assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+        assert_eq!(levenshtein_distance("", "main"), 4);
+        assert_eq!(levenshtein_distance("main", ""), 4);
+        assert_eq!(levenshtein_distance("main", "main"), 0);
+        // Unlike Damerau-Levenshtein, a transposition costs two edits.
+        assert_eq!(levenshtein_distance("mian", "main"), 2);
+        assert_eq!(levenshtein_distance("café", "cafe"), 1);
+        assert_eq!(
+            score_fuzzy_search("irks", "risk", FuzzySearchAlgorithm::Levenshtein),
+            Ok(3)
+        );
+    }
+
+    #[test]
+    fn test_bitap_errors() {
+        // The fewest errors of the query against any substring of the file name.
+        let substring_distance = |query: &str, file_name: &str| {
+            let chars: Vec<char> = file_name.chars().collect();
+            (0..=chars.len())
+                .flat_map(|start| (start..=chars.len()).map(move |end| (start, end)))
+                .map(|(start, end)| {
+                    let substring: String = chars[start..end].iter().collect();
+                    levenshtein_distance(query, &substring)
+                })
+                .min()
+                .unwrap()
+        };
+        let names = [
+            "main", "domain", "mains", "gain", "readme", "", "mian", "amni",
+        ];
+        for query in ["main", "mian", "rdme", "x", ""] {
+            for name in names {
+                assert_eq!(
+                    bitap_errors(query, name, 4),
+                    Ok(substring_distance(query, name)),
+                    "{} in {}",
+                    query,
+                    name
+                );
+            }
+        }
+        assert_eq!(bitap_errors("main", "readme", 1), Ok(4));
+        assert!(bitap_errors(&"a".repeat(65), "a", 1).is_err());
+        let longest = "a".repeat(64);
+        assert_eq!(bitap_errors(&longest, &longest, 64), Ok(0));
+        assert_eq!(bitap_errors(&longest, "b", 64), Ok(64));
+        assert_eq!(bitap_errors(&longest, &"a".repeat(60), 64), Ok(4));
+        let scorer = BitapScorer { max_errors: 1 };
+        let ranked = rank_candidates(
+            "main",
+            vec![
+                ("domain".to_string(), ()),
+                ("gain".to_string(), ()),
+                ("readme".to_string(), ()),
+            ],
+            &scorer,
+        )
+        .unwrap();
+        let names: Vec<&str> = ranked.iter().map(|(_, name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["domain", "gain"]);
+    }
+
+    #[test]
+    fn test_subsequence_penalty() {
+        assert_eq!(subsequence_penalty("main", "main"), 0);
+        assert_eq!(subsequence_penalty("main", "Main"), 0);
+        assert_eq!(subsequence_penalty("srch", "search"), 6);
+        assert_eq!(subsequence_penalty("xyz", "search"), NOT_A_SUBSEQUENCE);
+        assert_eq!(subsequence_penalty("", "search"), 0);
+        // Word starts are free, so `fb` costs only the gap and the unmatched end.
+        assert_eq!(subsequence_penalty("fb", "fooBar"), 5);
+        assert_eq!(subsequence_penalty("fb", "foobar"), 7);
+        let algorithm = FuzzySearchAlgorithm::Subsequence;
+        let ranked = rank_candidates(
+            "srch",
+            vec![
+                ("scratch".to_string(), ()),
+                ("readme".to_string(), ()),
+                ("search".to_string(), ()),
+                ("source_search".to_string(), ()),
+            ],
+            &algorithm,
+        )
+        .unwrap();
+        let names: Vec<&str> = ranked.iter().map(|(_, name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["search", "source_search", "scratch"]);
+    }
+
+    #[test]
+    fn test_tokens_score() {
+        assert_eq!(tokens_score("user service test", "user_service_test"), 0);
+        assert_eq!(tokens_score("user service test", "UserServiceTest"), 0);
+        assert_eq!(tokens_score("user service test", "user-service-tests"), 1);
+        assert_eq!(tokens_score("user service test", "user_sevice_test"), 2);
+        assert_eq!(
+            tokens_score("user service test", "user_service"),
+            TOKEN_MISS
+        );
+        assert_eq!(tokens_score("test user", "user_service_test"), 1);
+        assert_eq!(tokens_threshold("user service test"), 199);
+        assert_eq!(tokens_threshold("user"), 99);
+        let algorithm = FuzzySearchAlgorithm::Tokens;
+        let ranked = rank_candidates(
+            "user service test",
+            vec![
+                ("account_service".to_string(), ()),
+                ("user_service".to_string(), ()),
+                ("UserServiceTest".to_string(), ()),
+            ],
+            &algorithm,
+        )
+        .unwrap();
+        let names: Vec<&str> = ranked.iter().map(|(_, name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["UserServiceTest", "user_service"]);
+    }
+
+    #[test]
+    fn test_smith_waterman() {
+        let scorer = SmithWatermanScorer::default();
+        let score = |query, name| scorer.score(query, name).unwrap();
+        assert_eq!(score("handler", "legacy_request_handler_v2"), 0);
+        assert_eq!(score("Handler", "handler"), 0);
+        assert_eq!(score("hndler", "handler"), 1);
+        assert_eq!(score("handlr", "handler"), 1);
+        assert_eq!(score("", "handler"), 0);
+        assert_eq!(score("handler", ""), 14);
+        let strict = SmithWatermanScorer {
+            mismatch_penalty: 1,
+            gap_penalty: 3,
+        };
+        // Skipping the `a` now costs more than leaving out the `h`.
+        assert_eq!(strict.score("hndler", "handler"), Ok(2));
+        assert_eq!(scorer.threshold("handler"), 6);
+        let candidates = ["hdr", "handle", "legacy_request_handler_v2", "handler"]
+            .iter()
+            .map(|name| (name.to_string(), ()))
+            .collect();
+        let ranked: Vec<String> = rank_candidates("handler", candidates, &scorer)
+            .unwrap()
+            .into_iter()
+            .map(|(_, name, _)| name)
+            .collect();
+        assert_eq!(ranked, ["legacy_request_handler_v2", "handler", "handle"]);
+    }
+
+    #[test]
+    fn test_trigram_score() {
+        let mut main = Vec::new();
+        trigrams(&['m', 'a', 'i', 'n'], &mut main);
+        assert_eq!(
+            main,
+            [
+                [' ', ' ', 'm'],
+                [' ', 'm', 'a'],
+                ['a', 'i', 'n'],
+                ['i', 'n', ' '],
+                ['m', 'a', 'i']
+            ]
+        );
+        assert_eq!(trigram_score("main", "main"), 0);
+        assert_eq!(trigram_score("Main", "main_window"), 1);
+        assert_eq!(trigram_score("mian", "main"), 80);
+        // Only the trigrams joining the two words are missing.
+        assert_eq!(
+            trigram_score("windowcontroller", "main_window_controller"),
+            12
+        );
+        assert_eq!(trigram_score("", ""), 0);
+        let candidates = ["readme", "main_window_controller", "windowcontroler"]
+            .iter()
+            .map(|name| (name.to_string(), ()))
+            .collect();
+        let ranked: Vec<String> = rank_candidates(
+            "windowcontroller",
+            candidates,
+            &FuzzySearchAlgorithm::Trigram,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|(_, name, _)| name)
+        .collect();
+        assert_eq!(ranked, ["main_window_controller", "windowcontroler"]);
+    }
+
+    #[test]
+    fn test_case_normalization() {
+        let scores = |query: &str, scorer: &dyn ScoreFunction| -> Vec<u32> {
+            let candidates = vec![("Foo".to_string(), ()), ("FooBar".to_string(), ())];
+            rank_candidates(query, candidates, scorer)
+                .unwrap()
+                .into_iter()
+                .map(|(score, _, _)| score)
+                .collect()
+        };
+        assert_eq!(scores("foo", &FuzzySearchAlgorithm::Levenshtein), [0]);
+        assert_eq!(scores("FOO", &FuzzySearchAlgorithm::Bitap), [0, 0]);
+        let case_sensitive = CaseSensitive(Box::new(FuzzySearchAlgorithm::Levenshtein));
+        assert_eq!(scores("foo", &case_sensitive), [1]);
+        let loose = WithThreshold(Box::new(case_sensitive), Threshold::Ratio(0.7));
+        assert_eq!(loose.threshold("foo"), 3);
+        assert_eq!(scores("foo", &loose), [1, 3]);
+        let strict = WithThreshold(Box::new(loose), Threshold::Absolute(0));
+        assert_eq!(scores("foo", &strict), []);
+        // Scorers comparing words ignoring case still see the camelCase words of names.
+        assert_eq!(scores("bar foo", &FuzzySearchAlgorithm::Tokens), [0]);
+        let mut buffer = String::new();
+        assert_eq!(normalize_name("FooBar", false, &mut buffer), "foobar");
+        assert_eq!(normalize_name("FooBar", true, &mut buffer), "FooBar");
+    }
+
+    #[test]
+    fn test_similarity() {
+        let similarity = |scorer: &dyn ScoreFunction, query: &str, name: &str| {
+            let score = scorer.score(query, name).unwrap();
+            scorer.similarity(query, name, score)
+        };
+        let levenshtein = FuzzySearchAlgorithm::Levenshtein;
+        assert_eq!(similarity(&levenshtein, "main", "main"), 100);
+        assert_eq!(similarity(&levenshtein, "main", "mainx"), 80);
+        // The same distance is less similar in a shorter query.
+        assert_eq!(similarity(&levenshtein, "mian", "main"), 50);
+        assert_eq!(similarity(&levenshtein, "controler", "controller"), 90);
+        let bitap = FuzzySearchAlgorithm::Bitap;
+        assert_eq!(similarity(&bitap, "main", "my_mainx"), 100);
+        let smith_waterman = FuzzySearchAlgorithm::SmithWaterman;
+        assert_eq!(similarity(&smith_waterman, "handler", "old_handler"), 100);
+        let tokens = FuzzySearchAlgorithm::Tokens;
+        assert_eq!(similarity(&tokens, "user test", "user"), 50);
+        let jaro_winkler = FuzzySearchAlgorithm::JaroWinkler;
+        assert_eq!(similarity(&jaro_winkler, "main", "xyz"), 0);
+        assert_eq!(edit_similarity("mian", "main", 1), 75);
+        assert_eq!(edit_similarity("abc", "abd", 1), 66);
+        assert_eq!(edit_similarity("", "", 0), 100);
+    }
+
+    #[test]
+    fn test_full_path() {
+        let scorer = FullPath(Box::new(FuzzySearchAlgorithm::DamerauLevenshtein));
+        assert_eq!(scorer.score("handlers/user", "api/handlers/user.rs"), Ok(0));
+        assert_eq!(scorer.score("api/usr", "api/handlers/user.rs"), Ok(1));
+        // The last segment of the query matching a directory costs a little.
+        assert_eq!(scorer.score("user", "user/mod.rs"), Ok(1));
+        // Segments are matched in order.
+        let reversed = scorer.score("user/api", "api/handlers/user.rs");
+        assert_eq!(reversed, Ok(u32::MAX));
+        assert_eq!(scorer.score("ihandlers", "api/handlers.rs"), Ok(1));
+        assert_eq!(scorer.threshold("handlers/user"), 5);
+
+        let mut paths = PathArena::new();
+        for path in ["./web/user.rs", "./api/handlers/user.rs", "./api/model.rs"] {
+            paths.push(&candidate_name_of_path(path), path);
+        }
+        let ranked = rank_full_paths("api/user", &paths, paths.ids().collect(), &scorer).unwrap();
+        let ranked: Vec<&str> = ranked.iter().map(|(_, id)| paths.path(*id)).collect();
+        assert_eq!(ranked, ["./api/handlers/user.rs"]);
+        // Every segment of the query stays prepared from one candidate to the next.
+        let scorer = FullPath(Box::new(FuzzySearchAlgorithm::Levenshtein));
+        scorer
+            .score("api/handlers/user", "api/handlers/user.rs")
+            .unwrap();
+        SCRATCH.with(|scratch| {
+            let queries = &scratch.borrow().queries;
+            for segment in ["api", "handlers", "user"] {
+                assert!(queries.iter().any(|query| query.text == segment));
+            }
+        });
+    }
+
+    #[test]
+    fn test_unicode_normalization() {
+        let (composed, decomposed) = ("caf\u{e9}", "cafe\u{301}");
+        for algorithm in [
+            FuzzySearchAlgorithm::Levenshtein,
+            FuzzySearchAlgorithm::DamerauLevenshtein,
+            FuzzySearchAlgorithm::Subsequence,
+            FuzzySearchAlgorithm::Tokens,
+        ] {
+            assert_eq!(score_fuzzy_search(composed, decomposed, algorithm), Ok(0));
+            assert_eq!(score_fuzzy_search(decomposed, composed, algorithm), Ok(0));
+        }
+        let mut buffer = String::new();
+        assert_eq!(normalize_name("Cafe\u{301}", false, &mut buffer), composed);
+        assert_eq!(normalize_name("cafe\u{301}", true, &mut buffer), composed);
+        assert!(matches!(compose(composed), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_rank_files() {
+        let mut paths = PathArena::new();
+        for index in 0..3 * PARALLEL_CHUNK {
+            let name = format!(
+                "{}_{}",
+                ["main", "mains", "gain", "other"][index % 4],
+                index % 7
+            );
+            paths.push(&name, &format!("./src/{}.rs", name));
+        }
+        let ids: Vec<PathId> = paths.ids().collect();
+        // A pool of its own scores on several threads even on machines with a single CPU.
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        for query in ["main_3", "gain"] {
+            let algorithm = FuzzySearchAlgorithm::DamerauLevenshtein;
+            assert_eq!(
+                pool.install(|| rank_files(query, &paths, ids.clone(), &algorithm)),
+                rank_paths(query, &paths, ids.clone(), &algorithm)
+            );
+        }
+    }
+
+    #[test]
+    fn test_has_upper_case() {
+        assert!(!has_upper_case("main window", false));
+        assert!(has_upper_case("mainWindow", false));
+        assert!(has_upper_case("Ärger", false));
+        // Escapes of regexes aren't letters the query matches.
+        assert!(!has_upper_case(r"\Wmain\pL\p{Lu}.*", true));
+        assert!(has_upper_case(r"\WMain", true));
+        assert!(has_upper_case(r"\W", false));
+    }
+
+    #[test]
+    fn test_phonetic_score() {
+        for (misspelled, word, key) in [
+            ("kolor", "color", "KLR"),
+            ("fone", "phone", "FN"),
+            ("nite", "night", "NT"),
+            ("smyth", "smith", "SM0"),
+            ("kat", "cat", "KT"),
+            ("rite", "write", "RT"),
+            ("nolij", "knowledge", "NLJ"),
+        ] {
+            assert_eq!(metaphone(misspelled), key, "{}", misspelled);
+            assert_eq!(metaphone(word), key, "{}", word);
+        }
+        assert_eq!(metaphone("Xavier"), "SFR");
+        assert_eq!(metaphone("accent"), "AKSNT");
+        assert_eq!(phonetic_score("kolor", "color"), 0);
+        // One word of the name is left unused.
+        assert_eq!(phonetic_score("kolor", "color_utils"), 1);
+        assert_eq!(phonetic_score("kolor utils", "ColorUtils"), 0);
+        assert_eq!(phonetic_score("kolor", "main"), TOKEN_MISS + 1);
+        let candidates = ["main", "color_utils", "color"]
+            .iter()
+            .map(|name| (name.to_string(), ()))
+            .collect();
+        let ranked: Vec<String> = rank_candidates("kolor", candidates, &PhoneticScorer)
+            .unwrap()
+            .into_iter()
+            .map(|(_, name, _)| name)
+            .collect();
+        assert_eq!(ranked, ["color", "color_utils"]);
+    }
+
+    #[test]
+    fn test_jaro_winkler() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-3;
+        assert!(close(jaro_winkler_similarity("martha", "marhta"), 0.961));
+        assert!(close(jaro_winkler_similarity("dixon", "dicksonx"), 0.813));
+        assert!(close(jaro_winkler_similarity("", ""), 1.0));
+        assert!(close(jaro_winkler_similarity("main", ""), 0.0));
+        assert_eq!(jaro_winkler_score("main", "main"), 0);
+        assert_eq!(jaro_winkler_score("main", "mains"), 4);
+        assert_eq!(jaro_winkler_score("main", "xyz"), 100);
+        let algorithm = FuzzySearchAlgorithm::JaroWinkler;
+        let ranked = rank_candidates(
+            "main",
+            vec![
+                ("gain".to_string(), ()),
+                ("readme".to_string(), ()),
+                ("mains".to_string(), ()),
+            ],
+            &algorithm,
+        )
+        .unwrap();
+        let names: Vec<&str> = ranked.iter().map(|(_, name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["mains", "gain"]);
+    }
+
     #[test]
     fn test_damerau_levenshtein_distance() {
+        assert_eq!(damerau_levenshtein_distance("irks", "risk"), 2);
+        assert_eq!(damerau_levenshtein_distance("geeks", "forgeeks"), 3);
+        // Flags, emoji with modifiers and accented letters are single graphemes.
         assert_eq!(
-            damerau_levenshtein_distance("irks".to_string(), "risk".to_string()),
-            2
+            damerau_levenshtein_distance("\u{1f1eb}\u{1f1f7}", "\u{1f1e9}\u{1f1ea}"),
+            1
         );
         assert_eq!(
-            damerau_levenshtein_distance("geeks".to_string(), "forgeeks".to_string()),
-            3
+            damerau_levenshtein_distance("\u{1f44d}\u{1f3fd}.rs", "\u{1f44d}.rs"),
+            1
         );
+        assert_eq!(damerau_levenshtein_distance("ae\u{301}", "e\u{301}a"), 1);
+        assert_eq!(damerau_levenshtein_distance("caf\u{e9}s", "cafes"), 1);
+        assert_eq!(damerau_levenshtein_distance("", "main"), 4);
+        assert_eq!(damerau_levenshtein_distance("main", ""), 4);
+        assert_eq!(damerau_levenshtein_distance("abcdef", "badcfe"), 3);
+        // Optimal string alignment doesn't edit a transposed pair again.
+        assert_eq!(damerau_levenshtein_distance("ca", "abc"), 3);
+        let long_name = "a_rather_long_file_name_with_many_words".repeat(4);
+        assert_eq!(damerau_levenshtein_distance(&long_name, &long_name), 0);
     }
 }
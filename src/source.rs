@@ -0,0 +1,500 @@
+use crate::arena::{PathArena, PathId, PathInterner};
+use crate::filter::{CandidateEntry, CandidateFilter, FilterChain};
+use crate::search::{candidate_name_of_path, DirectoryWalk, WalkOptions};
+use crate::throttle::IoThrottle;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// A provider of search candidates, such as a directory walk or a list of paths read from stdin.
+///
+/// Every source yields the same `(name, full_path)` tuples, so the ranking code doesn't need to know
+/// where candidates come from.
+pub trait CandidateSource {
+    /// Collects the candidates of the source.
+    ///
+    /// # Returns
+    ///
+    /// A vector of tuples containing the name matched against the query and the full path, or an
+    /// error message if the source could not be read.
+    fn candidates(&mut self) -> Result<Vec<(String, String)>, String>;
+
+    /// Passes the candidates of the source to `visit` one at a time. Sources that can produce
+    /// candidates incrementally override this, so they never have to be held in memory together.
+    fn visit_candidates(
+        &mut self,
+        visit: &mut dyn FnMut(&str, &str) -> Result<(), String>,
+    ) -> Result<(), String> {
+        for (name, full_path) in self.candidates()? {
+            visit(&name, &full_path)?;
+        }
+        Ok(())
+    }
+
+    /// Problems met while collecting candidates that didn't stop the source, e.g. unreadable entries.
+    fn warnings(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Candidates from walking a directory, narrowed by a filter chain and optionally throttled.
+pub struct WalkSource<'a> {
+    pub root: PathBuf,
+    pub filters: &'a FilterChain,
+    pub throttle: Option<&'a IoThrottle>,
+    /// Whether to follow symbolic links, and which entries are candidates.
+    pub options: WalkOptions,
+    unreadable: Vec<String>,
+}
+
+impl<'a> WalkSource<'a> {
+    pub fn new(root: PathBuf, filters: &'a FilterChain, throttle: Option<&'a IoThrottle>) -> Self {
+        WalkSource {
+            root,
+            filters,
+            throttle,
+            options: WalkOptions::default(),
+            unreadable: Vec::new(),
+        }
+    }
+
+    /// Walks with the given options instead of the default ones, which yield only files.
+    pub fn with_options(mut self, options: WalkOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl CandidateSource for WalkSource<'_> {
+    fn candidates(&mut self) -> Result<Vec<(String, String)>, String> {
+        let mut files = Vec::new();
+        self.visit_candidates(&mut |name, full_path| {
+            files.push((name.to_string(), full_path.to_string()));
+            Ok(())
+        })?;
+        Ok(files)
+    }
+
+    fn visit_candidates(
+        &mut self,
+        visit: &mut dyn FnMut(&str, &str) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut walk = DirectoryWalk::new(&self.root, self.filters, self.throttle, self.options);
+        let result = walk.try_for_each(|(name, full_path)| visit(&name, &full_path));
+        self.unreadable.append(&mut walk.unreadable);
+        result
+    }
+
+    fn warnings(&self) -> Vec<String> {
+        self.unreadable.clone()
+    }
+}
+
+/// The candidates of several sources one after another, e.g. the walks of several directories, as
+/// a single source.
+pub struct ChainedSource<'a> {
+    pub sources: Vec<Box<dyn CandidateSource + 'a>>,
+}
+
+impl CandidateSource for ChainedSource<'_> {
+    fn candidates(&mut self) -> Result<Vec<(String, String)>, String> {
+        let mut files = Vec::new();
+        self.visit_candidates(&mut |name, full_path| {
+            files.push((name.to_string(), full_path.to_string()));
+            Ok(())
+        })?;
+        Ok(files)
+    }
+
+    fn visit_candidates(
+        &mut self,
+        visit: &mut dyn FnMut(&str, &str) -> Result<(), String>,
+    ) -> Result<(), String> {
+        for source in &mut self.sources {
+            source.visit_candidates(visit)?;
+        }
+        Ok(())
+    }
+
+    fn warnings(&self) -> Vec<String> {
+        self.sources
+            .iter()
+            .flat_map(|source| source.warnings())
+            .collect()
+    }
+}
+
+/// Passes on the candidates of another source, and hands the ones found since the last batch to
+/// `progress` once per `interval`, e.g. to show results while a large tree is walked. The ones
+/// found after the last batch aren't handed on, since the source is complete by then.
+pub struct ProgressSource<'a> {
+    pub inner: Box<dyn CandidateSource + 'a>,
+    pub interval: Duration,
+    /// Receives each batch of candidates, and stops the source by returning an error.
+    pub progress: &'a mut Progress<'a>,
+}
+
+/// Receives the candidates found since the last batch of a [`ProgressSource`].
+pub type Progress<'a> = dyn FnMut(&[(String, String)]) -> Result<(), String> + 'a;
+
+impl CandidateSource for ProgressSource<'_> {
+    fn candidates(&mut self) -> Result<Vec<(String, String)>, String> {
+        let mut files = Vec::new();
+        self.visit_candidates(&mut |name, full_path| {
+            files.push((name.to_string(), full_path.to_string()));
+            Ok(())
+        })?;
+        Ok(files)
+    }
+
+    fn visit_candidates(
+        &mut self,
+        visit: &mut dyn FnMut(&str, &str) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut batch: Vec<(String, String)> = Vec::new();
+        let mut last_batch = Instant::now();
+        let progress = &mut self.progress;
+        let interval = self.interval;
+        self.inner.visit_candidates(&mut |name, full_path| {
+            visit(name, full_path)?;
+            batch.push((name.to_string(), full_path.to_string()));
+            if last_batch.elapsed() >= interval {
+                progress(&batch)?;
+                batch.clear();
+                last_batch = Instant::now();
+            }
+            Ok(())
+        })
+    }
+
+    fn warnings(&self) -> Vec<String> {
+        self.inner.warnings()
+    }
+}
+
+/// Candidates from a list of file paths, one per line, e.g. piped through stdin.
+pub struct LineSource<'a, R: BufRead> {
+    pub reader: R,
+    pub filters: &'a FilterChain,
+}
+
+impl<R: BufRead> CandidateSource for LineSource<'_, R> {
+    fn candidates(&mut self) -> Result<Vec<(String, String)>, String> {
+        let mut files = Vec::new();
+        self.visit_candidates(&mut |name, full_path| {
+            files.push((name.to_string(), full_path.to_string()));
+            Ok(())
+        })?;
+        Ok(files)
+    }
+
+    fn visit_candidates(
+        &mut self,
+        visit: &mut dyn FnMut(&str, &str) -> Result<(), String>,
+    ) -> Result<(), String> {
+        for line in (&mut self.reader).lines() {
+            let line = line.map_err(|error| format!("Failed to read paths: {}", error))?;
+            let full_path = line.trim_end_matches('\r');
+            if full_path.is_empty() {
+                continue;
+            }
+            if accepts_file(self.filters, full_path) {
+                visit(&candidate_name_of_path(full_path), full_path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Applies a filter chain to a file path that wasn't found by walking, e.g. one listed by a tool.
+pub fn accepts_file(filters: &FilterChain, full_path: &str) -> bool {
+    let path = Path::new(full_path);
+    filters.accepts(&CandidateEntry {
+        path,
+        relative_path: path.strip_prefix(".").unwrap_or(path),
+        is_dir: false,
+        throttle: None,
+    })
+}
+
+/// The labels of the sources each candidate was found in, keyed by full path.
+pub type SourceLabels = BTreeMap<String, Vec<String>>;
+
+/// The most sources that can be merged, one bit of [`MergedCandidates`]' source sets each.
+pub const MAX_SOURCES: usize = u64::BITS as usize;
+
+/// The combined candidates of several sources.
+pub struct MergedCandidates {
+    /// The candidates, without paths already found by an earlier source.
+    pub paths: PathArena,
+    /// The labels of the sources, in order of precedence.
+    pub source_names: Vec<String>,
+    /// The sources each candidate was found in, as bits indexing `source_names`.
+    found_in: Vec<u64>,
+    /// The warnings of all sources.
+    pub warnings: Vec<String>,
+    /// The number of candidates handed to a [`Spill`] instead of being kept.
+    pub spilled: usize,
+}
+
+impl MergedCandidates {
+    /// Returns the labels of the sources a candidate was found in.
+    pub fn labels(&self, id: PathId) -> Vec<&str> {
+        let found_in = self.found_in[id.index()];
+        self.source_names
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| found_in & (1 << index) != 0)
+            .map(|(_, name)| name.as_str())
+            .collect()
+    }
+
+    /// Returns the labels of the sources of every candidate, keyed by full path.
+    pub fn all_labels(&self) -> SourceLabels {
+        self.paths
+            .ids()
+            .map(|id| {
+                let labels = self.labels(id).into_iter().map(str::to_string).collect();
+                (self.paths.path(id).to_string(), labels)
+            })
+            .collect()
+    }
+}
+
+/// Where [`merge_sources_spilling`] hands the collected candidates once they take more memory than
+/// allowed.
+pub struct Spill<'a> {
+    /// The estimated number of bytes the collected candidates may take.
+    pub limit: usize,
+    /// Receives the collected candidates whenever they exceed the limit.
+    pub sink: &'a mut dyn FnMut(PathArena) -> Result<(), String>,
+}
+
+/// A rough estimate of the memory a merged candidate takes: its path in the arena plus its span,
+/// its entry in the deduplication index and its set of sources.
+fn estimated_size(full_path: &str) -> usize {
+    const ENTRY_OVERHEAD: usize = 4 * std::mem::size_of::<usize>() + 32;
+    full_path.len() + ENTRY_OVERHEAD
+}
+
+/// Collects the candidates of several labelled sources, dropping paths already found by an earlier
+/// source.
+///
+/// # Arguments
+///
+/// * `sources` - The sources and their labels, in order of precedence.
+///
+/// # Returns
+///
+/// The merged candidates, or the first error of a source.
+pub fn merge_sources(
+    sources: Vec<(String, Box<dyn CandidateSource + '_>)>,
+) -> Result<MergedCandidates, String> {
+    merge_sources_spilling(sources, None)
+}
+
+/// Collects the candidates of several labelled sources like [`merge_sources`], optionally bounding
+/// the memory they take.
+///
+/// Whenever the collected candidates exceed the limit of the spill, they are handed to its sink
+/// and collection starts over. Paths are only deduplicated, and labelled, among the candidates
+/// collected since the last spill.
+///
+/// # Arguments
+///
+/// * `sources` - The sources and their labels, in order of precedence, at most [`MAX_SOURCES`].
+/// * `spill` - When provided, where candidates go once they take more memory than allowed.
+///
+/// # Returns
+///
+/// The candidates collected since the last spill, or the first error of a source or the sink.
+pub fn merge_sources_spilling(
+    sources: Vec<(String, Box<dyn CandidateSource + '_>)>,
+    mut spill: Option<Spill>,
+) -> Result<MergedCandidates, String> {
+    if sources.len() > MAX_SOURCES {
+        return Err(format!("At most {} sources can be searched", MAX_SOURCES));
+    }
+    let mut merged = MergedCandidates {
+        paths: PathArena::new(),
+        source_names: Vec::new(),
+        found_in: Vec::new(),
+        warnings: Vec::new(),
+        spilled: 0,
+    };
+    let mut interner = PathInterner::new();
+    let mut collected_size = 0;
+    for (index, (label, mut source)) in sources.into_iter().enumerate() {
+        merged.source_names.push(label);
+        source.visit_candidates(&mut |name, full_path| {
+            let (id, added) = interner.intern(name, full_path);
+            if added {
+                merged.found_in.push(0);
+                collected_size += estimated_size(full_path);
+            }
+            merged.found_in[id.index()] |= 1 << index;
+            if let Some(spill) = spill.as_mut().filter(|spill| collected_size > spill.limit) {
+                let paths = interner.take();
+                merged.spilled += paths.len();
+                (spill.sink)(paths)?;
+                merged.found_in.clear();
+                collected_size = 0;
+            }
+            Ok(())
+        })?;
+        merged.warnings.extend(source.warnings());
+    }
+    merged.paths = interner.take();
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::ExtensionFilter;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_line_source() {
+        let filters = FilterChain::new().with(ExtensionFilter {
+            exclude: BTreeSet::from(["md".to_string()]),
+            focus: BTreeSet::new(),
+        });
+        let mut source = LineSource {
+            reader: "src/main.rs\r\n\nREADME.md\n./lib.rs\n".as_bytes(),
+            filters: &filters,
+        };
+        assert_eq!(
+            source.candidates(),
+            Ok(vec![
+                ("main".to_string(), "src/main.rs".to_string()),
+                ("lib".to_string(), "./lib.rs".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_progress_source() {
+        let filters = FilterChain::new();
+        let mut batches: Vec<Vec<String>> = Vec::new();
+        let mut source = ProgressSource {
+            inner: Box::new(LineSource {
+                reader: "a.rs\nb.rs\nc.rs\n".as_bytes(),
+                filters: &filters,
+            }),
+            interval: Duration::ZERO,
+            progress: &mut |batch| {
+                batches.push(batch.iter().map(|(name, _)| name.clone()).collect());
+                Ok(())
+            },
+        };
+        assert_eq!(source.candidates().unwrap().len(), 3);
+        drop(source);
+        assert_eq!(batches, vec![vec!["a"], vec!["b"], vec!["c"]]);
+        let mut source = ProgressSource {
+            inner: Box::new(LineSource {
+                reader: "a.rs\nb.rs\n".as_bytes(),
+                filters: &filters,
+            }),
+            interval: Duration::ZERO,
+            progress: &mut |_| Err("Stopped".to_string()),
+        };
+        assert_eq!(source.candidates(), Err("Stopped".to_string()));
+    }
+
+    #[test]
+    fn test_chained_source() {
+        let filters = FilterChain::new();
+        let mut source = ChainedSource {
+            sources: vec![
+                Box::new(LineSource {
+                    reader: "src/main.rs\n".as_bytes(),
+                    filters: &filters,
+                }),
+                Box::new(LineSource {
+                    reader: "tests/cli.rs\n".as_bytes(),
+                    filters: &filters,
+                }),
+            ],
+        };
+        assert_eq!(
+            source.candidates(),
+            Ok(vec![
+                ("main".to_string(), "src/main.rs".to_string()),
+                ("cli".to_string(), "tests/cli.rs".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_merge_sources() {
+        let filters = FilterChain::new();
+        let sources: Vec<(String, Box<dyn CandidateSource>)> = vec![
+            (
+                "fs".to_string(),
+                Box::new(LineSource {
+                    reader: "./src/main.rs\n./build.rs\n".as_bytes(),
+                    filters: &filters,
+                }),
+            ),
+            (
+                "git".to_string(),
+                Box::new(LineSource {
+                    reader: "src/main.rs\nsrc/lib.rs\n".as_bytes(),
+                    filters: &filters,
+                }),
+            ),
+        ];
+        let merged = merge_sources(sources).unwrap();
+        assert_eq!(
+            merged
+                .paths
+                .ids()
+                .map(|id| merged.paths.to_owned_candidate(id))
+                .collect::<Vec<_>>(),
+            vec![
+                ("main".to_string(), "./src/main.rs".to_string()),
+                ("build".to_string(), "./build.rs".to_string()),
+                ("lib".to_string(), "src/lib.rs".to_string()),
+            ]
+        );
+        let labels = merged.all_labels();
+        assert_eq!(labels["./src/main.rs"], vec!["fs", "git"]);
+        assert_eq!(labels["src/lib.rs"], vec!["git"]);
+        assert_eq!(merged.spilled, 0);
+    }
+
+    #[test]
+    fn test_merge_sources_spilling() {
+        let filters = FilterChain::new();
+        let sources: Vec<(String, Box<dyn CandidateSource>)> = vec![(
+            "stdin".to_string(),
+            Box::new(LineSource {
+                reader: "a.rs\nb.rs\nc.rs\nd.rs\ne.rs\n".as_bytes(),
+                filters: &filters,
+            }),
+        )];
+        let mut spills: Vec<Vec<String>> = Vec::new();
+        let merged = merge_sources_spilling(
+            sources,
+            Some(Spill {
+                limit: 2 * estimated_size("a.rs") - 1,
+                sink: &mut |paths| {
+                    spills.push(paths.ids().map(|id| paths.name(id).to_string()).collect());
+                    Ok(())
+                },
+            }),
+        )
+        .unwrap();
+        assert_eq!(spills, vec![vec!["a", "b"], vec!["c", "d"]]);
+        assert_eq!(merged.spilled, 4);
+        assert_eq!(merged.paths.len(), 1);
+        let last = merged.paths.ids().next().unwrap();
+        assert_eq!(
+            merged.paths.to_owned_candidate(last),
+            ("e".to_string(), "e.rs".to_string())
+        );
+        assert_eq!(merged.all_labels()["e.rs"], vec!["stdin"]);
+    }
+}
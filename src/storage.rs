@@ -0,0 +1,103 @@
+use sha2::{Digest, Sha256};
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Returns the directory where fuzzy-ls keeps persistent data. It is not created here, but by
+/// whatever writes to it first.
+///
+/// Uses `$XDG_DATA_HOME/fuzzy-ls` or `~/.local/share/fuzzy-ls` on Unix and `%APPDATA%\fuzzy-ls` on Windows.
+///
+/// # Returns
+///
+/// The data directory, or an error message if no suitable location could be determined.
+pub fn data_dir() -> Result<PathBuf, String> {
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    Ok(base
+        .ok_or("Could not determine a data directory".to_string())?
+        .join("fuzzy-ls"))
+}
+
+/// Returns the directory where fuzzy-ls looks for user configuration and plugins.
+///
+/// Uses `$XDG_CONFIG_HOME/fuzzy-ls` or `~/.config/fuzzy-ls` on Unix and `%APPDATA%\fuzzy-ls` on Windows.
+/// Unlike [`data_dir`], the directory is not created.
+pub fn config_dir() -> Result<PathBuf, String> {
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    Ok(base
+        .ok_or("Could not determine a config directory".to_string())?
+        .join("fuzzy-ls"))
+}
+
+/// Returns the home directory of the current user, from `$HOME` on Unix and `%USERPROFILE%` on
+/// Windows, if it is known.
+pub fn home_dir() -> Option<PathBuf> {
+    env::var_os(if cfg!(target_os = "windows") {
+        "USERPROFILE"
+    } else {
+        "HOME"
+    })
+    .map(PathBuf::from)
+}
+
+/// Returns a file-name-safe identifier for the current project.
+///
+/// The project is the enclosing git repository, or the current directory outside of one.
+pub fn project_id() -> String {
+    let root = crate::git::repository_root()
+        .map(PathBuf::from)
+        .or_else(|_| env::current_dir().map_err(|error| error.to_string()))
+        .unwrap_or_default();
+    let root = root.canonicalize().unwrap_or(root);
+    project_id_of(&root)
+}
+
+/// The identifier of the project rooted at a directory: the directory's name, for people browsing
+/// the stores, followed by a hash of its whole path, so distinct roots never share an identifier.
+fn project_id_of(root: &Path) -> String {
+    let name: String = root
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let hash: String = Sha256::digest(root.to_string_lossy().as_bytes())[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("{}-{}", name, hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_id() {
+        let id = project_id_of(Path::new("/a/b c"));
+        assert!(id.starts_with("b_c-"));
+        assert_eq!(id.len(), "b_c-".len() + 16);
+        assert_ne!(
+            project_id_of(Path::new("/a/b_c")),
+            project_id_of(Path::new("/a/b/c"))
+        );
+    }
+}
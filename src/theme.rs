@@ -0,0 +1,251 @@
+use clap::ValueEnum;
+use std::env;
+use tui::style::{Color, Modifier, Style};
+
+/// The color theme requested on the command line.
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum ThemeChoice {
+    /// Detect the terminal background and pick the matching theme.
+    #[default]
+    Auto,
+    /// Colors for dark terminal backgrounds.
+    Dark,
+    /// Colors for light terminal backgrounds.
+    Light,
+}
+
+/// Brightness of the terminal background.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Background {
+    Dark,
+    Light,
+}
+
+/// Colors used by the results UI.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// Style of file names that match the query exactly.
+    pub exact: Style,
+    /// Style of file names that match the query approximately.
+    pub fuzzy: Style,
+    /// Style of the selected row's file name.
+    pub selected: Style,
+    /// Style of the table header.
+    pub header: Style,
+    /// Scale applied to the score gradient so it stays readable on the background.
+    heat_brightness: f64,
+}
+
+impl Theme {
+    /// The theme for dark terminal backgrounds.
+    pub fn dark() -> Theme {
+        Theme {
+            exact: Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+            fuzzy: Style::default().fg(Color::Blue),
+            selected: Style::default().bg(Color::Yellow).fg(Color::Black),
+            header: Style::default().add_modifier(Modifier::BOLD),
+            heat_brightness: 1.0,
+        }
+    }
+
+    /// The theme for light terminal backgrounds.
+    pub fn light() -> Theme {
+        Theme {
+            exact: Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+            fuzzy: Style::default().fg(Color::Blue),
+            selected: Style::default().bg(Color::Blue).fg(Color::White),
+            header: Style::default().add_modifier(Modifier::BOLD),
+            heat_brightness: 0.65,
+        }
+    }
+
+    /// Resolves the requested theme, detecting the terminal background for [`ThemeChoice::Auto`].
+    ///
+    /// Detection first looks at the `COLORFGBG` environment variable and then queries the terminal
+    /// for its background color, which requires raw mode to be enabled. Dark is assumed otherwise.
+    pub fn resolve(choice: ThemeChoice) -> Theme {
+        let background = match choice {
+            ThemeChoice::Dark => Background::Dark,
+            ThemeChoice::Light => Background::Light,
+            ThemeChoice::Auto => env::var("COLORFGBG")
+                .ok()
+                .and_then(|value| background_from_colorfgbg(&value))
+                .or_else(query_background)
+                .unwrap_or(Background::Dark),
+        };
+        match background {
+            Background::Dark => Theme::dark(),
+            Background::Light => Theme::light(),
+        }
+    }
+
+    /// Maps a match quality in `0.0..=1.0` onto a red → yellow → green gradient readable on the background.
+    pub fn heat_color(&self, quality: f64) -> Color {
+        let quality = quality.clamp(0.0, 1.0);
+        let (red, green) = if quality < 0.5 {
+            (255.0, 510.0 * quality)
+        } else {
+            (510.0 * (1.0 - quality), 255.0)
+        };
+        Color::Rgb(
+            (red * self.heat_brightness) as u8,
+            (green * self.heat_brightness) as u8,
+            0,
+        )
+    }
+}
+
+/// Interprets `COLORFGBG` (e.g. `15;0`), whose last component is the ANSI index of the background.
+fn background_from_colorfgbg(value: &str) -> Option<Background> {
+    let index: u8 = value.rsplit(';').next()?.trim().parse().ok()?;
+    match index {
+        7 | 9..=15 => Some(Background::Light),
+        0..=6 | 8 => Some(Background::Dark),
+        _ => None,
+    }
+}
+
+/// Parses the reply to an OSC 11 query, e.g. `\x1b]11;rgb:ffff/ffff/ffff\x07`.
+fn parse_osc11_response(response: &str) -> Option<Background> {
+    let start = response.find("rgb:")? + 4;
+    let channels: Vec<f64> = response[start..]
+        .split('/')
+        .take(3)
+        .map(|channel| {
+            let digits: String = channel
+                .chars()
+                .take_while(|c| c.is_ascii_hexdigit())
+                .collect();
+            let value = u32::from_str_radix(&digits, 16).ok()?;
+            let max = 16u32.checked_pow(digits.len() as u32)?.checked_sub(1)?;
+            Some(value as f64 / max.max(1) as f64)
+        })
+        .collect::<Option<Vec<f64>>>()?;
+    if channels.len() != 3 {
+        return None;
+    }
+    let luminance = 0.2126 * channels[0] + 0.7152 * channels[1] + 0.0722 * channels[2];
+    Some(if luminance > 0.5 {
+        Background::Light
+    } else {
+        Background::Dark
+    })
+}
+
+/// Asks the terminal for its background color with an OSC 11 query.
+///
+/// The query is followed by a primary device attributes request, which every terminal answers, so
+/// the reply can be read to completion even when OSC 11 is unsupported. The tty is read without
+/// echo or line buffering until the reply ends or 200ms pass, and its mode is restored before
+/// returning, so nothing is left reading the user's keystrokes.
+#[cfg(unix)]
+fn query_background() -> Option<Background> {
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+    use std::time::{Duration, Instant};
+
+    let mut tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    let fd = tty.as_raw_fd();
+    // SAFETY: `fd` is open for the lifetime of `tty` and `termios` is written by `tcgetattr`.
+    let mut original: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+        return None;
+    }
+    let mut raw = original;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    raw.c_cc[libc::VMIN] = 0;
+    raw.c_cc[libc::VTIME] = 0;
+    // SAFETY: `raw` is a valid termios copied from the tty's own.
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+        return None;
+    }
+
+    let deadline = Instant::now() + Duration::from_millis(200);
+    let mut response: Vec<u8> = Vec::new();
+    let mut complete = false;
+    let sent = tty
+        .write_all(b"\x1b]11;?\x07\x1b[c")
+        .and_then(|_| tty.flush());
+    if sent.is_ok() {
+        let mut buffer = [0u8; 64];
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            let mut poll = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: `poll` points to one valid pollfd.
+            let ready = unsafe { libc::poll(&mut poll, 1, remaining.as_millis() as libc::c_int) };
+            if ready <= 0 {
+                break;
+            }
+            let Ok(read) = tty.read(&mut buffer) else {
+                break;
+            };
+            response.extend_from_slice(&buffer[..read]);
+            if response.last() == Some(&b'c') && is_device_attributes_reply(&response) {
+                complete = true;
+                break;
+            }
+        }
+    }
+    // A reply cut short by the deadline is dropped, so its rest doesn't reach the UI as keys.
+    let restore = if complete {
+        libc::TCSANOW
+    } else {
+        libc::TCSAFLUSH
+    };
+    // SAFETY: `original` is the tty's termios as read above.
+    unsafe { libc::tcsetattr(fd, restore, &original) };
+    if !complete {
+        return None;
+    }
+    parse_osc11_response(&String::from_utf8_lossy(&response))
+}
+
+#[cfg(not(unix))]
+fn query_background() -> Option<Background> {
+    None
+}
+
+/// Whether the buffer ends with a primary device attributes reply such as `\x1b[?62;22c`.
+#[cfg(unix)]
+fn is_device_attributes_reply(response: &[u8]) -> bool {
+    let Some(start) = response.windows(3).rposition(|window| window == b"\x1b[?") else {
+        return false;
+    };
+    response[start + 3..response.len() - 1]
+        .iter()
+        .all(|byte| byte.is_ascii_digit() || *byte == b';')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_background_detection() {
+        assert_eq!(background_from_colorfgbg("15;0"), Some(Background::Dark));
+        assert_eq!(
+            background_from_colorfgbg("0;default;15"),
+            Some(Background::Light)
+        );
+        assert_eq!(
+            parse_osc11_response("\x1b]11;rgb:ffff/ffff/ffff\x07"),
+            Some(Background::Light)
+        );
+        assert_eq!(
+            parse_osc11_response("\x1b]11;rgb:1e/1e/2e\x1b\\"),
+            Some(Background::Dark)
+        );
+        assert_eq!(parse_osc11_response("\x1b[?62;22c"), None);
+    }
+}
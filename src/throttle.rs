@@ -0,0 +1,120 @@
+use std::io;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Delay added after the first failed operation, doubled after every further failure.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(50);
+/// Upper bound of the delay added after failed operations.
+const MAX_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Paces file system operations so that searches on network file systems (NFS, SMB) don't
+/// overload the server: operations are spread out evenly over time, at most a fixed number per
+/// second, and the pace slows down exponentially while operations keep failing.
+///
+/// Walks run one operation at a time, so the throttle limits their rate rather than how many run
+/// at once. Besides the directory reads of a walk, the filters read ignore files and metadata
+/// through [`paced`].
+pub struct IoThrottle {
+    min_interval: Duration,
+    state: Mutex<ThrottleState>,
+}
+
+struct ThrottleState {
+    next_start: Instant,
+    backoff: Duration,
+}
+
+impl IoThrottle {
+    /// Creates a throttle allowing `ops_per_second` operations per second.
+    pub fn new(ops_per_second: usize) -> IoThrottle {
+        let ops_per_second = ops_per_second.clamp(1, u32::MAX as usize) as u32;
+        IoThrottle {
+            min_interval: Duration::from_secs(1) / ops_per_second,
+            state: Mutex::new(ThrottleState {
+                next_start: Instant::now(),
+                backoff: Duration::ZERO,
+            }),
+        }
+    }
+
+    /// Blocks until the next operation may start.
+    pub fn wait(&self) {
+        let mut state = self.state.lock().unwrap();
+        let start = state.next_start.max(Instant::now());
+        state.next_start = start + self.min_interval + state.backoff;
+        drop(state);
+        std::thread::sleep(start.saturating_duration_since(Instant::now()));
+    }
+
+    /// Records the outcome of an operation: failures grow the backoff and a success resets it.
+    pub fn record(&self, succeeded: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.backoff = if succeeded {
+            Duration::ZERO
+        } else {
+            (state.backoff * 2).clamp(INITIAL_BACKOFF, MAX_BACKOFF)
+        };
+    }
+}
+
+/// Runs a file system operation at the pace of a throttle, when there is one, and records its
+/// outcome. A missing file is an answer rather than a failure, so it doesn't slow down the pace.
+pub fn paced<T>(
+    throttle: Option<&IoThrottle>,
+    operation: impl FnOnce() -> io::Result<T>,
+) -> io::Result<T> {
+    let Some(throttle) = throttle else {
+        return operation();
+    };
+    throttle.wait();
+    let result = operation();
+    throttle.record(
+        result
+            .as_ref()
+            .map_or_else(|error| error.kind() == io::ErrorKind::NotFound, |_| true),
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_and_resets() {
+        let throttle = IoThrottle::new(1000);
+        let backoff = |throttle: &IoThrottle| throttle.state.lock().unwrap().backoff;
+        throttle.record(false);
+        assert_eq!(backoff(&throttle), INITIAL_BACKOFF);
+        throttle.record(false);
+        assert_eq!(backoff(&throttle), INITIAL_BACKOFF * 2);
+        for _ in 0..10 {
+            throttle.record(false);
+        }
+        assert_eq!(backoff(&throttle), MAX_BACKOFF);
+        throttle.record(true);
+        assert_eq!(backoff(&throttle), Duration::ZERO);
+        let started = Instant::now();
+        throttle.wait();
+        throttle.wait();
+        let next_start = throttle.state.lock().unwrap().next_start;
+        assert!(next_start >= started + Duration::from_millis(2));
+    }
+
+    #[test]
+    fn test_paced() {
+        let throttle = IoThrottle::new(1000);
+        let backoff = |throttle: &IoThrottle| throttle.state.lock().unwrap().backoff;
+        let missing = paced(Some(&throttle), || {
+            std::fs::metadata("/nonexistent/.gitignore")
+        });
+        assert!(missing.is_err());
+        assert_eq!(backoff(&throttle), Duration::ZERO);
+        let timed_out = paced(Some(&throttle), || -> io::Result<()> {
+            Err(io::ErrorKind::TimedOut.into())
+        });
+        assert!(timed_out.is_err());
+        assert_eq!(backoff(&throttle), INITIAL_BACKOFF);
+        assert_eq!(paced(None, || Ok(7)).unwrap(), 7);
+    }
+}
@@ -0,0 +1,239 @@
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+/// The release feed checked for new versions. `FUZZY_LS_RELEASES_URL` overrides it, e.g. for mirrors.
+const RELEASES_URL: &str = "https://api.github.com/repos/Ashwin-1709/fuzzy-ls/releases/latest";
+/// The release asset listing the SHA-256 checksums of the other assets, in `sha256sum` format.
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+/// A release published on the feed.
+struct Release {
+    version: String,
+    binary_url: String,
+    checksums_url: String,
+}
+
+/// Downloads a URL with curl, which is available on every supported platform, into memory.
+fn download(url: &str) -> Result<Vec<u8>, String> {
+    let mut command = Command::new("curl");
+    command.args(["--fail", "--silent", "--show-error", "--location"]);
+    command.args(["--header", "Accept: application/vnd.github+json"]);
+    let output = command
+        .arg(url)
+        .output()
+        .map_err(|error| format!("Failed to run curl: {}", error))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to download {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// The name of the release asset holding the binary for the running platform, e.g. `fuzzy-ls-x86_64-linux`.
+fn binary_asset_name() -> String {
+    format!(
+        "fuzzy-ls-{}-{}{}",
+        env::consts::ARCH,
+        env::consts::OS,
+        env::consts::EXE_SUFFIX
+    )
+}
+
+/// Parses a version such as `v1.2.0` into its numeric components.
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['.', '-', '+'])
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+/// Reads the latest release from the feed's JSON.
+fn parse_release(feed: &str) -> Result<Release, String> {
+    let release: Value =
+        serde_json::from_str(feed).map_err(|error| format!("Invalid release feed: {}", error))?;
+    let version = release["tag_name"]
+        .as_str()
+        .ok_or("The release feed has no tag_name")?
+        .trim_start_matches('v')
+        .to_string();
+    let asset_url = |name: &str| -> Result<String, String> {
+        release["assets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|asset| asset["name"].as_str() == Some(name))
+            .and_then(|asset| asset["browser_download_url"].as_str())
+            .map(String::from)
+            .ok_or(format!("Release {} has no {} asset", version, name))
+    };
+    Ok(Release {
+        binary_url: asset_url(&binary_asset_name())?,
+        checksums_url: asset_url(CHECKSUMS_ASSET)?,
+        version,
+    })
+}
+
+/// Finds the checksum of an asset in a `sha256sum`-style listing (`<hex digest>  <name>`).
+fn expected_checksum(checksums: &str, asset_name: &str) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let (digest, name) = line.trim().split_once(char::is_whitespace)?;
+        (name.trim().trim_start_matches('*') == asset_name).then(|| digest.to_lowercase())
+    })
+}
+
+/// Replaces the running executable with the verified bytes of the new one.
+///
+/// The bytes are written to a new file next to the current executable, so nothing else can swap
+/// them after they were verified and the final rename stays on one file system. Windows can't
+/// overwrite a running executable but can rename it, so the old binary is moved aside there, and
+/// moved back if the new one can't take its place.
+fn replace_executable(current: &Path, contents: &[u8]) -> Result<(), String> {
+    let staged = current.with_extension("new");
+    let _ = fs::remove_file(&staged);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o755);
+    }
+    let written = options
+        .open(&staged)
+        .and_then(|mut file| file.write_all(contents).and_then(|_| file.sync_all()));
+    if let Err(error) = written {
+        let _ = fs::remove_file(&staged);
+        return Err(format!("Failed to stage the update: {}", error));
+    }
+    let old = current.with_extension("old");
+    if cfg!(target_os = "windows") {
+        let _ = fs::remove_file(&old);
+        if let Err(error) = fs::rename(current, &old) {
+            let _ = fs::remove_file(&staged);
+            return Err(format!(
+                "Failed to move the current executable aside: {}",
+                error
+            ));
+        }
+    }
+    fs::rename(&staged, current).map_err(|error| {
+        let _ = fs::remove_file(&staged);
+        if cfg!(target_os = "windows") {
+            let _ = fs::rename(&old, current);
+        }
+        format!("Failed to replace {}: {}", current.display(), error)
+    })
+}
+
+/// Checks the release feed and, unless `check_only` is set, installs a newer release in place of
+/// the running executable after verifying its SHA-256 checksum.
+///
+/// # Returns
+///
+/// A message describing the outcome, or an error message if any step failed. The executable is left
+/// untouched on errors.
+pub fn self_update(check_only: bool) -> Result<String, String> {
+    let current_version = env!("CARGO_PKG_VERSION");
+    let feed_url = env::var("FUZZY_LS_RELEASES_URL").unwrap_or(RELEASES_URL.to_string());
+    let feed = download(&feed_url)?;
+    let release = parse_release(&String::from_utf8_lossy(&feed))?;
+    if parse_version(&release.version) <= parse_version(current_version) {
+        return Ok(format!("fuzzy-ls {} is up to date.", current_version));
+    }
+    if check_only {
+        return Ok(format!(
+            "fuzzy-ls {} is available (installed: {}). Run `fuzzy-ls self-update` to install it.",
+            release.version, current_version
+        ));
+    }
+
+    let checksums = download(&release.checksums_url)?;
+    let expected = expected_checksum(&String::from_utf8_lossy(&checksums), &binary_asset_name())
+        .ok_or(format!(
+            "{} has no checksum for {}",
+            CHECKSUMS_ASSET,
+            binary_asset_name()
+        ))?;
+    // The binary is kept in memory, so the bytes installed are the ones verified.
+    let contents = download(&release.binary_url)?;
+    let actual: String = Sha256::digest(&contents)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    if actual != expected {
+        return Err(format!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            binary_asset_name(),
+            expected,
+            actual
+        ));
+    }
+    let current = env::current_exe()
+        .and_then(|path| path.canonicalize())
+        .map_err(|error| format!("Failed to locate the running executable: {}", error))?;
+    replace_executable(&current, &contents)?;
+    Ok(format!(
+        "Updated fuzzy-ls from {} to {}.",
+        current_version, release.version
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_parsing() {
+        assert!(parse_version("v1.10.0") > parse_version("1.9.3"));
+        assert_eq!(parse_version("1.2.0-rc.1"), vec![1, 2, 0]);
+        let feed = format!(
+            r#"{{"tag_name": "v1.3.0", "assets": [
+                {{"name": "{}", "browser_download_url": "https://example.com/bin"}},
+                {{"name": "SHA256SUMS", "browser_download_url": "https://example.com/sums"}}
+            ]}}"#,
+            binary_asset_name()
+        );
+        let release = parse_release(&feed).unwrap();
+        assert_eq!(release.version, "1.3.0");
+        assert_eq!(release.binary_url, "https://example.com/bin");
+        assert_eq!(release.checksums_url, "https://example.com/sums");
+        let checksums = "ABC123  fuzzy-ls-x86_64-linux\ndef456 *fuzzy-ls-aarch64-macos\n";
+        assert_eq!(
+            expected_checksum(checksums, "fuzzy-ls-x86_64-linux"),
+            Some("abc123".to_string())
+        );
+        assert_eq!(
+            expected_checksum(checksums, "fuzzy-ls-aarch64-macos"),
+            Some("def456".to_string())
+        );
+        assert_eq!(
+            expected_checksum(checksums, "fuzzy-ls-x86-windows.exe"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_replace_executable() {
+        let directory = env::temp_dir().join(format!("ffs-update-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let current = directory.join("fuzzy-ls");
+        fs::write(&current, "old").unwrap();
+        // A leftover staged file is replaced rather than written through.
+        fs::write(current.with_extension("new"), "stale").unwrap();
+        let replaced = replace_executable(&current, b"new");
+        let contents = fs::read_to_string(&current).unwrap();
+        let staged = current.with_extension("new").exists();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(replaced, Ok(()));
+        assert_eq!(contents, "new");
+        assert!(!staged);
+    }
+}
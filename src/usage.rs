@@ -0,0 +1,164 @@
+use crate::export::{file_size, format_size};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// How many extensions and directories the summary lists, the largest first.
+pub const SUMMARY_TOP: usize = 10;
+
+/// The size and number of the results sharing an extension or a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageTotal {
+    /// The extension with its leading dot or the directory, e.g. `.snap` or `src/snapshots`.
+    pub label: String,
+    pub bytes: u64,
+    pub files: usize,
+}
+
+/// How much disk space the results take, in total and by extension and directory, for `--du`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub bytes: u64,
+    pub files: usize,
+    /// The totals of each extension, the largest first.
+    pub by_extension: Vec<UsageTotal>,
+    /// The totals of each directory holding results directly, the largest first.
+    pub by_directory: Vec<UsageTotal>,
+}
+
+impl DiskUsage {
+    /// Sums the sizes of the results. Results that aren't files, or can't be read, are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `potential_hits` - The results, as tuples of score, file name and full path.
+    ///
+    /// # Returns
+    ///
+    /// The disk usage of the results.
+    pub fn of(potential_hits: &[(u32, String, String)]) -> DiskUsage {
+        DiskUsage::from_sizes(
+            potential_hits
+                .iter()
+                .filter_map(|(_, _, full_path)| Some((full_path.as_str(), file_size(full_path)?))),
+        )
+    }
+
+    /// Sums the sizes of files, given with their full paths.
+    pub fn from_sizes<'a>(sizes: impl Iterator<Item = (&'a str, u64)>) -> DiskUsage {
+        let mut extensions: HashMap<String, UsageTotal> = HashMap::new();
+        let mut directories: HashMap<String, UsageTotal> = HashMap::new();
+        let mut usage = DiskUsage {
+            bytes: 0,
+            files: 0,
+            by_extension: Vec::new(),
+            by_directory: Vec::new(),
+        };
+        for (full_path, bytes) in sizes {
+            usage.bytes += bytes;
+            usage.files += 1;
+            let path = Path::new(full_path);
+            let extension = path.extension().map_or("(none)".to_string(), |extension| {
+                format!(".{}", extension.to_string_lossy())
+            });
+            let directory = path
+                .parent()
+                .map(|parent| parent.to_string_lossy())
+                .filter(|parent| !parent.is_empty())
+                .map_or(".".to_string(), |parent| {
+                    let parent = parent.strip_prefix("./").unwrap_or(&parent);
+                    parent.to_string()
+                });
+            for (totals, label) in [(&mut extensions, extension), (&mut directories, directory)] {
+                let total = totals.entry(label.clone()).or_insert(UsageTotal {
+                    label,
+                    bytes: 0,
+                    files: 0,
+                });
+                total.bytes += bytes;
+                total.files += 1;
+            }
+        }
+        usage.by_extension = largest_first(extensions);
+        usage.by_directory = largest_first(directories);
+        usage
+    }
+
+    /// Returns the lines of the summary: the total, then the largest extensions and directories.
+    ///
+    /// # Arguments
+    ///
+    /// * `top` - How many extensions and directories are listed.
+    pub fn summary(&self, top: usize) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{} in {} {}",
+            format_size(self.bytes),
+            self.files,
+            if self.files == 1 { "file" } else { "files" }
+        )];
+        for (title, totals) in [
+            ("By extension:", &self.by_extension),
+            ("By directory:", &self.by_directory),
+        ] {
+            lines.push(title.to_string());
+            for total in totals.iter().take(top) {
+                lines.push(format!(
+                    "  {} {} ({})",
+                    total.label,
+                    format_size(total.bytes),
+                    total.files
+                ));
+            }
+            if totals.len() > top {
+                lines.push(format!("  {} more", totals.len() - top));
+            }
+        }
+        lines
+    }
+}
+
+/// Orders totals by size, the largest first, and by label among equal sizes.
+fn largest_first(totals: HashMap<String, UsageTotal>) -> Vec<UsageTotal> {
+    let mut totals: Vec<UsageTotal> = totals.into_values().collect();
+    totals.sort_by(|total, other| {
+        other
+            .bytes
+            .cmp(&total.bytes)
+            .then_with(|| total.label.cmp(&other.label))
+    });
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_usage() {
+        let usage = DiskUsage::from_sizes(
+            [
+                ("./src/snapshots/a.snap", 3072),
+                ("./src/snapshots/b.snap", 1024),
+                ("./src/main.rs", 512),
+                ("./Makefile", 10),
+            ]
+            .into_iter(),
+        );
+        assert_eq!((usage.bytes, usage.files), (4618, 4));
+        assert_eq!(
+            usage.summary(2),
+            [
+                "4.5 KiB in 4 files",
+                "By extension:",
+                "  .snap 4.0 KiB (2)",
+                "  .rs 512 B (1)",
+                "  1 more",
+                "By directory:",
+                "  src/snapshots 4.0 KiB (2)",
+                "  src 512 B (1)",
+                "  1 more",
+            ]
+        );
+        assert_eq!(usage.by_directory[2].label, ".");
+        assert_eq!(usage.by_extension[2].label, "(none)");
+    }
+}
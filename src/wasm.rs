@@ -0,0 +1,72 @@
+use crate::search::{self, AlgorithmChoice};
+use wasm_bindgen::prelude::*;
+
+/// A candidate accepted by [`rank`].
+#[wasm_bindgen]
+pub struct RankedCandidate {
+    score: u32,
+    name: String,
+    path: String,
+}
+
+#[wasm_bindgen]
+impl RankedCandidate {
+    /// The fuzzy score of the candidate. Lower is better and `0` denotes an exact match.
+    #[wasm_bindgen(getter)]
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    /// The name the candidate was matched by: its file name without the final extension.
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// The candidate path as it was passed in.
+    #[wasm_bindgen(getter)]
+    pub fn path(&self) -> String {
+        self.path.clone()
+    }
+}
+
+/// Ranks candidate paths against a query exactly like the fuzzy-ls command line does.
+///
+/// # Arguments
+///
+/// * `query` - The search query string.
+/// * `paths` - The candidate paths.
+/// * `algorithm` - The scoring algorithm, `damerau-levenshtein` by default. Plugins are not supported.
+///
+/// # Returns
+///
+/// The candidates within the fuzzy threshold, best first, or an error for an unknown algorithm.
+#[wasm_bindgen]
+pub fn rank(
+    query: &str,
+    paths: Vec<String>,
+    algorithm: Option<String>,
+) -> Result<Vec<RankedCandidate>, JsError> {
+    let algorithm = match algorithm
+        .as_deref()
+        .unwrap_or("damerau-levenshtein")
+        .parse::<AlgorithmChoice>()
+        .map_err(|error| JsError::new(&error))?
+    {
+        AlgorithmChoice::Builtin(algorithm) => algorithm,
+        AlgorithmChoice::Plugin(_) => {
+            return Err(JsError::new(
+                "Scorer plugins are not supported in WebAssembly",
+            ))
+        }
+    };
+    let candidates = paths
+        .into_iter()
+        .map(|path| (search::candidate_name_of_path(&path), path))
+        .collect();
+    Ok(search::rank_candidates(query, candidates, &algorithm)
+        .map_err(|error| JsError::new(&error))?
+        .into_iter()
+        .map(|(score, name, path)| RankedCandidate { score, name, path })
+        .collect())
+}